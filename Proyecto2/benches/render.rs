@@ -0,0 +1,36 @@
+// Benchmarks del render sobre las escenas de `BenchScene`: la misma geometría en la
+// rejilla de vóxeles y como cubos en la BVH, para comparar los dos aceleradores
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use proyecto2::bench_scene::BenchScene;
+use proyecto2::framebuffer::Framebuffer;
+use proyecto2::render::render;
+use proyecto2::settings::RenderSettings;
+use proyecto2::{integrator, sampler};
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 32;
+
+fn render_bench_scenes(c: &mut Criterion) {
+    let integrator = integrator::from_name("whitted").unwrap();
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+
+    for blocks in [100, 1000, 5000] {
+        for as_objects in [false, true] {
+            let bench = BenchScene { blocks, seed: 0, as_objects };
+            let (scene, camera) = bench.build(RenderSettings::default());
+            let sampler = sampler::from_name("independent", scene.settings.samples_per_pixel, 0).unwrap();
+            let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+            let accelerator = if as_objects { "bvh" } else { "voxel_grid" };
+
+            group.bench_with_input(BenchmarkId::new(accelerator, blocks), &blocks, |b, _| {
+                b.iter(|| render(&mut framebuffer, &scene, &camera, integrator.as_ref(), sampler.as_ref()));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render_bench_scenes);
+criterion_main!(benches);
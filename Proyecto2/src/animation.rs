@@ -0,0 +1,183 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::fs;
+
+use crate::camera::Camera;
+use crate::scene::Scene;
+
+// Valores de un punto a lo largo del tiempo. Entre dos claves se interpola con una
+// spline de Catmull-Rom, que pasa por todas ellas sin esquinas; antes de la primera
+// y después de la última se queda quieto.
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    // Pares (segundos, posición) ordenados por tiempo
+    keys: Vec<(f32, Vec3)>,
+}
+
+impl Track {
+    pub fn new() -> Self {
+        Track { keys: Vec::new() }
+    }
+
+    pub fn add_key(&mut self, time: f32, value: Vec3) {
+        let index = self.keys.partition_point(|(t, _)| *t <= time);
+        self.keys.insert(index, (time, value));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |(time, _)| *time)
+    }
+
+    pub fn sample(&self, time: f32) -> Option<Vec3> {
+        let (first, last) = (self.keys.first()?, self.keys.last()?);
+        if time <= first.0 {
+            return Some(first.1);
+        }
+        if time >= last.0 {
+            return Some(last.1);
+        }
+
+        let next = self.keys.partition_point(|(t, _)| *t <= time);
+        let (t1, p1) = self.keys[next - 1];
+        let (t2, p2) = self.keys[next];
+        // En los extremos se repite la clave para que la curva empiece y acabe en ella
+        let p0 = self.keys.get(next.wrapping_sub(2)).map_or(p1, |(_, p)| *p);
+        let p3 = self.keys.get(next + 1).map_or(p2, |(_, p)| *p);
+
+        let s = if t2 > t1 { (time - t1) / (t2 - t1) } else { 1.0 };
+        let (s2, s3) = (s * s, s * s * s);
+        Some(0.5 * (p1 * 2.0
+            + (p2 - p0) * s
+            + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * s2
+            + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * s3))
+    }
+}
+
+// Recorrido de la cámara (ojo y centro) y de las luces de la escena a lo largo del
+// tiempo. Se describe en un archivo de texto, una clave por línea:
+//
+//   # comentario
+//   eye    segundos x y z
+//   center segundos x y z
+//   light  segundos índice x y z
+//   loop
+//
+// `índice` es la posición de la luz en `Scene::lights`. Con `loop` el último instante
+// equivale al primero, así que al exportar no se repite ese fotograma.
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    pub eye: Track,
+    pub center: Track,
+    pub lights: Vec<(usize, Track)>,
+    // Segundos que dura; al cargar un archivo es el de la última clave
+    pub duration: f32,
+    pub looping: bool,
+}
+
+impl Animation {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+        Self::parse(&source).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut animation = Animation::default();
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: &str| format!("línea {}: {}", number + 1, message);
+            let numbers = |values: &[&str]| -> Result<Vec<f32>, String> {
+                values.iter()
+                    .map(|v| v.parse::<f32>().map_err(|_| error(&format!("número inválido '{}'", v))))
+                    .collect()
+            };
+
+            match (tokens[0], tokens.len()) {
+                ("loop", 1) => animation.looping = true,
+                ("eye" | "center", 5) => {
+                    let v = numbers(&tokens[1..5])?;
+                    let track = if tokens[0] == "eye" { &mut animation.eye } else { &mut animation.center };
+                    track.add_key(v[0], Vec3::new(v[1], v[2], v[3]));
+                }
+                ("light", 6) => {
+                    let index = tokens[2].parse::<usize>().map_err(|_| error(&format!("índice de luz inválido '{}'", tokens[2])))?;
+                    let mut v = numbers(&tokens[1..6])?;
+                    v.remove(1);
+                    animation.light_track(index).add_key(v[0], Vec3::new(v[1], v[2], v[3]));
+                }
+                _ => return Err(error(&format!("comando inválido '{}'", line))),
+            }
+        }
+
+        animation.duration = animation.lights.iter()
+            .map(|(_, track)| track.duration())
+            .fold(animation.eye.duration().max(animation.center.duration()), f32::max);
+        if animation.duration <= 0.0 {
+            return Err("la animación necesita claves después del segundo 0".to_string());
+        }
+        Ok(animation)
+    }
+
+    // Vuelta completa alrededor del centro de `camera` en `seconds` segundos, a la
+    // altura y distancia actuales. Usa una clave cada 30 grados.
+    pub fn turntable(camera: &Camera, seconds: f32) -> Self {
+        const STEPS: usize = 12;
+        let mut animation = Animation { duration: seconds, looping: true, ..Animation::default() };
+        let offset = camera.eye - camera.center;
+        let (radius, angle) = (offset.x.hypot(offset.z), offset.z.atan2(offset.x));
+
+        // Una clave más a cada lado para que el principio y el final tengan la misma curva
+        for step in -1..=STEPS as i32 + 1 {
+            let fraction = step as f32 / STEPS as f32;
+            let yaw = angle + fraction * 2.0 * PI;
+            let eye = camera.center + Vec3::new(radius * yaw.cos(), offset.y, radius * yaw.sin());
+            animation.eye.add_key(fraction * seconds, eye);
+        }
+        animation.center.add_key(0.0, camera.center);
+        animation
+    }
+
+    fn light_track(&mut self, index: usize) -> &mut Track {
+        let position = match self.lights.iter().position(|(i, _)| *i == index) {
+            Some(position) => position,
+            None => {
+                self.lights.push((index, Track::new()));
+                self.lights.len() - 1
+            }
+        };
+        &mut self.lights[position].1
+    }
+
+    // Instante del fotograma `frame` de `frames` repartidos por toda la animación
+    pub fn frame_time(&self, frame: u32, frames: u32) -> f32 {
+        let steps = if self.looping { frames } else { frames.saturating_sub(1) };
+        if steps == 0 {
+            return 0.0;
+        }
+        self.duration * frame as f32 / steps as f32
+    }
+
+    // Coloca la cámara y las luces animadas en el instante `time`
+    pub fn apply(&self, time: f32, camera: &mut Camera, scene: &mut Scene) {
+        if let Some(eye) = self.eye.sample(time) {
+            camera.eye = eye;
+        }
+        if let Some(center) = self.center.sample(time) {
+            camera.center = center;
+        }
+        for (index, track) in &self.lights {
+            if let (Some(light), Some(position)) = (scene.lights.get_mut(*index), track.sample(time)) {
+                light.position = position;
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use minifb::Key;
+#[cfg(not(feature = "winit-backend"))]
+use minifb::{Window, WindowOptions};
+use crate::camera::Camera;
+use crate::clock::Clock;
+use crate::framebuffer::Framebuffer;
+use crate::input::{Input, InputState};
+use crate::inspector::Inspector;
+use crate::integrator::Integrator;
+use crate::pacing::FramePacer;
+use crate::render::{render, render_progressive};
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+use crate::terminal::Terminal;
+
+// Función que la aplicación llama en cada fotograma antes de renderizar, con el reloj
+// de la escena ya avanzado (`clock.delta` segundos desde el anterior). Si edita `scene.world` debe llamar a
+// `scene.rebuild_blocks`, y si mueve `scene.objects` a `scene.rebuild_bvh`.
+pub type UpdateFn = Box<dyn FnMut(&mut Scene, &Clock)>;
+
+// Bucle principal del visor: entrada, actualización de la escena y render en una
+// ventana (minifb, o winit con `winit-backend`) o en la terminal. Quien use la
+// biblioteca registra sus animaciones con `on_update` en lugar de escribir su propio bucle.
+pub struct App {
+    pub scene: Scene,
+    pub camera: Camera,
+    pub framebuffer: Framebuffer,
+    pub integrator: Box<dyn Integrator>,
+    pub sampler: Box<dyn Sampler>,
+    // Tamaño de la ventana en píxeles lógicos; el framebuffer se escala si no coincide
+    pub window_size: (usize, usize),
+    // Resolución del render a calidad completa en píxeles lógicos, independiente de la ventana
+    pub render_size: (usize, usize),
+    // Factor de escala de la pantalla (2 en Retina o 4K al 200 %). Sin fijarlo se
+    // detecta: winit lo da la ventana y con minifb se lee de GDK_SCALE o QT_SCALE_FACTOR
+    pub scale_factor: Option<f32>,
+    // Renderiza a la densidad real de la pantalla (`window_size` por el factor de
+    // escala) en lugar de estirar el framebuffer original
+    pub hidpi: bool,
+    // Con más de 1 el render se muestra con el mayor aumento entero que quepa en la
+    // ventana, centrado, para ver la baja resolución con píxeles grandes y nítidos en
+    // lugar de estirada
+    pub pixel_scale: usize,
+    // Divisor de la resolución mientras se mueve la cámara (1, 2, 4...); al quedarse quieta
+    // se vuelve a la resolución completa. Z lo dobla y Q lo reduce a la mitad en la ventana.
+    pub motion_scale: usize,
+    // Fotogramas por segundo como máximo; None para renderizar sin pausas
+    pub target_fps: Option<f32>,
+    // Modo progresivo: cada fotograma suma una muestra por píxel hasta este número, y se
+    // empieza de nuevo cuando cambian la cámara, las luces, los grupos o la geometría
+    pub progressive: Option<u32>,
+    // Inspector de píxeles bajo el ratón en la ventana; None lo desactiva
+    pub inspector: Option<Inspector>,
+    updates: Vec<UpdateFn>,
+    // Resolución completa en píxeles físicos: `render_size`, o ajustada a la pantalla con `hidpi`
+    full_size: (usize, usize),
+    // Cámara del fotograma anterior y segundos que lleva sin moverse
+    last_camera: Vec<f32>,
+    still_time: f32,
+    // Estado de la vista con el que se acumuló el render progresivo
+    last_view: Vec<f32>,
+}
+
+impl App {
+    pub fn new(scene: Scene, camera: Camera, integrator: Box<dyn Integrator>, sampler: Box<dyn Sampler>, width: usize, height: usize) -> Self {
+        App {
+            scene,
+            camera,
+            framebuffer: Framebuffer::new(width, height),
+            integrator,
+            sampler,
+            window_size: (width, height),
+            render_size: (width, height),
+            scale_factor: None,
+            hidpi: false,
+            pixel_scale: 1,
+            motion_scale: 1,
+            target_fps: Some(60.0),
+            progressive: None,
+            inspector: None,
+            updates: Vec::new(),
+            full_size: (width, height),
+            last_camera: Vec::new(),
+            still_time: 0.0,
+            last_view: Vec::new(),
+        }
+    }
+
+    // Registra una actualización por fotograma; se llaman en el orden en que se registran
+    pub fn on_update(&mut self, update: impl FnMut(&mut Scene, &Clock) + 'static) {
+        self.updates.push(Box::new(update));
+    }
+
+    // Avanza el reloj de la escena `dt` segundos reales y renderiza el fotograma
+    pub fn frame(&mut self, dt: f32) {
+        self.scene.clock.tick(dt);
+        let clock = self.scene.clock;
+        for update in self.updates.iter_mut() {
+            update(&mut self.scene, &clock);
+        }
+        self.scene.update_sun(clock.delta);
+        self.scene.update_weather(clock.delta);
+        self.scene.update_torches(clock.elapsed);
+        let probes_changed = self.scene.update_light_probes();
+        self.update_resolution(dt);
+        self.scene.prepare_view(&self.camera, self.framebuffer.height);
+
+        let Some(max_frames) = self.progressive else {
+            render(&mut self.framebuffer, &self.scene, &self.camera, self.integrator.as_ref(), self.sampler.as_ref());
+            return;
+        };
+
+        let view = view_signature(&self.camera, &self.scene);
+        if view != self.last_view || probes_changed {
+            self.framebuffer.reset_accumulation();
+            self.last_view = view;
+        }
+        // Con la imagen ya refinada no se vuelve a trazar, y la ventana sigue respondiendo
+        if self.framebuffer.accumulated_frames < max_frames {
+            render_progressive(&mut self.framebuffer, &self.scene, &self.camera, self.integrator.as_ref(), self.sampler.as_ref());
+        }
+    }
+
+    // Elige la resolución del fotograma: reducida por `motion_scale` mientras la cámara se
+    // mueve y completa tras `IDLE_DELAY` segundos quieta. Al cambiar de tamaño el
+    // framebuffer se crea de nuevo, así que el render progresivo empieza otra vez.
+    fn update_resolution(&mut self, dt: f32) {
+        let camera = camera_signature(&self.camera);
+        if camera != self.last_camera {
+            self.last_camera = camera;
+            self.still_time = 0.0;
+        } else {
+            self.still_time += dt;
+        }
+        let divisor = if self.still_time < IDLE_DELAY { self.motion_scale.max(1) } else { 1 };
+        let (width, height) = ((self.full_size.0 / divisor).max(1), (self.full_size.1 / divisor).max(1));
+        if (width, height) != (self.framebuffer.width, self.framebuffer.height) {
+            self.framebuffer = Framebuffer::new(width, height);
+        }
+    }
+
+    // Z reduce a la mitad la resolución en movimiento y Q la dobla, hasta la completa
+    pub fn resolution_keys(&mut self, input: &dyn Input) {
+        if input.is_key_pressed(Key::Z) {
+            self.motion_scale = (self.motion_scale * 2).min(MAX_MOTION_SCALE);
+        } else if input.is_key_pressed(Key::Q) {
+            self.motion_scale = (self.motion_scale / 2).max(1);
+        } else {
+            return;
+        }
+        let (width, height) = self.full_size;
+        eprintln!("Resolución en movimiento: {}x{}", (width / self.motion_scale).max(1), (height / self.motion_scale).max(1));
+    }
+
+    // Rectángulo (x, y, ancho, alto) de una ventana de `width` x `height` píxeles donde
+    // se muestra la imagen: toda la ventana, o el mayor aumento entero de la resolución
+    // completa con `pixel_scale`. No cambia al bajar la resolución en movimiento.
+    pub fn display_rect(&self, (width, height): (usize, usize)) -> (usize, usize, usize, usize) {
+        if self.pixel_scale <= 1 {
+            return (0, 0, width, height);
+        }
+        let (full_width, full_height) = self.full_size;
+        let factor = (width / full_width.max(1)).min(height / full_height.max(1)).max(1);
+        let (shown_width, shown_height) = (full_width * factor, full_height * factor);
+        (width.saturating_sub(shown_width) / 2, height.saturating_sub(shown_height) / 2, shown_width, shown_height)
+    }
+
+    // Punto de la ventana en píxeles de la resolución completa, sea cual sea la del
+    // fotograma, para que el ratón gire la cámara igual a cualquier resolución
+    pub fn window_to_pixels(&self, window: (usize, usize), x: f32, y: f32) -> (f32, f32) {
+        let (rect_x, rect_y, rect_width, rect_height) = self.display_rect(window);
+        (
+            (x - rect_x as f32) * self.full_size.0 as f32 / rect_width.max(1) as f32,
+            (y - rect_y as f32) * self.full_size.1 as f32 / rect_height.max(1) as f32,
+        )
+    }
+
+    // Pasa al inspector la entrada del fotograma ya renderizado, si está activo. Con la
+    // resolución reducida la posición del ratón no corresponde al framebuffer y se espera.
+    pub fn inspect(&mut self, input: &dyn Input) {
+        if (self.framebuffer.width, self.framebuffer.height) != self.full_size {
+            return;
+        }
+        if let Some(inspector) = &mut self.inspector {
+            inspector.update(input, &self.framebuffer, &self.scene, &self.camera, self.sampler.as_ref());
+        }
+    }
+
+    // Ajusta el framebuffer a la escala de la pantalla si `hidpi` está activo. El
+    // framebuffer nuevo empieza sin acumulación, así que el render progresivo se reinicia.
+    pub fn apply_scale_factor(&mut self, scale: f32) {
+        if !self.hidpi {
+            return;
+        }
+        self.full_size = physical_size(self.render_size, scale);
+        let (width, height) = self.full_size;
+        if (width, height) != (self.framebuffer.width, self.framebuffer.height) {
+            self.framebuffer = Framebuffer::new(width, height);
+        }
+    }
+
+    // Bucle en una ventana hasta cerrarla o pulsar Escape; `input` recibe el estado
+    // de teclado y ratón de cada fotograma. Con la feature `winit-backend` la ventana
+    // es de winit + softbuffer en lugar de minifb.
+    #[cfg(feature = "winit-backend")]
+    pub fn run_window(&mut self, title: &str, input: impl FnMut(&dyn Input, &mut Camera, &mut Scene)) {
+        crate::winit_window::run(self, title, input);
+    }
+
+    #[cfg(not(feature = "winit-backend"))]
+    pub fn run_window(&mut self, title: &str, mut input: impl FnMut(&dyn Input, &mut Camera, &mut Scene)) {
+        // minifb no conoce la escala de la pantalla: se abre la ventana ya en píxeles físicos
+        let scale = self.scale_factor.unwrap_or_else(scale_factor_from_env);
+        self.apply_scale_factor(scale);
+        let (width, height) = physical_size(self.window_size, scale);
+        let mut window = Window::new(title, width, height, WindowOptions::default()).unwrap();
+        // El ritmo lo lleva `FramePacer`; minifb no debe añadir su propia espera
+        window.set_target_fps(0);
+        let mut pacer = FramePacer::new(self.target_fps);
+        let mut state = InputState::new();
+        let mut scaled = Vec::new();
+
+        while window.is_open() && !window.is_key_down(Key::Escape) {
+            let dt = pacer.begin_frame();
+            let size = window.get_size();
+            state = InputState::from_window(&window, &state, |x, y| self.window_to_pixels(size, x, y));
+            state.frame_time = dt;
+            input(&state, &mut self.camera, &mut self.scene);
+            self.resolution_keys(&state);
+            self.frame(dt);
+            self.inspect(&state);
+
+            let (width, height) = size;
+            scaled.resize(width * height, 0);
+            self.framebuffer.blit_scaled(&mut scaled, width, height, self.display_rect(size));
+            window.update_with_buffer(&scaled, width, height).unwrap();
+            pacer.wait();
+        }
+    }
+
+    // Igual que `run_window` pero dibujando en la terminal, para sesiones sin servidor
+    // gráfico; ahí no hay ratón ni teclas mantenidas, solo pulsaciones
+    pub fn run_terminal(&mut self, mut input: impl FnMut(&dyn Input, &mut Camera, &mut Scene)) {
+        let terminal = Terminal::new();
+        // La terminal dibuja cada píxel en una celda: no se puede bajar la resolución al moverse
+        self.motion_scale = 1;
+        let mut pacer = FramePacer::new(self.target_fps);
+
+        loop {
+            let dt = pacer.begin_frame();
+            let keys = terminal.pressed_keys();
+            if keys.contains(&Key::Escape) {
+                break;
+            }
+            let state = InputState { frame_time: dt, ..InputState::from_keys(keys) };
+            input(&state, &mut self.camera, &mut self.scene);
+            self.frame(dt);
+
+            terminal.present(&self.framebuffer);
+            pacer.wait();
+        }
+    }
+}
+
+// Segundos sin mover la cámara tras los que se renderiza a la resolución completa
+const IDLE_DELAY: f32 = 0.25;
+// Mayor divisor de la resolución en movimiento que se alcanza con Z
+const MAX_MOTION_SCALE: usize = 16;
+
+// Tamaño en píxeles físicos de un tamaño lógico
+pub fn physical_size((width, height): (usize, usize), scale: f32) -> (usize, usize) {
+    let scale = scale.max(0.1);
+    (((width as f32 * scale).round() as usize).max(1), ((height as f32 * scale).round() as usize).max(1))
+}
+
+// Escala que piden los entornos de escritorio de Linux por variable de entorno
+#[cfg(not(feature = "winit-backend"))]
+fn scale_factor_from_env() -> f32 {
+    ["GDK_SCALE", "QT_SCALE_FACTOR"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok()?.parse::<f32>().ok())
+        .filter(|scale| *scale > 0.0)
+        .unwrap_or(1.0)
+}
+
+// Posición, orientación y campo de visión de la cámara, para saber si se ha movido
+fn camera_signature(camera: &Camera) -> Vec<f32> {
+    let mut view = vec![camera.fov];
+    view.extend(camera.eye.iter().chain(camera.center.iter()).chain(camera.up.iter()));
+    view
+}
+
+// Lo que cambia la imagen entre fotogramas: cámara, luces, grupos visibles, geometría
+// y los ajustes que se cambian con el teclado
+fn view_signature(camera: &Camera, scene: &Scene) -> Vec<f32> {
+    let mut view = camera_signature(camera);
+    view.push(scene.revision() as f32);
+    for (index, light) in scene.lights.iter().enumerate() {
+        view.extend(light.position.iter());
+        view.push(scene.steady_intensity(index));
+    }
+    view.extend(scene.groups.iter().map(|group| if group.visible { 1.0 } else { 0.0 }));
+    let settings = &scene.settings;
+    view.extend([settings.max_depth as f32, settings.samples_per_pixel as f32, settings.shadow_samples.unwrap_or(0) as f32]);
+    view.push(if settings.debug_overlay { 1.0 } else { 0.0 });
+    view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrator::Whitted;
+    use crate::material::Material;
+    use crate::sampler::IndependentSampler;
+    use crate::settings::RenderSettings;
+    use crate::torch::Torch;
+    use nalgebra_glm::Vec3;
+    use std::sync::Arc;
+
+    #[test]
+    fn flickering_torch_keeps_accumulating_a_still_view() {
+        let mut scene = Scene::new(Vec::new(), Vec::new(), RenderSettings::default());
+        let mut torch = Torch::new(Vec3::zeros(), Arc::new(Material::default()));
+        torch.flicker = 1.0;
+        scene.add_torch(torch);
+        let camera = Camera::new(Vec3::new(0.0, 1.0, 3.0), Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 1.0, 0.0));
+
+        let mut app = App::new(scene, camera, Box::new(Whitted), Box::new(IndependentSampler::new(0)), 4, 4);
+        app.progressive = Some(16);
+        let mut intensities = Vec::new();
+        for _ in 0..5 {
+            app.frame(0.1);
+            intensities.push(app.scene.lights[0].intensity);
+        }
+
+        // El brillo cambia, pero la vista es la misma y las muestras se siguen sumando
+        assert!(intensities.windows(2).any(|pair| pair[0] != pair[1]));
+        assert_eq!(app.framebuffer.accumulated_frames, 5);
+    }
+}
@@ -0,0 +1,118 @@
+use nalgebra_glm::{IVec3, Vec3};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::light::{Light, ShadowModel};
+use crate::material::Material;
+use crate::rng::Rng;
+use crate::scene::{Scene, EMISSIVE_LIGHT_RANGE};
+use crate::settings::RenderSettings;
+use crate::sky::FlatSky;
+use crate::texture::Texture;
+use crate::world::{BlockEdit, BlockId};
+
+// Fracción de las celdas del volumen que se llenan; con menos los rayos cruzan más
+// celdas vacías y con más casi todos se paran en la primera capa
+const FILL: f32 = 0.3;
+// Cada cuántos bloques uno es emisivo (y por tanto una luz más)
+const EMISSIVE_EVERY: usize = 50;
+
+// Escena de prueba de rendimiento: `blocks` bloques repartidos al azar dentro de un
+// cubo, con materiales mezclados (mates, con textura, espejo, cristal y alguno
+// emisivo), una luz puntual y una cámara que lo ve entero. La misma semilla da
+// siempre la misma escena, así que sirve para comparar versiones del render.
+#[derive(Debug, Clone)]
+pub struct BenchScene {
+    pub blocks: usize,
+    pub seed: u64,
+    // Coloca los bloques como cubos sueltos en la BVH en lugar de en la rejilla de
+    // vóxeles, para medir los dos aceleradores con la misma geometría
+    pub as_objects: bool,
+}
+
+impl BenchScene {
+    pub fn new(blocks: usize) -> Self {
+        BenchScene { blocks, seed: 0, as_objects: false }
+    }
+
+    // Lado del cubo en bloques para que quepan `blocks` con la densidad de `FILL`
+    pub fn side(&self) -> i32 {
+        ((self.blocks as f32 / FILL).cbrt().ceil() as i32).max(1)
+    }
+
+    // Posiciones distintas de los bloques, en el orden en que se generan
+    pub fn positions(&self) -> Vec<IVec3> {
+        let side = self.side();
+        let capacity = (side * side * side) as usize;
+        let mut rng = Rng::new(self.seed);
+        let mut taken = HashSet::new();
+        let mut positions = Vec::with_capacity(self.blocks.min(capacity));
+        while positions.len() < self.blocks.min(capacity) {
+            let coordinate = |rng: &mut Rng| (rng.next_u32() % side as u32) as i32 - side / 2;
+            let position = IVec3::new(coordinate(&mut rng), coordinate(&mut rng), coordinate(&mut rng));
+            if taken.insert(position) {
+                positions.push(position);
+            }
+        }
+        positions
+    }
+
+    pub fn build(&self, settings: RenderSettings) -> (Scene, Camera) {
+        let mut scene = Scene::new(Vec::new(), Vec::new(), settings);
+        let materials = materials();
+        let ids: Vec<BlockId> = materials.iter()
+            .enumerate()
+            .map(|(index, material)| scene.world.palette.add(&format!("bench_{}", index), material.clone()))
+            .collect();
+
+        for (index, position) in self.positions().into_iter().enumerate() {
+            // El emisivo es el último material; el resto se reparte por turnos
+            let material = if index % EMISSIVE_EVERY == EMISSIVE_EVERY - 1 { materials.len() - 1 } else { index % (materials.len() - 1) };
+            if self.as_objects {
+                let min = Vec3::new(position.x as f32, position.y as f32, position.z as f32);
+                scene.add_cube(min, min + Vec3::repeat(1.0), Arc::new(materials[material].clone()));
+                // Los cubos sueltos no iluminan solos: la misma luz que `rebuild_blocks`
+                // da a un bloque emisivo, para que las dos versiones tengan igual coste
+                let emissive = &materials[material];
+                if emissive.emission_intensity > 0.0 {
+                    let mut light = Light::new(min + Vec3::repeat(0.5), emissive.emission, emissive.emission_intensity);
+                    light.shadow = ShadowModel::Hard;
+                    light.range = Some(EMISSIVE_LIGHT_RANGE * emissive.emission_intensity.sqrt());
+                    scene.add_light(light);
+                }
+            } else {
+                scene.world.set_block(position, ids[material]);
+            }
+        }
+        scene.rebuild_blocks();
+
+        let side = self.side() as f32;
+        let mut light = Light::new(Vec3::new(side, side * 1.5, -side), Color::new(255, 250, 240), 1.0);
+        light.shadow = ShadowModel::Hard;
+        scene.add_light(light);
+        scene.sky = Box::new(FlatSky::new(Color::new(150, 180, 220)));
+
+        let camera = Camera::new(Vec3::new(side * 0.8, side * 0.9, -side * 1.6), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        (scene, camera)
+    }
+}
+
+// Mates de tres colores, tablero con textura, espejo, cristal y, el último, emisivo
+fn materials() -> Vec<Material> {
+    let matte = |r, g, b| Material::new(Color::new(r, g, b), 10.0, [0.8, 0.2, 0.0, 0.0], 0.0, None);
+    let checker = Texture::checkerboard(Color::new(230, 230, 230), Color::new(40, 40, 40));
+    let mut emissive = matte(255, 210, 120);
+    emissive.emission = Color::new(255, 210, 120);
+    emissive.emission_intensity = 0.5;
+    vec![
+        matte(200, 60, 50),
+        matte(60, 160, 80),
+        matte(70, 90, 200),
+        Material::new(Color::black(), 10.0, [0.8, 0.2, 0.0, 0.0], 0.0, Some(checker)),
+        Material::new(Color::new(220, 220, 230), 120.0, [0.2, 0.6, 0.7, 0.0], 0.0, None),
+        Material::new(Color::new(200, 230, 255), 120.0, [0.1, 0.5, 0.3, 0.8], 1.5, None),
+        emissive,
+    ]
+}
@@ -0,0 +1,123 @@
+use nalgebra_glm::Vec3;
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::scene::DEFAULT_GROUP;
+use crate::texture::AlphaMask;
+use std::sync::Arc;
+
+// Sprite plano que siempre mira hacia la cámara: objetos sueltos, llamas de antorchas
+// o marcadores. Es un rectángulo de `size` centrado en `position` que `face` reorienta
+// hacia el ojo en cada fotograma; `alpha` recorta la silueta de la imagen.
+pub struct Billboard {
+    pub position: Vec3,
+    // Ancho y alto en unidades del mundo
+    pub size: (f32, f32),
+    pub material: Arc<Material>,
+    pub alpha: Option<Arc<AlphaMask>>,
+    // Gira solo alrededor del eje Y y se mantiene vertical, como una llama o un árbol;
+    // si es falso se inclina también para mirar de frente desde arriba o abajo
+    pub upright: bool,
+    // Por defecto no proyecta sombra: visto desde la luz el quad no tiene la orientación de la cámara
+    pub casts_shadows: bool,
+    pub visible_to_camera: bool, // Si es falso, los rayos primarios lo ignoran
+    pub group: usize, // Grupo de la escena al que pertenece
+    // Base del quad según la última llamada a `face`
+    right: Vec3,
+    up: Vec3,
+    normal: Vec3,
+}
+
+impl Billboard {
+    // Empieza mirando hacia -Z, hacia donde está la cámara inicial del diorama
+    pub fn new(position: Vec3, size: (f32, f32), material: Arc<Material>) -> Self {
+        Billboard {
+            position,
+            size,
+            material,
+            alpha: None,
+            upright: true,
+            casts_shadows: false,
+            visible_to_camera: true,
+            group: DEFAULT_GROUP,
+            right: Vec3::new(-1.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, -1.0),
+        }
+    }
+
+    // Orienta el quad hacia `eye`
+    pub fn face(&mut self, eye: &Vec3) {
+        let mut to_eye = eye - self.position;
+        if self.upright {
+            to_eye.y = 0.0;
+        }
+        if to_eye.magnitude() < 1e-6 {
+            return;
+        }
+        self.normal = to_eye.normalize();
+
+        // Con el ojo justo encima o debajo se usa Z como referencia en lugar de Y
+        let world_up = if self.normal.y.abs() > 0.999 { Vec3::new(0.0, 0.0, 1.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+        self.right = world_up.cross(&self.normal).normalize();
+        self.up = self.normal.cross(&self.right);
+    }
+}
+
+impl RayIntersect for Billboard {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let denominator = self.normal.dot(direction);
+        if denominator.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+        let distance = (self.position - origin).dot(&self.normal) / denominator;
+        if distance <= 0.0 {
+            return Intersect::empty();
+        }
+
+        // La imagen va de izquierda a derecha con `right` y de arriba abajo contra `up`
+        let point = origin + direction * distance;
+        let local = point - self.position;
+        let u = local.dot(&self.right) / self.size.0 + 0.5;
+        let v = 0.5 - local.dot(&self.up) / self.size.1;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return Intersect::empty();
+        }
+        if self.alpha.as_ref().is_some_and(|alpha| !alpha.covers(u, v)) {
+            return Intersect::empty();
+        }
+
+        // Es una lámina sin interior: la normal mira hacia el lado del que llega el rayo
+        let normal = if denominator > 0.0 { -self.normal } else { self.normal };
+        let mut intersect = Intersect::new(point, normal, distance, self.material.clone());
+        intersect.uv = Some((u, v));
+        intersect.tangent = Some((self.right, -self.up));
+        intersect
+    }
+
+    // Caja que contiene el quad en cualquier orientación
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let radius = (self.size.0 * self.size.0 + self.size.1 * self.size.1).sqrt() * 0.5;
+        let extent = Vec3::repeat(radius);
+        (self.position - extent, self.position + extent)
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Arc<Material> {
+        &mut self.material
+    }
+}
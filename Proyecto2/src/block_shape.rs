@@ -0,0 +1,88 @@
+use nalgebra_glm::Vec3;
+
+use crate::world::Axis;
+
+// Grosor de una lámina de cristal, en fracción de bloque (2 de 16 píxeles)
+const PANE_THICKNESS: f32 = 2.0 / 16.0;
+// Lado de un poste de cerca (4 de 16 píxeles)
+const POST_WIDTH: f32 = 4.0 / 16.0;
+
+// Forma de un tipo de bloque dentro de su celda. Los bloques llenos ocupan la celda
+// entera; los finos son una caja más pequeña centrada en ella que la rejilla interseca
+// aparte, con las UV de la celda completa para que la textura no se estire ni se encoja.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlockShape {
+    #[default]
+    Full,
+    // Lámina perpendicular al eje, como un cristal de ventana
+    Pane(Axis),
+    // Columna vertical, como un poste de cerca
+    Post,
+}
+
+impl BlockShape {
+    // Nombre en los archivos de escena: "full", "pane_x", "pane_y", "pane_z" o "post"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(BlockShape::Full),
+            "post" => Some(BlockShape::Post),
+            _ => name.strip_prefix("pane_").and_then(Axis::from_name).map(BlockShape::Pane),
+        }
+    }
+
+    pub fn is_full(self) -> bool {
+        self == BlockShape::Full
+    }
+
+    // Esquinas de la caja que ocupa, relativas a la esquina mínima de la celda
+    pub fn bounds(self) -> (Vec3, Vec3) {
+        let (min, max) = match self {
+            BlockShape::Full => return (Vec3::zeros(), Vec3::repeat(1.0)),
+            BlockShape::Pane(axis) => {
+                let mut half = Vec3::repeat(0.5);
+                half[axis.index()] = PANE_THICKNESS * 0.5;
+                (Vec3::repeat(0.5) - half, Vec3::repeat(0.5) + half)
+            }
+            BlockShape::Post => {
+                let half = POST_WIDTH * 0.5;
+                (Vec3::new(0.5 - half, 0.0, 0.5 - half), Vec3::new(0.5 + half, 1.0, 0.5 + half))
+            }
+        };
+        (min, max)
+    }
+
+    // Distancia al primer impacto de un rayo dado en coordenadas de la celda, eje de la
+    // cara golpeada y si el origen estaba dentro (entonces es la cara de salida). La cara
+    // sale del eje de la caja por el que entra el rayo, sin comparar el punto con las
+    // caras, así que funciona igual con láminas de cualquier grosor.
+    pub fn intersect(self, origin: &Vec3, direction: &Vec3) -> Option<(f32, usize, bool)> {
+        let (min, max) = self.bounds();
+        let (mut tmin, mut tmax) = (f32::NEG_INFINITY, f32::INFINITY);
+        let (mut entry_axis, mut exit_axis) = (0, 0);
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min[axis] - origin[axis]) / direction[axis];
+            let t1 = (max[axis] - origin[axis]) / direction[axis];
+            if t0.min(t1) > tmin {
+                tmin = t0.min(t1);
+                entry_axis = axis;
+            }
+            if t0.max(t1) < tmax {
+                tmax = t0.max(t1);
+                exit_axis = axis;
+            }
+            if tmin > tmax {
+                return None;
+            }
+        }
+        if tmax < 0.0 {
+            return None;
+        }
+        Some(if tmin < 0.0 { (tmax, exit_axis, true) } else { (tmin, entry_axis, false) })
+    }
+}
@@ -0,0 +1,133 @@
+use nalgebra_glm::Vec3;
+
+// Objetos por hoja antes de dejar de dividir
+const MAX_LEAF_SIZE: usize = 4;
+
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    // En las hojas, rango [start, start + count) de `indices`; en los nodos
+    // internos `count` es 0, el hijo izquierdo es el siguiente nodo y `right` el derecho
+    start: usize,
+    count: usize,
+    right: usize,
+}
+
+// Jerarquía de cajas alineadas a los ejes sobre una lista de objetos, para que
+// cada rayo solo pruebe los objetos cuyas cajas atraviesa
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+}
+
+impl Default for Bvh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bvh {
+    pub fn new() -> Self {
+        Bvh { nodes: Vec::new(), indices: Vec::new() }
+    }
+
+    // Construye el árbol a partir de la caja (min, max) de cada objeto;
+    // los índices devueltos al recorrerlo son posiciones en `bounds`
+    pub fn build(bounds: &[(Vec3, Vec3)]) -> Self {
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(bounds.len() * 2),
+            indices: (0..bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build_node(bounds, 0, bounds.len());
+        }
+        bvh
+    }
+
+    fn build_node(&mut self, bounds: &[(Vec3, Vec3)], start: usize, end: usize) -> usize {
+        let items = &mut self.indices[start..end];
+
+        let mut min = Vec3::repeat(f32::INFINITY);
+        let mut max = Vec3::repeat(f32::NEG_INFINITY);
+        let mut centroid_min = Vec3::repeat(f32::INFINITY);
+        let mut centroid_max = Vec3::repeat(f32::NEG_INFINITY);
+        for &index in items.iter() {
+            let (object_min, object_max) = bounds[index];
+            min = min.inf(&object_min);
+            max = max.sup(&object_max);
+            let centroid = (object_min + object_max) * 0.5;
+            centroid_min = centroid_min.inf(&centroid);
+            centroid_max = centroid_max.sup(&centroid);
+        }
+
+        let node_index = self.nodes.len();
+        self.nodes.push(Node { min, max, start, count: end - start, right: 0 });
+
+        let extent = centroid_max - centroid_min;
+        if end - start <= MAX_LEAF_SIZE || extent.max() <= 0.0 {
+            return node_index;
+        }
+
+        // División por la mediana del eje más largo de los centroides
+        let axis = extent.imax();
+        let centroid = |index: &usize| bounds[*index].0[axis] + bounds[*index].1[axis];
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |a, b| centroid(a).total_cmp(&centroid(b)));
+
+        self.nodes[node_index].count = 0;
+        self.build_node(bounds, start, start + middle);
+        let right = self.build_node(bounds, start + middle, end);
+        self.nodes[node_index].right = right;
+        node_index
+    }
+
+    // Recorre las hojas que atraviesa el rayo hasta `max_distance`. `visit` recibe el
+    // índice de cada objeto candidato y devuelve la distancia si lo golpea, lo que
+    // permite descartar las cajas más lejanas que el impacto más cercano encontrado.
+    pub fn traverse(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32, mut visit: impl FnMut(usize) -> Option<f32>) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inverse = Vec3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
+        let mut closest = max_distance;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !hits_box(&node.min, &node.max, ray_origin, &inverse, closest) {
+                continue;
+            }
+
+            if node.count > 0 {
+                for &index in &self.indices[node.start..node.start + node.count] {
+                    if let Some(distance) = visit(index) {
+                        closest = closest.min(distance);
+                    }
+                }
+            } else {
+                stack.push(node.right);
+                stack.push(node_index + 1);
+            }
+        }
+    }
+}
+
+fn hits_box(min: &Vec3, max: &Vec3, origin: &Vec3, inverse: &Vec3, max_distance: f32) -> bool {
+    let mut t_near = 0.0f32;
+    let mut t_far = max_distance;
+    for axis in 0..3 {
+        // Rayo paralelo a las caras de este eje: solo importa si el origen está entre ellas
+        if inverse[axis].is_infinite() {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return false;
+            }
+            continue;
+        }
+        let t0 = (min[axis] - origin[axis]) * inverse[axis];
+        let t1 = (max[axis] - origin[axis]) * inverse[axis];
+        t_near = t_near.max(t0.min(t1));
+        t_far = t_far.min(t0.max(t1));
+    }
+    t_near <= t_far
+}
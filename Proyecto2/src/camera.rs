@@ -2,10 +2,21 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Cómo responde la cámara a los controles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    // Gira alrededor de `center` y se acerca o aleja de él
+    Orbit,
+    // Primera persona: el ratón gira la vista y las teclas mueven el ojo
+    Fly,
+}
+
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
-    pub up: Vec3
+    pub up: Vec3,
+    pub fov: f32, // Campo de visión vertical en radianes
+    pub mode: CameraMode,
 }
 
 impl Camera {
@@ -13,7 +24,30 @@ impl Camera {
         Camera {
             eye,
             center,
-            up
+            up,
+            fov: PI / 3.0,
+            mode: CameraMode::Orbit,
+        }
+    }
+
+    // Cámara en el instante `t` (de 0 a 1) de un dolly zoom hacia `target`: el campo de
+    // visión pasa suavemente a `end_fov` mientras la cámara avanza o retrocede para que
+    // el plano del objetivo conserve su tamaño en pantalla
+    pub fn dolly_zoom(&self, target: &Vec3, end_fov: f32, t: f32) -> Camera {
+        let t = t.clamp(0.0, 1.0);
+        let eased = t * t * (3.0 - 2.0 * t);
+        let fov = self.fov + (end_fov - self.fov) * eased;
+
+        // El ancho visible a la distancia d es 2·d·tan(fov/2); se mantiene constante
+        let offset = self.eye - target;
+        let distance = offset.magnitude() * (self.fov * 0.5).tan() / (fov * 0.5).tan();
+
+        Camera {
+            eye: target + offset.normalize() * distance,
+            center: *target,
+            up: self.up,
+            fov,
+            mode: self.mode,
         }
     }
 
@@ -33,7 +67,26 @@ impl Camera {
 
         let rotated = vector.x * right + vector.y * up - vector.z * forward;
 
-        return rotated.normalize();
+        rotated.normalize()
+    }
+
+    // Punto de la imagen de `width` x `height` píxeles en el que se ve `point`, o None si
+    // queda detrás de la cámara. Es la inversa de la dirección de los rayos primarios.
+    pub fn project(&self, point: &Vec3, width: usize, height: usize) -> Option<(f32, f32)> {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        let offset = point - self.eye;
+        let depth = offset.dot(&forward);
+        if depth <= 1e-6 {
+            return None;
+        }
+        let (width, height) = (width as f32, height as f32);
+        let perspective_scale = (self.fov * 0.5).tan();
+        let screen_x = offset.dot(&right) / (depth * perspective_scale * width / height);
+        let screen_y = offset.dot(&up) / (depth * perspective_scale);
+        Some(((screen_x + 1.0) * width * 0.5, (1.0 - screen_y) * height * 0.5))
     }
 
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
@@ -55,4 +108,39 @@ impl Camera {
 
         self.eye = new_eye;
     }
-}
\ No newline at end of file
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+    }
+
+    // Gira la vista alrededor del ojo, como al mover la cabeza: `center` se desplaza y
+    // `eye` se queda quieto. Un yaw positivo gira a la derecha y un pitch positivo hacia arriba.
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+
+        let yaw = view.z.atan2(view.x) + delta_yaw;
+        let pitch = ((view.y / distance).asin() + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+
+        self.center = self.eye + distance * Vec3::new(yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+    }
+
+    // Mueve el ojo y el centro juntos. `movement` va en ejes de la cámara: x a la derecha,
+    // y hacia arriba del mundo y z hacia delante sin inclinarse, para no hundirse al mirar abajo
+    pub fn fly(&mut self, movement: &Vec3) {
+        let view = self.center - self.eye;
+        let forward = Vec3::new(view.x, 0.0, view.z);
+        if forward.magnitude() < 1e-6 {
+            return;
+        }
+        let forward = forward.normalize();
+        let right = forward.cross(&self.up).normalize();
+
+        let offset = right * movement.x + self.up * movement.y + forward * movement.z;
+        self.eye += offset;
+        self.center += offset;
+    }
+}
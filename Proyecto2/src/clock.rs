@@ -0,0 +1,43 @@
+// Reloj de la escena: todas las animaciones (agua, partículas, ciclo de día...) leen
+// de aquí el tiempo en lugar de consultar el reloj del sistema, así que se pausan,
+// aceleran o exportan fotograma a fotograma de la misma forma.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    // Segundos de escena desde el inicio
+    pub elapsed: f32,
+    // Segundos de escena que avanzó el último fotograma
+    pub delta: f32,
+    // Fotogramas avanzados desde el inicio
+    pub frame: u64,
+    // Multiplicador del tiempo real (0.5 a cámara lenta, 0 equivale a pausar)
+    pub time_scale: f32,
+    pub paused: bool,
+    // Paso máximo por fotograma, para que un tirón (arrastrar la ventana, un render
+    // lento) no haga saltar las animaciones
+    pub max_delta: f32,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock { elapsed: 0.0, delta: 0.0, frame: 0, time_scale: 1.0, paused: false, max_delta: 0.25 }
+    }
+
+    // Avanza el reloj `real_dt` segundos de tiempo real
+    pub fn tick(&mut self, real_dt: f32) {
+        self.delta = if self.paused { 0.0 } else { real_dt.clamp(0.0, self.max_delta) * self.time_scale };
+        self.elapsed += self.delta;
+        self.frame += 1;
+    }
+
+    // Coloca el reloj en un instante concreto, p. ej. al exportar una animación
+    pub fn set_time(&mut self, elapsed: f32) {
+        self.delta = elapsed - self.elapsed;
+        self.elapsed = elapsed;
+    }
+}
@@ -1,30 +1,65 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+// Gamma con la que se codifican los colores de 8 bits (texturas, hex, pantalla)
+pub const GAMMA: f32 = 2.2;
+
+// Color en espacio lineal y en punto flotante: las luces se suman sin recortarse y
+// solo al escribir en el framebuffer se pasa por el tonemap y la gamma
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
+    r: f32,
+    g: f32,
+    b: f32,
 }
 
 impl Color {
-    pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Color { r, g, b }
+    // Color de 8 bits en sRGB (como en un editor de imágenes), pasado a lineal
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        let decode = |channel: u8| (channel as f32 / 255.0).powf(GAMMA);
+        Color { r: decode(r), g: decode(g), b: decode(b) }
     }
 
-    pub const fn from_hex(hex: u32) -> Self {
-        let r = ((hex >> 16) & 0xFF) as u8;
-        let g = ((hex >> 8) & 0xFF) as u8;
-        let b = (hex & 0xFF) as u8;
+    // Componentes ya lineales; pueden pasar de 1 en luces intensas
+    pub const fn from_linear(r: f32, g: f32, b: f32) -> Self {
         Color { r, g, b }
     }
 
+    pub fn from_hex(hex: u32) -> Self {
+        Color::new((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
     pub const fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0.0, g: 0.0, b: 0.0 }
+    }
+
+    pub fn linear(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
     }
 
-    pub fn to_hex(&self) -> u32 {
-        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    // Canales con gamma en [0, 1], recortando lo que pase de 1
+    pub fn to_srgb(self) -> [f32; 3] {
+        self.linear().map(|channel| channel.clamp(0.0, 1.0).powf(1.0 / GAMMA))
+    }
+
+    // 0xRRGGBB en sRGB; lo que pase de 1 se recorta, así que antes va el tonemap
+    pub fn to_hex(self) -> u32 {
+        let [r, g, b] = self.to_srgb().map(|channel| (channel * 255.0).round() as u32);
+        (r << 16) | (g << 8) | b
+    }
+
+    // Luminancia relativa (Rec. 709)
+    pub fn luminance(self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    // Promedio de varias muestras
+    pub fn average(colors: &[Color]) -> Color {
+        if colors.is_empty() {
+            return Color::black();
+        }
+
+        let sum = colors.iter().fold(Color::black(), |sum, color| sum + *color);
+        sum * (1.0 / colors.len() as f32)
     }
 }
 
@@ -35,9 +70,9 @@ impl Add for Color {
 
     fn add(self, other: Color) -> Color {
         Color {
-            r: self.r.saturating_add(other.r),
-            g: self.g.saturating_add(other.g),
-            b: self.b.saturating_add(other.b),
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
         }
     }
 }
@@ -48,11 +83,12 @@ use std::ops::Mul;
 impl Mul<f32> for Color {
     type Output = Color;
 
+    // Sin límite superior; los factores negativos dejan el canal en 0
     fn mul(self, scalar: f32) -> Color {
         Color {
-            r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
-            g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
-            b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            r: (self.r * scalar).max(0.0),
+            g: (self.g * scalar).max(0.0),
+            b: (self.b * scalar).max(0.0),
         }
     }
 }
@@ -65,8 +101,20 @@ impl fmt::Display for Color {
 
 impl AddAssign for Color {
     fn add_assign(&mut self, other: Color) {
-        self.r = self.r.saturating_add(other.r);
-        self.g = self.g.saturating_add(other.g);
-        self.b = self.b.saturating_add(other.b);
+        self.r += other.r;
+        self.g += other.g;
+        self.b += other.b;
+    }
+}
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    // Modulación componente a componente (por ejemplo, luz por albedo)
+    fn mul(self, other: Color) -> Color {
+        Color {
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b,
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,261 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::fog::{Fog, DEFAULT_FOG_COLOR};
+use crate::light::Attenuation;
+use crate::settings::RenderSettings;
+use crate::texture::TextureFilter;
+use crate::tonemap::Tonemap;
+use crate::weather::WeatherKind;
+use crate::worldgen::WorldGenConfig;
+
+// Opciones de ejecución leídas de la línea de comandos
+pub struct Config {
+    pub terminal: bool,
+    // Hilos del render con `--threads`; por defecto uno por núcleo. La imagen es la
+    // misma con cualquier número de hilos
+    pub threads: Option<usize>,
+    // Tamaño del framebuffer con `--width` y `--height` (200x100 por defecto)
+    pub width: usize,
+    pub height: usize,
+    // Render sin ventana con `--render <salida.png>`: traza `--spp` muestras por píxel,
+    // guarda la imagen y termina
+    pub render_output: Option<String>,
+    pub integrator: String,
+    pub sampler: String,
+    pub seed: u64,
+    pub shadows: Option<String>,
+    // Calidad de sombra y bias solo para la luz principal (el sol)
+    pub sun_shadows: Option<String>,
+    pub sun_shadow_bias: Option<f32>,
+    // Convierte el sol en una luz de área cuadrada de ese lado, con `--sun-area-samples` muestras
+    pub sun_area: Option<f32>,
+    pub sun_area_samples: u32,
+    // Caída con la distancia de todas las luces con `--attenuation <none|c,l,q>`
+    pub attenuation: Option<Attenuation>,
+    // La niebla de `render` sale de `--fog <densidad>` (exponencial) o `--fog-linear
+    // <inicio,fin>` en bloques, con `--fog-color RRGGBB`
+    pub render: RenderSettings,
+    // Techo emisivo sobre el mundo con `--sky-ceiling <intensidad>` y `--sky-ceiling-color RRGGBB`
+    pub sky_ceiling: Option<f32>,
+    pub sky_ceiling_color: Option<u32>,
+    // Cielo con `--skybox <flat|gradient|procedural|cruz.png>`; sustituye al de la escena
+    pub skybox: Option<String>,
+    // Cielo nocturno con `--night <0 a 1>` (cuánto es de noche) y `--moon-light <intensidad>`
+    pub night: Option<f32>,
+    // Ciclo de día y noche con `--time-of-day <hora>` y `--day-length <segundos por día>`
+    pub time_of_day: Option<f32>,
+    pub day_length: Option<f32>,
+    pub moon_light: Option<f32>,
+    // Tiempo inicial con `--weather <clear|rain|storm>` y segundos por estado con `--weather-cycle`
+    pub weather: Option<WeatherKind>,
+    pub weather_cycle: Option<f32>,
+    // Nieve sobre las caras que miran arriba con `--snow <cobertura de 0 a 1>`
+    pub snow: Option<f32>,
+    // Render a la densidad de la pantalla con `--hidpi`; `--scale-factor` fija la escala
+    // en lugar de detectarla
+    pub hidpi: bool,
+    pub scale_factor: Option<f32>,
+    // Aumento entero de los píxeles en la ventana con `--pixel-scale <2|4|8>`
+    pub pixel_scale: usize,
+    // Tamaño de la ventana con `--window-width` y `--window-height`; sin ellos es el del
+    // framebuffer por `--pixel-scale`
+    pub window_width: Option<usize>,
+    pub window_height: Option<usize>,
+    // Divisor de la resolución mientras se mueve la cámara con `--motion-scale <2|4|8>`
+    pub motion_scale: usize,
+    // Límite de fotogramas por segundo con `--fps <n>` (60 por defecto, 0 sin límite)
+    pub target_fps: f32,
+    // Directorios de paquetes de texturas con `--texture-packs <dir1,dir2>`; T pasa de uno a otro
+    pub texture_packs: Vec<String>,
+    // Recarga las texturas al guardarlas desde otro programa con `--watch-textures`
+    pub watch_textures: bool,
+    // Render progresivo en la ventana con `--progressive <fotogramas>`
+    pub progressive: Option<u32>,
+    // Archivo de escena JSON que sustituye al diorama incluido
+    pub scene: Option<String>,
+    // Escena de rendimiento de `--bench-scene <bloques>` generada con `--seed`; con
+    // `--bench-objects` los bloques van a la BVH como cubos sueltos
+    pub bench_scene: Option<usize>,
+    pub bench_objects: bool,
+    // Sombras horneadas: se cargan de `--lightmap <ruta.png>` si existe y coincide
+    // con la escena; si no, se hornean con `--lightmap-resolution` texeles por lado y se guardan ahí
+    pub lightmap: Option<String>,
+    pub lightmap_resolution: usize,
+    // Sondas de luz dentro de la casa cada `--light-probes <separación>` unidades,
+    // con `--probe-samples` rayos por sonda
+    pub light_probes: Option<f32>,
+    pub probe_samples: u32,
+    // Exporta `--dolly-zoom <fotogramas>` imágenes numeradas en `--frames-dir` llevando
+    // el campo de visión a `--dolly-fov <grados>` hacia `--dolly-target x,y,z` (por
+    // defecto el centro de la cámara), y termina
+    pub dolly_zoom: Option<u32>,
+    pub dolly_fov: f32,
+    pub dolly_target: Option<Vec3>,
+    pub frames_dir: String,
+    // Campo de visión vertical de la cámara en grados con `--fov`
+    pub fov: Option<f32>,
+    // Inspector de píxeles en la ventana con `--inspect`: el píxel bajo el ratón se
+    // describe en la consola
+    pub inspect: bool,
+    // Registra todos los rebotes del rayo del píxel `--trace-pixel x,y` y termina
+    pub trace_pixel: Option<(usize, usize)>,
+    // Exporta `--animate <fotogramas>` imágenes `frame_0001.png`... en `--frames-dir`
+    // siguiendo las claves de `--animation <ruta>` o, sin archivo, una vuelta alrededor
+    // del centro de la cámara de `--turntable-seconds` segundos, y termina
+    pub animate: Option<u32>,
+    pub animation: Option<String>,
+    pub turntable_seconds: f32,
+    // Terreno generado en lugar del diorama plano, activado con `--worldgen`
+    pub worldgen: Option<WorldGenConfig>,
+}
+
+impl Config {
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let defaults = RenderSettings::default();
+
+        Config {
+            terminal: has_flag(&args, "--terminal"),
+            threads: parse_value::<usize>(&args, "--threads").map(|threads| threads.max(1)),
+            width: parse_value::<usize>(&args, "--width").unwrap_or(200).max(1),
+            height: parse_value::<usize>(&args, "--height").unwrap_or(100).max(1),
+            render_output: value_of(&args, "--render").map(str::to_string),
+            integrator: value_of(&args, "--integrator").unwrap_or("whitted").to_string(),
+            sampler: value_of(&args, "--sampler").unwrap_or("independent").to_string(),
+            seed: parse_value(&args, "--seed").unwrap_or(0),
+            shadows: value_of(&args, "--shadows").map(str::to_string),
+            sun_shadows: value_of(&args, "--sun-shadows").map(str::to_string),
+            sun_shadow_bias: parse_value(&args, "--sun-shadow-bias"),
+            sun_area: parse_value(&args, "--sun-area"),
+            sun_area_samples: parse_value(&args, "--sun-area-samples").unwrap_or(16),
+            attenuation: value_of(&args, "--attenuation").map(|value| {
+                Attenuation::from_name(value).unwrap_or_else(|| panic!("Valor inválido para --attenuation: {}", value))
+            }),
+            worldgen: has_flag(&args, "--worldgen").then(|| {
+                let defaults = WorldGenConfig::default();
+                WorldGenConfig {
+                    seed: parse_value(&args, "--seed").unwrap_or(0),
+                    caves: has_flag(&args, "--caves"),
+                    cave_threshold: parse_value(&args, "--cave-threshold").unwrap_or(defaults.cave_threshold),
+                    sea_level: parse_value(&args, "--sea-level"),
+                    ..defaults
+                }
+            }),
+            sky_ceiling: parse_value(&args, "--sky-ceiling"),
+            sky_ceiling_color: value_of(&args, "--sky-ceiling-color").map(|hex| {
+                u32::from_str_radix(hex.trim_start_matches('#'), 16)
+                    .unwrap_or_else(|_| panic!("Valor inválido para --sky-ceiling-color: {}", hex))
+            }),
+            scene: value_of(&args, "--scene").map(str::to_string),
+            bench_scene: parse_value(&args, "--bench-scene"),
+            bench_objects: has_flag(&args, "--bench-objects"),
+            skybox: value_of(&args, "--skybox").map(str::to_string),
+            night: parse_value::<f32>(&args, "--night").map(|amount| amount.clamp(0.0, 1.0)),
+            moon_light: parse_value(&args, "--moon-light"),
+            time_of_day: parse_value(&args, "--time-of-day"),
+            day_length: parse_value(&args, "--day-length"),
+            weather: value_of(&args, "--weather").map(|name| {
+                WeatherKind::from_name(name).unwrap_or_else(|| panic!("Tiempo desconocido: {}", name))
+            }),
+            weather_cycle: parse_value(&args, "--weather-cycle"),
+            snow: parse_value::<f32>(&args, "--snow").map(|coverage| coverage.clamp(0.0, 1.0)),
+            progressive: parse_value(&args, "--progressive"),
+            watch_textures: has_flag(&args, "--watch-textures"),
+            texture_packs: value_of(&args, "--texture-packs")
+                .map(|value| value.split(',').map(|dir| dir.trim().to_string()).collect())
+                .unwrap_or_default(),
+            hidpi: has_flag(&args, "--hidpi"),
+            scale_factor: parse_value(&args, "--scale-factor"),
+            target_fps: parse_value(&args, "--fps").unwrap_or(60.0),
+            pixel_scale: parse_value::<usize>(&args, "--pixel-scale").unwrap_or(1).max(1),
+            window_width: parse_value::<usize>(&args, "--window-width").map(|width| width.max(1)),
+            window_height: parse_value::<usize>(&args, "--window-height").map(|height| height.max(1)),
+            motion_scale: parse_value::<usize>(&args, "--motion-scale").unwrap_or(1).max(1),
+            lightmap: value_of(&args, "--lightmap").map(str::to_string),
+            dolly_zoom: parse_value(&args, "--dolly-zoom"),
+            dolly_fov: parse_value(&args, "--dolly-fov").unwrap_or(90.0),
+            dolly_target: value_of(&args, "--dolly-target").map(|value| {
+                let coords: Vec<f32> = value.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()
+                    .filter(|coords: &Vec<f32>| coords.len() == 3)
+                    .unwrap_or_else(|| panic!("Valor inválido para --dolly-target: {}", value));
+                Vec3::new(coords[0], coords[1], coords[2])
+            }),
+            frames_dir: value_of(&args, "--frames-dir").unwrap_or("frames").to_string(),
+            fov: parse_value::<f32>(&args, "--fov").map(|degrees| degrees.clamp(1.0, 179.0)),
+            inspect: has_flag(&args, "--inspect"),
+            trace_pixel: value_of(&args, "--trace-pixel").map(|value| {
+                let coords: Vec<usize> = value.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()
+                    .filter(|coords: &Vec<usize>| coords.len() == 2)
+                    .unwrap_or_else(|| panic!("Valor inválido para --trace-pixel: {}", value));
+                (coords[0], coords[1])
+            }),
+            animate: parse_value(&args, "--animate"),
+            animation: value_of(&args, "--animation").map(str::to_string),
+            turntable_seconds: parse_value::<f32>(&args, "--turntable-seconds").unwrap_or(10.0).max(0.1),
+            lightmap_resolution: parse_value(&args, "--lightmap-resolution").unwrap_or(8),
+            light_probes: parse_value::<f32>(&args, "--light-probes").map(|spacing| spacing.max(0.1)),
+            probe_samples: parse_value::<u32>(&args, "--probe-samples").unwrap_or(64).max(1),
+            render: RenderSettings {
+                max_depth: parse_value(&args, "--max-depth").unwrap_or(defaults.max_depth),
+                origin_bias: parse_value(&args, "--bias").unwrap_or(defaults.origin_bias),
+                world_scale: parse_value::<f32>(&args, "--world-scale").unwrap_or(defaults.world_scale).max(1e-4),
+                debug_overlay: has_flag(&args, "--debug-overlay"),
+                shadow_samples: parse_value::<u32>(&args, "--shadow-samples").map(|samples| samples.max(1)),
+                contact_hardening: parse_value::<f32>(&args, "--contact-hardening").unwrap_or(defaults.contact_hardening).max(0.0),
+                shadow_normal_bias: parse_value::<f32>(&args, "--shadow-normal-bias").unwrap_or(defaults.shadow_normal_bias).max(0.0),
+                shadow_slope_bias: parse_value::<f32>(&args, "--shadow-slope-bias").unwrap_or(defaults.shadow_slope_bias).max(0.0),
+                fog: {
+                    let color = Color::from_hex(value_of(&args, "--fog-color").map_or(DEFAULT_FOG_COLOR, |hex| {
+                        u32::from_str_radix(hex.trim_start_matches('#'), 16)
+                            .unwrap_or_else(|_| panic!("Valor inválido para --fog-color: {}", hex))
+                    }));
+                    match (parse_value::<f32>(&args, "--fog"), value_of(&args, "--fog-linear")) {
+                        (_, Some(value)) => {
+                            let range: Vec<f32> = value.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()
+                                .filter(|range: &Vec<f32>| range.len() == 2)
+                                .unwrap_or_else(|| panic!("Valor inválido para --fog-linear: {}", value));
+                            Some(Fog::linear(color, range[0], range[1]))
+                        }
+                        (Some(density), None) => Some(Fog::exponential(color, density)),
+                        (None, None) => defaults.fog,
+                    }
+                },
+                samples_per_pixel: parse_value::<u32>(&args, "--spp").unwrap_or(defaults.samples_per_pixel).max(1),
+                adaptive_threshold: parse_value(&args, "--adaptive"),
+                ao_samples: parse_value(&args, "--ao").unwrap_or(defaults.ao_samples),
+                ao_radius: parse_value(&args, "--ao-radius").unwrap_or(defaults.ao_radius),
+                ao_strength: parse_value::<f32>(&args, "--ao-strength").unwrap_or(defaults.ao_strength).clamp(0.0, 1.0),
+                lens_flare: parse_value::<f32>(&args, "--lens-flare").unwrap_or(defaults.lens_flare).max(0.0),
+                bloom_threshold: parse_value(&args, "--bloom-threshold").unwrap_or(defaults.bloom_threshold),
+                bloom_intensity: parse_value::<f32>(&args, "--bloom").unwrap_or(defaults.bloom_intensity).max(0.0),
+                bloom_radius: parse_value::<f32>(&args, "--bloom-radius").unwrap_or(defaults.bloom_radius).max(0.0),
+                tonemap: value_of(&args, "--tonemap").map_or(defaults.tonemap, |name| {
+                    Tonemap::from_name(name).unwrap_or_else(|| panic!("Tonemap desconocido: {}", name))
+                }),
+                exposure: parse_value::<f32>(&args, "--exposure").unwrap_or(defaults.exposure).max(0.0),
+                texture_filter: value_of(&args, "--filter").map_or(defaults.texture_filter, |name| {
+                    TextureFilter::from_name(name).unwrap_or_else(|| panic!("Filtro de textura desconocido: {}", name))
+                }),
+            },
+        }
+    }
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+// Valor que sigue a una opción, por ejemplo `--spp 4`
+fn value_of<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn parse_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    value_of(args, flag).map(|value| {
+        value.parse().unwrap_or_else(|_| panic!("Valor inválido para {}: {}", flag, value))
+    })
+}
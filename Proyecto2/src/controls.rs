@@ -0,0 +1,298 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::camera::{Camera, CameraMode};
+use crate::input::{Input, Key};
+use crate::scene::Scene;
+
+// Tope de las muestras por píxel que se alcanzan doblándolas con =
+pub const MAX_SAMPLES_PER_PIXEL: u32 = 256;
+// Bloques por segundo que se mueve la luz principal con IJKLUO
+pub const LIGHT_SPEED: f32 = 3.0;
+// Horas del día que avanza el ciclo por segundo con coma o punto pulsados
+pub const SCRUB_HOURS_PER_SECOND: f32 = 3.0;
+// Velocidades de la cámara orbital: radianes por segundo y bloques por segundo
+pub const ORBIT_SPEED: f32 = PI / 2.0;
+pub const ZOOM_SPEED: f32 = 4.0;
+// Velocidades de la cámara de vuelo: bloques por segundo, radianes por segundo y
+// radianes por píxel del framebuffer que se mueve el ratón
+pub const MOVE_SPEED: f32 = 4.0;
+pub const TURN_SPEED: f32 = PI / 2.0;
+pub const MOUSE_SENSITIVITY: f32 = 0.01;
+// Pasos fijos por pulsación cuando la entrada no tiene teclas mantenidas (terminal):
+// radianes al girar, bloques al acercar, volar o mover la luz y horas del ciclo
+pub const ORBIT_STEP: f32 = PI / 10.0;
+pub const ZOOM_STEP: f32 = 0.1;
+pub const TURN_STEP: f32 = PI / 20.0;
+pub const MOVE_STEP: f32 = 0.25;
+pub const LIGHT_STEP: f32 = 0.1;
+pub const SCRUB_HOURS_STEP: f32 = 0.25;
+
+// Tecla mantenida en un eje: 1 con `positive`, -1 con `negative` y 0 con ambas o ninguna
+fn axis(input: &dyn Input, negative: Key, positive: Key) -> f32 {
+    (input.is_key_down(positive) as i32 - input.is_key_down(negative) as i32) as f32
+}
+
+// Cuánto avanza un control en este fotograma: `speed` por segundo, o `step` por
+// pulsación si la entrada lo pide
+fn advance(input: &dyn Input, speed: f32, step: f32) -> f32 {
+    if input.per_press() { step } else { speed * input.frame_time() }
+}
+
+// Tab alterna entre la cámara orbital y la de vuelo libre; después se aplican los
+// controles del modo activo
+pub fn camera_controls(input: &dyn Input, camera: &mut Camera, scale: f32) {
+    if input.is_key_pressed(Key::Tab) {
+        camera.toggle_mode();
+        eprintln!("Cámara: {}", if camera.mode == CameraMode::Fly { "vuelo libre" } else { "orbital" });
+    }
+
+    match camera.mode {
+        CameraMode::Orbit => orbit_controls(input, camera, scale),
+        CameraMode::Fly => fly_controls(input, camera, scale),
+    }
+}
+
+// Cámara orbital: las flechas giran alrededor del centro y W/S acercan o alejan, con
+// velocidades por segundo escaladas por la duración del fotograma y el zoom por la
+// escala del mundo, así que se mueve igual de rápido aunque la escena cueste más
+pub fn orbit_controls(input: &dyn Input, camera: &mut Camera, scale: f32) {
+    let turn = advance(input, ORBIT_SPEED, ORBIT_STEP);
+    let (yaw, pitch) = (axis(input, Key::Right, Key::Left), axis(input, Key::Up, Key::Down));
+    if yaw != 0.0 || pitch != 0.0 {
+        camera.orbit(yaw * turn, pitch * turn);
+    }
+    let zoom = axis(input, Key::S, Key::W);
+    if zoom != 0.0 {
+        camera.zoom(zoom * advance(input, ZOOM_SPEED, ZOOM_STEP) * scale);
+    }
+}
+
+// Cámara de vuelo: el ratón (o las flechas) gira la vista, WASD mueve en horizontal y
+// Space/Shift suben y bajan, con velocidades por segundo escaladas por la duración del
+// fotograma y el desplazamiento por la escala del mundo
+pub fn fly_controls(input: &dyn Input, camera: &mut Camera, scale: f32) {
+    let turn = advance(input, TURN_SPEED, TURN_STEP);
+
+    let (dx, dy) = input.mouse_delta();
+    let yaw = dx * MOUSE_SENSITIVITY + axis(input, Key::Left, Key::Right) * turn;
+    let pitch = -dy * MOUSE_SENSITIVITY + axis(input, Key::Down, Key::Up) * turn;
+    camera.look(yaw, pitch);
+
+    let shift = input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift);
+    let movement = Vec3::new(
+        axis(input, Key::A, Key::D),
+        input.is_key_down(Key::Space) as i32 as f32 - shift as i32 as f32,
+        axis(input, Key::S, Key::W),
+    );
+    if movement != Vec3::zeros() {
+        camera.fly(&(movement.normalize() * advance(input, MOVE_SPEED, MOVE_STEP) * scale));
+    }
+}
+
+// IJKLUO mueven la luz principal en bloques por segundo, y coma y punto mueven la hora
+// del ciclo de día y noche hacia atrás y hacia delante
+pub fn light_controls(input: &dyn Input, scene: &mut Scene) {
+    let step = advance(input, LIGHT_SPEED, LIGHT_STEP) * scene.settings.world_scale;
+    if let Some(light) = scene.lights.first_mut() {
+        light.position += Vec3::new(
+            axis(input, Key::J, Key::L),
+            axis(input, Key::K, Key::I),
+            axis(input, Key::O, Key::U),
+        ) * step;
+    }
+
+    if let Some(sun) = &mut scene.sun {
+        let scrub = axis(input, Key::Comma, Key::Period);
+        if scrub != 0.0 {
+            sun.scrub(scrub * advance(input, SCRUB_HOURS_PER_SECOND, SCRUB_HOURS_STEP));
+            scene.update_sun(0.0);
+        }
+    }
+}
+
+// Conmutadores de la escena y de la calidad del render, con las teclas recién pulsadas
+pub fn settings_controls(input: &dyn Input, scene: &mut Scene) {
+    // Las teclas numéricas muestran u ocultan el grupo con ese índice
+    const GROUP_KEYS: [Key; 10] = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+        Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    ];
+    for (index, key) in GROUP_KEYS.iter().enumerate() {
+        if input.is_key_pressed(*key) && index < scene.groups.len() {
+            scene.toggle_group(index);
+            let group = &scene.groups[index];
+            eprintln!("Grupo '{}': {}", group.name, if group.visible { "visible" } else { "oculto" });
+        }
+    }
+
+    // P congela o reanuda el reloj de las animaciones
+    if input.is_key_pressed(Key::P) {
+        scene.clock.paused = !scene.clock.paused;
+        eprintln!("Animaciones {}", if scene.clock.paused { "en pausa" } else { "en marcha" });
+    }
+
+    // H muestra u oculta las cajas envolventes y las normales de depuración
+    if input.is_key_pressed(Key::H) {
+        scene.settings.debug_overlay = !scene.settings.debug_overlay;
+        eprintln!("Capa de depuración {}", if scene.settings.debug_overlay { "visible" } else { "oculta" });
+    }
+
+    // [ y ] bajan o suben los rebotes; - y = reducen a la mitad o doblan las muestras por píxel
+    let settings = &mut scene.settings;
+    if input.is_key_pressed(Key::LeftBracket) {
+        settings.max_depth = settings.max_depth.saturating_sub(1);
+        eprintln!("Rebotes: {}", settings.max_depth);
+    }
+    if input.is_key_pressed(Key::RightBracket) {
+        settings.max_depth += 1;
+        eprintln!("Rebotes: {}", settings.max_depth);
+    }
+    if input.is_key_pressed(Key::Minus) {
+        settings.samples_per_pixel = (settings.samples_per_pixel / 2).max(1);
+        eprintln!("Muestras por píxel: {}", settings.samples_per_pixel);
+    }
+    if input.is_key_pressed(Key::Equal) {
+        settings.samples_per_pixel = (settings.samples_per_pixel * 2).min(MAX_SAMPLES_PER_PIXEL);
+        eprintln!("Muestras por píxel: {}", settings.samples_per_pixel);
+    }
+
+    // Y pasa al siguiente estado del tiempo: despejado, lluvia, tormenta
+    if input.is_key_pressed(Key::Y) {
+        scene.weather.next();
+        eprintln!("Tiempo: {:?}", scene.weather.kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::input::InputState;
+    use crate::light::Light;
+    use crate::settings::RenderSettings;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn held(keys: &[Key], frame_time: f32) -> InputState {
+        InputState { keys_down: keys.to_vec(), frame_time, ..InputState::default() }
+    }
+
+    fn pressed(keys: &[Key]) -> InputState {
+        InputState { keys_pressed: keys.to_vec(), ..InputState::default() }
+    }
+
+    fn camera() -> Camera {
+        Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0))
+    }
+
+    fn scene_with_light() -> Scene {
+        let light = Light::new(Vec3::zeros(), Color::new(255, 255, 255), 1.0);
+        Scene::new(Vec::new(), vec![light], RenderSettings::default())
+    }
+
+    #[test]
+    fn orbit_zoom_scales_with_frame_time_and_world_scale() {
+        let mut camera = camera();
+        orbit_controls(&held(&[Key::W], 0.25), &mut camera, 2.0);
+        assert!((camera.eye.z - (5.0 - ZOOM_SPEED * 2.0 * 0.25)).abs() < EPSILON);
+        assert_eq!(camera.center, Vec3::zeros());
+    }
+
+    #[test]
+    fn orbit_turns_around_the_center() {
+        let mut camera = camera();
+        orbit_controls(&held(&[Key::Left], 0.5), &mut camera, 1.0);
+        let angle = camera.eye.normalize().dot(&Vec3::new(0.0, 0.0, 1.0)).clamp(-1.0, 1.0).acos();
+        assert!((angle - ORBIT_SPEED * 0.5).abs() < EPSILON);
+        assert!((camera.eye.magnitude() - 5.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn orbit_ignores_opposite_keys() {
+        let mut camera = camera();
+        orbit_controls(&held(&[Key::W, Key::S, Key::Left, Key::Right], 1.0), &mut camera, 1.0);
+        assert_eq!(camera.eye, Vec3::new(0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn fly_moves_forward_and_up() {
+        let mut camera = camera();
+        fly_controls(&held(&[Key::W], 0.5), &mut camera, 1.0);
+        assert!((camera.eye - Vec3::new(0.0, 0.0, 5.0 - MOVE_SPEED * 0.5)).magnitude() < EPSILON);
+
+        let before = camera.eye;
+        fly_controls(&held(&[Key::Space], 0.5), &mut camera, 2.0);
+        assert!((camera.eye - before - Vec3::new(0.0, MOVE_SPEED, 0.0)).magnitude() < EPSILON);
+    }
+
+    #[test]
+    fn fly_looks_with_the_mouse() {
+        let mut camera = camera();
+        let input = InputState { mouse_delta: (10.0, 0.0), ..InputState::default() };
+        fly_controls(&input, &mut camera, 1.0);
+        assert_eq!(camera.eye, Vec3::new(0.0, 0.0, 5.0));
+        let view = (camera.center - camera.eye).normalize();
+        let angle = view.dot(&Vec3::new(0.0, 0.0, -1.0)).clamp(-1.0, 1.0).acos();
+        assert!((angle - 10.0 * MOUSE_SENSITIVITY).abs() < EPSILON);
+    }
+
+    #[test]
+    fn tab_switches_to_fly_mode() {
+        let mut camera = camera();
+        camera_controls(&InputState { keys_pressed: vec![Key::Tab], ..InputState::default() }, &mut camera, 1.0);
+        assert_eq!(camera.mode, CameraMode::Fly);
+    }
+
+    #[test]
+    fn terminal_presses_move_a_fixed_step() {
+        // Una pulsación de la terminal dura un fotograma: el paso no depende de su duración
+        let input = InputState { frame_time: 0.001, ..InputState::from_keys(vec![Key::W]) };
+        let mut zoomed = camera();
+        orbit_controls(&input, &mut zoomed, 2.0);
+        assert!((zoomed.eye.z - (5.0 - ZOOM_STEP * 2.0)).abs() < EPSILON);
+
+        let input = InputState { frame_time: 0.001, ..InputState::from_keys(vec![Key::Left]) };
+        let mut turned = camera();
+        orbit_controls(&input, &mut turned, 1.0);
+        let angle = turned.eye.normalize().dot(&Vec3::new(0.0, 0.0, 1.0)).clamp(-1.0, 1.0).acos();
+        assert!((angle - ORBIT_STEP).abs() < EPSILON);
+
+        let mut scene = scene_with_light();
+        light_controls(&InputState::from_keys(vec![Key::I]), &mut scene);
+        assert!((scene.lights[0].position.y - LIGHT_STEP).abs() < EPSILON);
+    }
+
+    #[test]
+    fn light_moves_by_speed_times_frame_time() {
+        let mut scene = scene_with_light();
+        scene.settings.world_scale = 2.0;
+        light_controls(&held(&[Key::I, Key::L, Key::O], 0.5), &mut scene);
+        let step = LIGHT_SPEED * 2.0 * 0.5;
+        assert!((scene.lights[0].position - Vec3::new(step, step, -step)).magnitude() < EPSILON);
+    }
+
+    #[test]
+    fn settings_keys_change_depth_and_samples() {
+        let mut scene = scene_with_light();
+        let (depth, samples) = (scene.settings.max_depth, scene.settings.samples_per_pixel);
+        settings_controls(&pressed(&[Key::RightBracket, Key::Equal]), &mut scene);
+        assert_eq!(scene.settings.max_depth, depth + 1);
+        assert_eq!(scene.settings.samples_per_pixel, (samples * 2).min(MAX_SAMPLES_PER_PIXEL));
+
+        scene.settings.samples_per_pixel = MAX_SAMPLES_PER_PIXEL;
+        settings_controls(&pressed(&[Key::Equal, Key::LeftBracket]), &mut scene);
+        assert_eq!(scene.settings.samples_per_pixel, MAX_SAMPLES_PER_PIXEL);
+        assert_eq!(scene.settings.max_depth, depth);
+    }
+
+    #[test]
+    fn held_keys_do_not_toggle_settings() {
+        let mut scene = scene_with_light();
+        let paused = scene.clock.paused;
+        let overlay = scene.settings.debug_overlay;
+        settings_controls(&held(&[Key::P, Key::H], 1.0), &mut scene);
+        assert_eq!(scene.clock.paused, paused);
+        assert_eq!(scene.settings.debug_overlay, overlay);
+    }
+}
@@ -1,35 +1,167 @@
 use nalgebra_glm::Vec3;
 use crate::material::Material;
 use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::scene::DEFAULT_GROUP;
 use std::sync::Arc;
 
+// Cara de un cubo, con los nombres de Minecraft para los lados: norte hacia -Z,
+// sur hacia +Z, este hacia +X y oeste hacia -X
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    East,
+    West,
+    Top,
+    Bottom,
+    South,
+    North,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::East, Face::West, Face::Top, Face::Bottom, Face::South, Face::North];
+
+    // Cara cuya normal exterior es `normal` (se usa el eje dominante)
+    pub fn from_normal(normal: &Vec3) -> Face {
+        let axis = normal.abs().imax();
+        match (axis, normal[axis] > 0.0) {
+            (0, true) => Face::East,
+            (0, false) => Face::West,
+            (1, true) => Face::Top,
+            (1, false) => Face::Bottom,
+            (_, true) => Face::South,
+            (_, false) => Face::North,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    // Normal exterior de la cara
+    pub fn normal(self) -> Vec3 {
+        match self {
+            Face::East => Vec3::new(1.0, 0.0, 0.0),
+            Face::West => Vec3::new(-1.0, 0.0, 0.0),
+            Face::Top => Vec3::new(0.0, 1.0, 0.0),
+            Face::Bottom => Vec3::new(0.0, -1.0, 0.0),
+            Face::South => Vec3::new(0.0, 0.0, 1.0),
+            Face::North => Vec3::new(0.0, 0.0, -1.0),
+        }
+    }
+
+    // Nombre en los archivos de escena y en los paquetes de texturas; "side" son los cuatro lados
+    pub fn from_name(name: &str) -> Option<Vec<Face>> {
+        let faces = match name {
+            "east" => vec![Face::East],
+            "west" => vec![Face::West],
+            "top" => vec![Face::Top],
+            "bottom" => vec![Face::Bottom],
+            "south" => vec![Face::South],
+            "north" => vec![Face::North],
+            "side" => vec![Face::East, Face::West, Face::South, Face::North],
+            _ => return None,
+        };
+        Some(faces)
+    }
+}
+
+// Orientación de la textura sobre una cara: `flip` la refleja en horizontal y después
+// se gira `quarter_turns` cuartos de vuelta en sentido horario, vista desde fuera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UvOrientation {
+    pub quarter_turns: u8,
+    pub flip: bool,
+}
+
+impl UvOrientation {
+    // Grados múltiplos de 90, en sentido horario; también valen negativos
+    pub fn new(degrees: i32, flip: bool) -> Option<Self> {
+        (degrees % 90 == 0).then(|| UvOrientation { quarter_turns: (degrees / 90).rem_euclid(4) as u8, flip })
+    }
+
+    // Coordenada de la textura que se ve en el punto (u, v) de la cara
+    pub fn apply(self, (u, v): (f32, f32)) -> (f32, f32) {
+        let u = if self.flip { 1.0 - u } else { u };
+        match self.quarter_turns % 4 {
+            1 => (v, 1.0 - u),
+            2 => (1.0 - u, 1.0 - v),
+            3 => (1.0 - v, u),
+            _ => (u, v),
+        }
+    }
+
+    // La base tangente de `Cube::face_basis` girada igual que la textura, para que los
+    // mapas de normales sigan a las coordenadas de `apply`
+    pub fn apply_basis(self, (tangent, bitangent): (Vec3, Vec3)) -> (Vec3, Vec3) {
+        let tangent = if self.flip { -tangent } else { tangent };
+        match self.quarter_turns % 4 {
+            1 => (bitangent, -tangent),
+            2 => (-tangent, -bitangent),
+            3 => (-bitangent, tangent),
+            _ => (tangent, bitangent),
+        }
+    }
+}
+
 pub struct Cube {
     pub min: Vec3,
     pub max: Vec3,
     pub material: Arc<Material>, // Usar Arc aquí para permitir compartición de datos
+    pub casts_shadows: bool, // Si es falso, los rayos de sombra lo atraviesan
+    pub visible_to_camera: bool, // Si es falso, los rayos primarios lo ignoran
+    pub group: usize, // Grupo de la escena al que pertenece
 }
 
 impl Cube {
     pub fn new(min: Vec3, max: Vec3, material: Arc<Material>) -> Self {
-        Cube { min, max, material }
+        Cube {
+            min,
+            max,
+            material,
+            casts_shadows: true,
+            visible_to_camera: true,
+            group: DEFAULT_GROUP,
+        }
     }
 
-    pub fn calculate_uv(&self, intersect: &Intersect) -> (f32, f32) {
-        let local_point = intersect.point - self.min; // Coordenada local dentro del cubo
-        let size = self.size();
-
-        let (u, v) = if intersect.normal.x.abs() > 0.0 {
-            // Cara del cubo que es paralela al plano YZ
-            ((local_point.z / size.z) % 1.0, (local_point.y / size.y) % 1.0)
-        } else if intersect.normal.y.abs() > 0.0 {
-            // Cara del cubo que es paralela al plano XZ
-            ((local_point.x / size.x) % 1.0, (local_point.z / size.z) % 1.0)
+    // Base tangente de cada cara vista desde fuera: `tangent` apunta a la derecha
+    // de la textura y `bitangent` hacia abajo, de modo que todas las caras
+    // (incluidas las opuestas) muestran la textura con la misma orientación
+    pub fn face_basis(face_normal: &Vec3) -> (Vec3, Vec3) {
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        if face_normal.y.abs() > 0.5 {
+            // Tapa y base: la parte superior de la textura mira hacia -Z en la tapa
+            let tangent = Vec3::new(1.0, 0.0, 0.0);
+            let bitangent = Vec3::new(0.0, 0.0, face_normal.y.signum());
+            (tangent, bitangent)
         } else {
-            // Cara del cubo que es paralela al plano XY
-            ((local_point.x / size.x) % 1.0, (local_point.y / size.y) % 1.0)
-        };
+            // Caras laterales: la parte superior de la textura siempre hacia +Y
+            (up.cross(face_normal), -up)
+        }
+    }
+
+    // `face_normal` es la normal exterior de la cara impactada
+    pub fn calculate_uv(&self, point: &Vec3, face_normal: &Vec3) -> (f32, f32) {
+        Cube::face_uv(&self.local(point), face_normal)
+    }
+
+    // Coordenada local dentro del cubo normalizada a [0, 1]
+    fn local(&self, point: &Vec3) -> Vec3 {
+        (point - self.min).component_div(&self.size())
+    }
+
+    // UV de un punto `local` del cubo unidad sobre la cara con normal exterior
+    // `face_normal`: su posición respecto al centro proyectada sobre la base de la cara,
+    // así u crece hacia `tangent` y v hacia `bitangent` en todas las caras
+    pub fn face_uv(local: &Vec3, face_normal: &Vec3) -> (f32, f32) {
+        let (tangent, bitangent) = Cube::face_basis(face_normal);
+        let centered = local - Vec3::new(0.5, 0.5, 0.5);
+        ((centered.dot(&tangent) + 0.5).clamp(0.0, 1.0), (centered.dot(&bitangent) + 0.5).clamp(0.0, 1.0))
+    }
 
-        (u.abs(), v.abs()) // Aseguramos que las coordenadas UV sean positivas
+    // UV y base tangente de la cara con normal exterior `face_normal`, orientadas por el material
+    pub fn oriented_face(local: &Vec3, face_normal: &Vec3, material: &Material) -> ((f32, f32), (Vec3, Vec3)) {
+        let orientation = material.face_orientation[Face::from_normal(face_normal).index()];
+        (orientation.apply(Cube::face_uv(local, face_normal)), orientation.apply_basis(Cube::face_basis(face_normal)))
     }
 
     pub fn size(&self) -> Vec3 {
@@ -39,90 +171,92 @@ impl Cube {
 
 impl RayIntersect for Cube {
     fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
-        // Evitar divisiones por cero y manejar rayos paralelos a los planos del cubo
-        let inv_dir_x = if direction.x != 0.0 { 1.0 / direction.x } else { f32::INFINITY };
-        let inv_dir_y = if direction.y != 0.0 { 1.0 / direction.y } else { f32::INFINITY };
-        let inv_dir_z = if direction.z != 0.0 { 1.0 / direction.z } else { f32::INFINITY };
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
+        // Ejes cuyas caras producen la entrada (tmin) y la salida (tmax) del rayo
+        let mut entry_axis = 0;
+        let mut exit_axis = 0;
 
-        let mut tmin = (self.min.x - origin.x) * inv_dir_x;
-        let mut tmax = (self.max.x - origin.x) * inv_dir_x;
+        for axis in 0..3 {
+            // Rayo paralelo a los planos de este eje: solo hay impacto si el origen está entre ellos
+            if direction[axis] == 0.0 {
+                if origin[axis] < self.min[axis] || origin[axis] > self.max[axis] {
+                    return Intersect::empty();
+                }
+                continue;
+            }
 
-        if tmin > tmax {
-            (tmin, tmax) = (tmax, tmin); // Intercambia si tmin > tmax
-        }
+            let inv_dir = 1.0 / direction[axis];
+            let mut t0 = (self.min[axis] - origin[axis]) * inv_dir;
+            let mut t1 = (self.max[axis] - origin[axis]) * inv_dir;
 
-        let mut tymin = (self.min.y - origin.y) * inv_dir_y;
-        let mut tymax = (self.max.y - origin.y) * inv_dir_y;
+            if t0 > t1 {
+                (t0, t1) = (t1, t0); // Intercambia si t0 > t1
+            }
 
-        if tymin > tymax {
-            (tymin, tymax) = (tymax, tymin);
+            if t0 > tmin {
+                tmin = t0;
+                entry_axis = axis;
+            }
+
+            if t1 < tmax {
+                tmax = t1;
+                exit_axis = axis;
+            }
+
+            if tmin > tmax {
+                return Intersect::empty();
+            }
         }
 
-        if tmin > tymax || tymin > tmax {
+        // El cubo queda completamente detrás del origen del rayo
+        if tmax < 0.0 {
             return Intersect::empty();
         }
 
-        if tymin > tmin {
-            tmin = tymin;
-        }
+        // Si el origen está dentro del cubo (tmin < 0) usamos la cara de salida,
+        // con la normal invertida para que apunte hacia el interior
+        let (distance, axis) = if tmin < 0.0 { (tmax, exit_axis) } else { (tmin, entry_axis) };
 
-        if tymax < tmax {
-            tmax = tymax;
-        }
+        // La cara impactada siempre mira en contra de la dirección del rayo en ese eje
+        let mut normal = Vec3::new(0.0, 0.0, 0.0);
+        normal[axis] = -direction[axis].signum();
 
-        let mut tzmin = (self.min.z - origin.z) * inv_dir_z;
-        let mut tzmax = (self.max.z - origin.z) * inv_dir_z;
+        // Normal exterior de la cara, usada para orientar la textura
+        let face_normal = if tmin < 0.0 { -normal } else { normal };
 
-        if tzmin > tzmax {
-            (tzmin, tzmax) = (tzmax, tzmin);
-        }
+        let point = origin + direction * distance;
 
-        if tmin > tzmax || tzmin > tmax {
-            return Intersect::empty();
-        }
+        let mut intersect = Intersect::new(point, normal, distance, self.material.clone());
+        let (uv, basis) = Cube::oriented_face(&self.local(&point), &face_normal, &self.material);
+        intersect.uv = Some(uv);
+        intersect.tangent = Some(basis);
+        intersect.face = Some(Face::from_normal(&face_normal));
+        intersect.front_face = tmin >= 0.0;
+        intersect
+    }
 
-        if tzmin > tmin {
-            tmin = tzmin;
-        }
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (self.min, self.max)
+    }
 
-        let point = origin + direction * tmin;
-        let normal = self.get_normal(&point);
-
-        let uv = Some(self.calculate_uv(&Intersect {
-            is_intersecting: true,
-            distance: tmin,
-            point,
-            normal,
-            material: self.material.clone(),
-            uv: None,
-        }));
-
-        Intersect {
-            is_intersecting: true,
-            distance: tmin,
-            point,
-            normal,
-            material: self.material.clone(), // Clonamos el material
-            uv,
-        }
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
     }
-}
 
-impl Cube {
-    fn get_normal(&self, point: &Vec3) -> Vec3 {
-        // Determinar la normal en función de la posición del punto de intersección
-        if (point.x - self.min.x).abs() < 1e-3 {
-            Vec3::new(-1.0, 0.0, 0.0)
-        } else if (point.x - self.max.x).abs() < 1e-3 {
-            Vec3::new(1.0, 0.0, 0.0)
-        } else if (point.y - self.min.y).abs() < 1e-3 {
-            Vec3::new(0.0, -1.0, 0.0)
-        } else if (point.y - self.max.y).abs() < 1e-3 {
-            Vec3::new(0.0, 1.0, 0.0)
-        } else if (point.z - self.min.z).abs() < 1e-3 {
-            Vec3::new(0.0, 0.0, -1.0)
-        } else {
-            Vec3::new(0.0, 0.0, 1.0)
-        }
+    fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Arc<Material> {
+        &mut self.material
     }
 }
@@ -0,0 +1,86 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::ray_intersect::RayIntersect;
+use crate::render::camera_ray;
+use crate::scene::Scene;
+
+// Píxeles entre dos rayos de la muestra de normales, en horizontal y en vertical
+const NORMAL_SPACING: usize = 12;
+// Largo de cada normal dibujada, en bloques
+const NORMAL_LENGTH: f32 = 0.3;
+
+const OBJECT_COLOR: u32 = 0xFFFF00;
+const BILLBOARD_COLOR: u32 = 0x00FFFF;
+const DECAL_COLOR: u32 = 0xFF00FF;
+
+// Aristas de las cajas envolventes de los objetos sueltos, sprites y calcomanías, y
+// un trazo corto en la dirección de la normal en una rejilla de impactos de la
+// cámara. Las normales llevan el mismo color que la vista de depuración "normals"
+// (x en rojo, y en verde, z en azul), así que una cara mal elegida destaca. Las cajas
+// se ven a través de la escena, para encontrar también los objetos tapados.
+pub fn draw_debug_overlay(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
+    let visible = |object: &dyn RayIntersect| scene.is_group_visible(object.group());
+
+    framebuffer.set_current_color(OBJECT_COLOR);
+    for object in scene.objects.iter().filter(|object| visible(object.as_ref())) {
+        draw_box(framebuffer, camera, object.bounds());
+    }
+    framebuffer.set_current_color(BILLBOARD_COLOR);
+    for billboard in scene.billboards.iter().filter(|billboard| visible(*billboard)) {
+        draw_box(framebuffer, camera, billboard.bounds());
+    }
+    framebuffer.set_current_color(DECAL_COLOR);
+    for decal in scene.decals.iter().filter(|decal| visible(*decal)) {
+        draw_box(framebuffer, camera, decal.bounds());
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let length = NORMAL_LENGTH * scene.settings.world_scale;
+    for y in (NORMAL_SPACING / 2..height).step_by(NORMAL_SPACING) {
+        for x in (NORMAL_SPACING / 2..width).step_by(NORMAL_SPACING) {
+            let direction = camera_ray(camera, width, height, x as f32, y as f32);
+            let hit = scene.intersect_camera(&camera.eye, &direction);
+            if !hit.is_intersecting {
+                continue;
+            }
+            let tip = hit.point + hit.normal * length;
+            let Some(end) = camera.project(&tip, width, height) else { continue };
+
+            let normal = hit.normal.normalize();
+            let color = Color::from_linear(normal.x.abs(), normal.y.abs(), normal.z.abs());
+            framebuffer.set_current_color(color.to_hex());
+            framebuffer.line((x as f32, y as f32), end);
+        }
+    }
+}
+
+// Las 12 aristas de la caja (min, max); se salta las que quedan detrás de la cámara
+// y las cajas infinitas
+fn draw_box(framebuffer: &mut Framebuffer, camera: &Camera, (min, max): (Vec3, Vec3)) {
+    if !(min.iter().chain(max.iter()).all(|c| c.is_finite())) {
+        return;
+    }
+    let corner = |i: usize| Vec3::new(
+        if i & 1 == 0 { min.x } else { max.x },
+        if i & 2 == 0 { min.y } else { max.y },
+        if i & 4 == 0 { min.z } else { max.z },
+    );
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let projected: Vec<Option<(f32, f32)>> = (0..8).map(|i| camera.project(&corner(i), width, height)).collect();
+
+    // Cada arista une dos esquinas que difieren en un solo bit (un solo eje)
+    for from in 0..8 {
+        for axis in [1, 2, 4] {
+            let to = from | axis;
+            if to == from {
+                continue;
+            }
+            if let (Some(a), Some(b)) = (projected[from], projected[to]) {
+                framebuffer.line(a, b);
+            }
+        }
+    }
+}
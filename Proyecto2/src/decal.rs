@@ -0,0 +1,109 @@
+use nalgebra_glm::{IVec3, Vec3};
+use crate::cube::{Cube, Face};
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::scene::DEFAULT_GROUP;
+use crate::texture::AlphaMask;
+use std::sync::Arc;
+
+// Separación entre la calcomanía y la cara del bloque, para que no compitan en profundidad
+const SURFACE_OFFSET: f32 = 0.01;
+
+// Lámina pegada a una cara de un bloque: escaleras de mano, enredaderas o cuadros.
+// Es un rectángulo de `size` centrado en la cara, un poco por delante de ella, con la
+// textura orientada igual que la del bloque; `alpha` recorta los huecos de la imagen.
+#[derive(Clone)]
+pub struct Decal {
+    pub block: IVec3,
+    pub face: Face,
+    // Ancho y alto sobre la cara en unidades del mundo; un cuadro puede ocupar varios bloques
+    pub size: (f32, f32),
+    pub material: Arc<Material>,
+    pub alpha: Option<Arc<AlphaMask>>,
+    pub casts_shadows: bool,
+    pub visible_to_camera: bool, // Si es falso, los rayos primarios lo ignoran
+    pub group: usize, // Grupo de la escena al que pertenece
+}
+
+impl Decal {
+    pub fn new(block: IVec3, face: Face, material: Arc<Material>) -> Self {
+        Decal {
+            block,
+            face,
+            size: (1.0, 1.0),
+            material,
+            alpha: None,
+            casts_shadows: true,
+            visible_to_camera: true,
+            group: DEFAULT_GROUP,
+        }
+    }
+
+    // Centro del rectángulo, separado de la cara hacia fuera
+    pub fn center(&self) -> Vec3 {
+        let block_center = Vec3::new(self.block.x as f32 + 0.5, self.block.y as f32 + 0.5, self.block.z as f32 + 0.5);
+        block_center + self.face.normal() * (0.5 + SURFACE_OFFSET)
+    }
+}
+
+impl RayIntersect for Decal {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let normal = self.face.normal();
+        let denominator = normal.dot(direction);
+        if denominator.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+        let center = self.center();
+        let distance = (center - origin).dot(&normal) / denominator;
+        if distance <= 0.0 {
+            return Intersect::empty();
+        }
+
+        // Misma base que las caras de los cubos: `tangent` a la derecha y `bitangent` hacia abajo
+        let (tangent, bitangent) = Cube::face_basis(&normal);
+        let point = origin + direction * distance;
+        let local = point - center;
+        let u = local.dot(&tangent) / self.size.0 + 0.5;
+        let v = local.dot(&bitangent) / self.size.1 + 0.5;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return Intersect::empty();
+        }
+        if self.alpha.as_ref().is_some_and(|alpha| !alpha.covers(u, v)) {
+            return Intersect::empty();
+        }
+
+        // Desde detrás (dentro del bloque) la normal mira hacia el rayo, como en los sprites
+        let facing = if denominator > 0.0 { -normal } else { normal };
+        let mut intersect = Intersect::new(point, facing, distance, self.material.clone());
+        intersect.uv = Some((u, v));
+        intersect.tangent = Some((tangent, bitangent));
+        intersect.face = Some(self.face);
+        intersect
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let (tangent, bitangent) = Cube::face_basis(&self.face.normal());
+        let extent = (tangent * self.size.0 + bitangent * self.size.1).abs() * 0.5 + Vec3::repeat(SURFACE_OFFSET);
+        (self.center() - extent, self.center() + extent)
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Arc<Material> {
+        &mut self.material
+    }
+}
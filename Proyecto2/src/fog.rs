@@ -0,0 +1,58 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+
+// Color de la niebla cuando no se indica otro: un gris azulado de día nublado
+pub const DEFAULT_FOG_COLOR: u32 = 0xB8C4D0;
+// Exponente de la franja de niebla sobre el cielo: más alto la pega más al horizonte
+const HORIZON_EXPONENT: f32 = 4.0;
+
+// Cómo crece la niebla con la distancia, medida en bloques
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FogFalloff {
+    // Se pierde `1 - e^(-density·d)` del color: nunca tapa del todo
+    Exponential { density: f32 },
+    // Nada hasta `start` y todo a partir de `end`, en línea recta entre medias
+    Linear { start: f32, end: f32 },
+}
+
+// Niebla atmosférica fija de la escena, aparte de la que trae el tiempo: los impactos
+// se mezclan con `color` según su distancia y el cielo de los fallos se funde con él
+// hacia el horizonte, para que el fondo y la geometría lejana acaben del mismo tono
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    pub color: Color,
+    pub falloff: FogFalloff,
+}
+
+impl Fog {
+    pub fn exponential(color: Color, density: f32) -> Self {
+        Fog { color, falloff: FogFalloff::Exponential { density: density.max(0.0) } }
+    }
+
+    pub fn linear(color: Color, start: f32, end: f32) -> Self {
+        Fog { color, falloff: FogFalloff::Linear { start, end: end.max(start) } }
+    }
+
+    // Fracción del color que tapa la niebla a `distance` bloques, de 0 a 1
+    pub fn amount(&self, distance: f32) -> f32 {
+        match self.falloff {
+            FogFalloff::Exponential { density } => 1.0 - (-density * distance).exp(),
+            FogFalloff::Linear { start, end } if end > start => ((distance - start) / (end - start)).clamp(0.0, 1.0),
+            FogFalloff::Linear { start, .. } => if distance >= start { 1.0 } else { 0.0 },
+        }
+    }
+
+    // `color` visto a `distance` bloques a través de la niebla
+    pub fn apply(&self, color: Color, distance: f32) -> Color {
+        let amount = self.amount(distance);
+        color * (1.0 - amount) + self.color * amount
+    }
+
+    // Cielo en `direction` fundido con la niebla: del todo en el horizonte y nada mirando
+    // arriba o abajo del todo
+    pub fn horizon(&self, sky: Color, direction: &Vec3) -> Color {
+        let amount = (1.0 - direction.normalize().y.abs()).powf(HORIZON_EXPONENT);
+        sky * (1.0 - amount) + self.color * amount
+    }
+}
@@ -1,8 +1,23 @@
+use image::{Rgb, RgbImage};
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::tonemap::Tonemap;
+
+// Color lineal de un píxel (r, g, b), sin límite superior
+pub type HdrPixel = [f32; 3];
 
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
+    // Píxeles 0xRRGGBB que se muestran en la ventana; salen de `hdr` con `resolve`
     pub buffer: Vec<u32>,
+    // Color lineal sin recortar de cada píxel, donde escribe el render y trabajan los
+    // efectos de pantalla
+    pub hdr: Vec<HdrPixel>,
+    // Suma de las muestras de cada píxel en el modo progresivo, y cuántos fotogramas lleva
+    accumulation: Vec<HdrPixel>,
+    pub accumulated_frames: u32,
     background_color: u32,
     current_color: u32,
 }
@@ -13,6 +28,9 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
+            hdr: vec![[0.0; 3]; width * height],
+            accumulation: vec![[0.0; 3]; width * height],
+            accumulated_frames: 0,
             background_color: 0x000000,
             current_color: 0xFFFFFF
         }
@@ -22,6 +40,14 @@ impl Framebuffer {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
         }
+        self.hdr.fill(Color::from_hex(self.background_color).linear());
+    }
+
+    // Pasa `hdr` a `buffer`: exposición, tonemap y gamma
+    pub fn resolve(&mut self, tonemap: Tonemap, exposure: f32) {
+        self.buffer.par_iter_mut().zip(self.hdr.par_iter()).for_each(|(pixel, &[r, g, b])| {
+            *pixel = tonemap.apply(Color::from_linear(r, g, b), exposure).to_hex();
+        });
     }
 
     pub fn point(&mut self, x: usize, y: usize) {
@@ -30,6 +56,19 @@ impl Framebuffer {
         }
     }
 
+    // Segmento de (x0, y0) a (x1, y1) con el color actual; lo que cae fuera se recorta
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        // Un extremo muy lejos de la imagen no debe costar millones de pasos
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().clamp(1.0, 4096.0) as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            if x >= 0.0 && y >= 0.0 {
+                self.point(x as usize, y as usize);
+            }
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
@@ -37,4 +76,61 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
-}
\ No newline at end of file
+
+    // Guarda el contenido como imagen; el formato sale de la extensión de `path`
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let image = RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pixel = self.buffer[y as usize * self.width + x as usize];
+            Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+        });
+        image.save(path).map_err(|e| format!("No se pudo guardar {}: {}", path, e))
+    }
+
+    // Huella FNV-1a de los píxeles mostrados: dos renders son idénticos bit a bit si
+    // coinciden, por ejemplo con distinto número de hilos
+    pub fn checksum(&self) -> u64 {
+        self.buffer.iter().fold(0xCBF2_9CE4_8422_2325u64, |hash, pixel| {
+            (hash ^ *pixel as u64).wrapping_mul(0x0000_0100_0000_01B3)
+        })
+    }
+
+    // Copia el framebuffer en el rectángulo (x, y, ancho, alto) de `target` (de `width`
+    // x `height`) con el vecino más cercano; lo que queda fuera del rectángulo es negro
+    pub fn blit_scaled(&self, target: &mut [u32], width: usize, height: usize, (rect_x, rect_y, rect_width, rect_height): (usize, usize, usize, usize)) {
+        let (rect_width, rect_height) = (rect_width.max(1), rect_height.max(1));
+        target[..width * height].par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            row.fill(0);
+            let Some(source_y) = y.checked_sub(rect_y).filter(|y| *y < rect_height).map(|y| y * self.height / rect_height) else {
+                return;
+            };
+            let source = &self.buffer[source_y * self.width..(source_y + 1) * self.width];
+            for (x, pixel) in row.iter_mut().enumerate().skip(rect_x).take(rect_width) {
+                *pixel = source[(x - rect_x) * self.width / rect_width];
+            }
+        });
+    }
+
+    // Descarta lo acumulado, por ejemplo al mover la cámara
+    pub fn reset_accumulation(&mut self) {
+        self.accumulation.fill([0.0; 3]);
+        self.accumulated_frames = 0;
+    }
+
+    // Como `par_hdr_rows_mut`, junto con la fila correspondiente de la acumulación
+    pub fn par_accumulation_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = (usize, (&mut [HdrPixel], &mut [HdrPixel]))> {
+        self.hdr.par_chunks_mut(self.width)
+            .zip(self.accumulation.par_chunks_mut(self.width))
+            .enumerate()
+    }
+
+    // Filas del buffer como porciones independientes, repartidas entre hilos:
+    // cada hilo escribe solo en su fila, así que no hace falta bloquear
+    pub fn par_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = (usize, &mut [u32])> {
+        self.buffer.par_chunks_mut(self.width).enumerate()
+    }
+
+    // Lo mismo sobre el color lineal
+    pub fn par_hdr_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = (usize, &mut [HdrPixel])> {
+        self.hdr.par_chunks_mut(self.width).enumerate()
+    }
+}
@@ -0,0 +1,102 @@
+use minifb::{MouseMode, Window};
+
+// Se reutilizan los identificadores de teclas y botones de minifb; otros backends
+// (terminal, winit, wasm) traducen sus eventos a ellos
+pub use minifb::{Key, MouseButton};
+
+// Estado de la entrada en el fotograma actual. Los controles de cámara y luces solo
+// dependen de este trait, así que funcionan igual con cualquier backend o en pruebas.
+pub trait Input {
+    // Tecla mantenida (movimiento continuo)
+    fn is_key_down(&self, key: Key) -> bool;
+    // Tecla recién pulsada en este fotograma (conmutadores)
+    fn is_key_pressed(&self, key: Key) -> bool;
+    // Posición del ratón en píxeles de la ventana, si está dentro
+    fn mouse_position(&self) -> Option<(f32, f32)>;
+    // Movimiento del ratón desde el fotograma anterior
+    fn mouse_delta(&self) -> (f32, f32);
+    fn is_mouse_down(&self, button: MouseButton) -> bool;
+    // Segundos reales desde el fotograma anterior, para que el movimiento no dependa de los FPS
+    fn frame_time(&self) -> f32;
+    // Si cada pulsación debe mover un paso fijo en lugar de según `frame_time`, porque
+    // el backend no sabe cuánto tiempo se mantiene una tecla
+    fn per_press(&self) -> bool;
+}
+
+// Instantánea de la entrada que rellenan los backends en cada fotograma
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    pub keys_down: Vec<Key>,
+    pub keys_pressed: Vec<Key>,
+    pub mouse_position: Option<(f32, f32)>,
+    pub mouse_delta: (f32, f32),
+    pub mouse_buttons: Vec<MouseButton>,
+    pub frame_time: f32,
+    pub per_press: bool,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        InputState::default()
+    }
+
+    // Entrada de la terminal: solo llegan pulsaciones, que cuentan como mantenidas y nuevas
+    // y mueven un paso fijo cada una
+    pub fn from_keys(keys: Vec<Key>) -> Self {
+        InputState { keys_down: keys.clone(), keys_pressed: keys, per_press: true, ..InputState::default() }
+    }
+
+    // Lee la ventana de minifb; `previous` es el estado del fotograma anterior, para el delta
+    // del ratón, y `to_pixels` pasa un punto de la ventana a píxeles de la imagen
+    pub fn from_window(window: &Window, previous: &InputState, to_pixels: impl Fn(f32, f32) -> (f32, f32)) -> Self {
+        let mouse_position = window.get_mouse_pos(MouseMode::Discard).map(|(x, y)| to_pixels(x, y));
+        let mouse_delta = match (mouse_position, previous.mouse_position) {
+            (Some((x, y)), Some((last_x, last_y))) => (x - last_x, y - last_y),
+            _ => (0.0, 0.0),
+        };
+        let mouse_buttons = [MouseButton::Left, MouseButton::Middle, MouseButton::Right]
+            .into_iter()
+            .filter(|button| window.get_mouse_down(*button))
+            .collect();
+
+        InputState {
+            keys_down: window.get_keys(),
+            keys_pressed: window.get_keys_pressed(minifb::KeyRepeat::No),
+            mouse_position,
+            mouse_delta,
+            mouse_buttons,
+            frame_time: 0.0,
+            per_press: false,
+        }
+    }
+}
+
+impl Input for InputState {
+    fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    fn mouse_position(&self) -> Option<(f32, f32)> {
+        self.mouse_position
+    }
+
+    fn mouse_delta(&self) -> (f32, f32) {
+        self.mouse_delta
+    }
+
+    fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+
+    fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    fn per_press(&self) -> bool {
+        self.per_press
+    }
+}
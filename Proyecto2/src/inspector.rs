@@ -0,0 +1,90 @@
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::input::Input;
+use crate::integrator::{shading_normal, wet_surface_color, whitted_light};
+use crate::light::LightKind;
+use crate::ray_debug::{hex, vector};
+use crate::render::camera_ray;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+// Inspector de píxeles: con el ratón sobre la imagen escribe en la consola qué se ve
+// en ese píxel (color final, objeto golpeado, distancia, normal, UV) y cuánto aporta
+// cada luz según el sombreado de Whitted. Solo vuelve a escribir cuando cambia el píxel.
+pub struct Inspector {
+    last: Option<(usize, usize)>,
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Inspector { last: None }
+    }
+
+    // Se llama después de renderizar el fotograma, con la entrada del mismo fotograma
+    pub fn update(&mut self, input: &dyn Input, framebuffer: &Framebuffer, scene: &Scene, camera: &Camera, sampler: &dyn Sampler) {
+        let pixel = input.mouse_position()
+            .filter(|(x, y)| *x >= 0.0 && *y >= 0.0)
+            .map(|(x, y)| (x as usize, y as usize))
+            .filter(|(x, y)| *x < framebuffer.width && *y < framebuffer.height);
+        if pixel == self.last {
+            return;
+        }
+        self.last = pixel;
+        if let Some((x, y)) = pixel {
+            eprintln!("{}", inspect_pixel(framebuffer, scene, camera, sampler, x, y));
+        }
+    }
+}
+
+// Informe de varias líneas del píxel (x, y) del último fotograma
+pub fn inspect_pixel(framebuffer: &Framebuffer, scene: &Scene, camera: &Camera, sampler: &dyn Sampler, x: usize, y: usize) -> String {
+    let final_color = framebuffer.buffer[y * framebuffer.width + x];
+    let mut report = format!("Píxel ({}, {}): #{:06X}", x, y, final_color);
+
+    // Centro del píxel, como los rayos de las muestras repartidas
+    let direction = camera_ray(camera, framebuffer.width, framebuffer.height, x as f32 + 0.5, y as f32 + 0.5);
+    let Some(hit) = scene.trace(&camera.eye, &direction) else {
+        report += "\n  cielo";
+        return report;
+    };
+    let intersect = scene.intersect_camera(&camera.eye, &direction);
+
+    let object = match hit.block {
+        Some(block) => format!("bloque ({}, {}, {})", block.x, block.y, block.z),
+        None => "objeto suelto".to_string(),
+    };
+    let group = scene.groups.get(hit.group).map_or("?", |group| group.name.as_str());
+    report += &format!("\n  {} del grupo '{}' a {:.3}", object, group, hit.distance);
+    report += &format!("\n  punto {}  normal {}", vector(&hit.point), vector(&hit.normal));
+    if let Some(face) = intersect.face {
+        report += &format!("  cara {:?}", face);
+    }
+    if let Some((u, v)) = intersect.uv {
+        report += &format!("  uv ({:.3}, {:.3})", u, v);
+    }
+
+    let base_color = wet_surface_color(&intersect, scene);
+    let normal = shading_normal(&intersect, scene);
+    let view_dir = (camera.eye - intersect.point).normalize();
+    report += &format!("\n  color base {}  ambiente {}", hex(base_color), hex(base_color * scene.ambient));
+
+    let mut sampler = sampler.clone_box();
+    sampler.start_pixel(x, y, 0);
+    for (index, light) in scene.lights_for(&intersect).enumerate() {
+        let kind = match light.kind {
+            LightKind::Point => "puntual",
+            LightKind::Directional { .. } => "direccional",
+            LightKind::Spot(_) => "foco",
+            LightKind::Area(_) => "de área",
+        };
+        let contribution = whitted_light(&intersect, light, base_color, &normal, &view_dir, scene, sampler.as_mut());
+        report += &format!("\n  luz {} ({} en {}): {}", index, kind, vector(&light.position), hex(contribution));
+    }
+    report
+}
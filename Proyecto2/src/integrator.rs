@@ -0,0 +1,722 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::light::{Light, LightKind, ShadowModel};
+use crate::ray_debug::{self, color_text, describe_hit};
+use crate::ray_intersect::Intersect;
+use crate::scene::Scene;
+use crate::sampler::Sampler;
+use crate::skybox::Skybox;
+use crate::snow::Snow;
+
+// Algoritmo de transporte de luz usado por `render` para obtener el color de
+// un rayo primario. Cada implementación decide cómo recorrer la escena.
+pub trait Integrator: Sync {
+    fn li(&self, ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color;
+}
+
+// Devuelve el integrador correspondiente al nombre usado en la línea de comandos
+pub fn from_name(name: &str) -> Option<Box<dyn Integrator>> {
+    match name {
+        "whitted" => Some(Box::new(Whitted)),
+        "path" => Some(Box::new(PathTracer)),
+        "ao" => Some(Box::new(AmbientOcclusion::new(16, 2.0))),
+        "normals" | "debug" => Some(Box::new(DebugIntegrator::new(DebugView::Normals))),
+        "depth" => Some(Box::new(DebugIntegrator::new(DebugView::Depth))),
+        "uv" => Some(Box::new(DebugIntegrator::new(DebugView::Uv))),
+        _ => None,
+    }
+}
+
+pub fn offset_origin(intersect: &Intersect, direction: &Vec3, bias: f32) -> Vec3 {
+    let offset = intersect.normal * bias;
+    if direction.dot(&intersect.normal) < 0.0 {
+        intersect.point - offset
+    } else {
+        intersect.point + offset
+    }
+}
+
+pub fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - 2.0 * incident.dot(normal) * normal
+}
+
+pub fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32, front_face: bool) -> Vec3 {
+    // La normal puede venir en cualquier sentido: se usa la del lado por el que llega el
+    // rayo, y si entra o sale del material lo dice `front_face`
+    let n_normal = if incident.dot(normal) > 0.0 { -normal } else { *normal };
+    let n_cosi = -incident.dot(&n_normal).clamp(-1.0, 1.0);
+    let eta = if front_face { 1.0 / eta_t } else { eta_t };
+
+    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
+
+    if k < 0.0 {
+        reflect(incident, &n_normal)
+    } else {
+        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+    }
+}
+
+// Con las superficies empapadas el color base se oscurece esta fracción, y se suma
+// este albedo especular con un brillo más concentrado
+const WET_DARKENING: f32 = 0.3;
+const WET_SPECULAR: f32 = 0.6;
+const WET_SHININESS: f32 = 40.0;
+// Altura hasta la que se busca un techo sobre una superficie nevada
+const SNOW_SHELTER_DISTANCE: f32 = 64.0;
+// Tamaño mínimo de la luz con el endurecimiento por contacto; en el contacto mismo la
+// sombra queda casi dura pero sin el escalón de un solo punto
+const MIN_PENUMBRA_SCALE: f32 = 0.05;
+// Semiángulo del cono de refracción con `transmission_roughness` 1
+const FROST_ANGLE: f32 = PI / 3.0;
+
+// Cuánto moja la lluvia la superficie: de lleno las caras que miran arriba, nada las de abajo
+fn wetness(intersect: &Intersect, scene: &Scene) -> f32 {
+    scene.weather.atmosphere.wetness * intersect.normal.y.max(0.0)
+}
+
+// Los rayos primarios (profundidad 0) no ven los objetos ocultos para la cámara
+fn primary_or_secondary(scene: &Scene, ray_origin: &Vec3, ray_direction: &Vec3, depth: u32) -> Intersect {
+    if depth == 0 {
+        scene.intersect_camera(ray_origin, ray_direction)
+    } else {
+        scene.intersect(ray_origin, ray_direction)
+    }
+}
+
+// Color base oscurecido por la lluvia en las caras que miran arriba
+pub fn wet_surface_color(intersect: &Intersect, scene: &Scene) -> Color {
+    surface_color(intersect, scene) * (1.0 - WET_DARKENING * wetness(intersect, scene))
+}
+
+// Color base de la superficie: la textura de la cara golpeada o la del material si
+// existen, o el difuso del material, con la nieve de la escena encima
+pub fn surface_color(intersect: &Intersect, scene: &Scene) -> Color {
+    let material = &intersect.material;
+    let face_texture = material.face_textures.as_ref().zip(intersect.face).and_then(|(faces, face)| faces.get(face));
+    let color = if let Some(texture) = face_texture.or(material.texture.as_ref()) {
+        let uv = intersect.uv.unwrap_or((0.0, 0.0));
+        // Tamaño del píxel sobre la superficie en bloques, lo que mide una repetición de la textura
+        let footprint = intersect.distance * scene.pixel_angle / scene.settings.world_scale;
+        texture.sample_footprint(uv.0, uv.1, scene.settings.texture_filter, footprint)
+    } else {
+        material.diffuse
+    };
+
+    match &scene.snow {
+        Some(snow) => {
+            let amount = snow_amount(snow, intersect, scene);
+            if amount <= 0.0 {
+                return color;
+            }
+            color * (1.0 - amount) + snow.color(&intersect.point, scene.settings.texture_filter) * amount
+        }
+        None => color,
+    }
+}
+
+// Nieve sobre el punto golpeado. No cae en materiales transparentes ni emisivos, ni
+// donde algo la tapa desde arriba (el suelo dentro de la casa)
+fn snow_amount(snow: &Snow, intersect: &Intersect, scene: &Scene) -> f32 {
+    let material = &intersect.material;
+    if material.refractive_index > 1.0 || material.emission_intensity > 0.0 {
+        return 0.0;
+    }
+    let amount = snow.amount(&intersect.point, &intersect.normal);
+    if amount <= 0.0 {
+        return 0.0;
+    }
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let origin = offset_origin(intersect, &up, scene.settings.bias());
+    if scene.occluder_distance(&origin, &up, SNOW_SHELTER_DISTANCE).is_some() {
+        return 0.0;
+    }
+    amount
+}
+
+// Normal usada para iluminar: la geométrica perturbada por el mapa de normales del
+// material, si lo tiene y el objeto aporta UV y base tangente, y por sus ondas en el
+// instante del reloj de la escena
+pub fn shading_normal(intersect: &Intersect, scene: &Scene) -> Vec3 {
+    let normal = match (&intersect.material.normal_map, intersect.uv, intersect.tangent) {
+        (Some(normal_map), Some((u, v)), Some((tangent, bitangent))) => {
+            let local = normal_map.sample_normal(u, v, scene.settings.texture_filter);
+            // v crece hacia abajo en la imagen, así que la y del mapa va contra la bitangente
+            (tangent * local.x - bitangent * local.y + intersect.normal * local.z).normalize()
+        }
+        _ => intersect.normal,
+    };
+    match &intersect.material.ripples {
+        Some(ripples) => ripples.perturb(&intersect.point, &normal, scene.clock.elapsed),
+        None => normal,
+    }
+}
+
+// Distancia al primer objeto entre el punto y `target`, si lo hay. `normal_bias` es la
+// separación en bloques a lo largo de la normal, a la que se suma la de la pendiente
+fn occluder_distance(intersect: &Intersect, target: &Vec3, scene: &Scene, normal_bias: f32) -> Option<f32> {
+    let light_dir = (target - intersect.point).normalize();
+    let light_distance = (target - intersect.point).magnitude();
+    let bias = scene.settings.shadow_bias(normal_bias, light_dir.dot(&intersect.normal));
+    let shadow_ray_origin = offset_origin(intersect, &light_dir, bias);
+
+    scene.occluder_distance(&shadow_ray_origin, &light_dir, light_distance)
+}
+
+// Fracción de la luz bloqueada (0 = iluminado, 1 = en sombra total)
+pub fn cast_shadow(intersect: &Intersect, light: &Light, scene: &Scene, sampler: &mut dyn Sampler) -> f32 {
+    if !light.casts_shadows {
+        return 0.0;
+    }
+    if let Some(shadow) = scene.lightmap.as_ref().and_then(|lightmap| lightmap.shadow(intersect, light)) {
+        return shadow;
+    }
+
+    let bias = light.shadow_bias.unwrap_or(scene.settings.shadow_normal_bias);
+
+    // Luz de área: fracción de puntos de su superficie tapados
+    if let LightKind::Area(area) = &light.kind {
+        let samples = scene.settings.shadow_samples.unwrap_or(area.samples).max(1);
+        let target_at = |u1: f32, u2: f32, scale: f32| light.position + (area.sample(&light.position, u1, u2) - light.position) * scale;
+        return soft_shadow(intersect, scene, sampler, samples, bias, target_at);
+    }
+
+    let target = light.shadow_target(&intersect.point);
+    match light.shadow {
+        ShadowModel::Hard => {
+            if occluder_distance(intersect, &target, scene, bias).is_some() { 1.0 } else { 0.0 }
+        }
+        ShadowModel::Falloff => {
+            let light_distance = (target - intersect.point).magnitude();
+            match occluder_distance(intersect, &target, scene, bias) {
+                Some(distance) => 1.0 - (distance / light_distance).min(1.0).powf(2.0),
+                None => 0.0,
+            }
+        }
+        ShadowModel::Soft { samples, radius } => {
+            let samples = scene.settings.shadow_samples.unwrap_or(samples).max(1);
+            let target_at = |u1: f32, u2: f32, scale: f32| target + sample_sphere(u1, u2) * (radius * scale);
+            soft_shadow(intersect, scene, sampler, samples, bias, target_at)
+        }
+    }
+}
+
+// Fracción de `samples` puntos de la luz tapados. `target_at(u1, u2, scale)` da un
+// punto de la luz encogida a `scale` veces su tamaño, que baja con el endurecimiento
+// por contacto
+fn soft_shadow(intersect: &Intersect, scene: &Scene, sampler: &mut dyn Sampler, samples: u32, bias: f32, target_at: impl Fn(f32, f32, f32) -> Vec3) -> f32 {
+    let scale = penumbra_scale(intersect, scene, sampler, samples, bias, &target_at);
+    let mut blocked = 0;
+    for _ in 0..samples {
+        let (u1, u2) = sampler.next_2d();
+        if occluder_distance(intersect, &target_at(u1, u2, scale), scene, bias).is_some() {
+            blocked += 1;
+        }
+    }
+    blocked as f32 / samples as f32
+}
+
+// Tamaño relativo de la luz para el endurecimiento por contacto: primero se busca con
+// una cuarta parte de las muestras, sobre la luz entera, a qué distancia media están
+// los oclusores, y la luz se encoge en proporción a esa distancia dentro del alcance
+// de `contact_hardening`. Sin oclusores (o desactivado) la luz queda igual
+fn penumbra_scale(intersect: &Intersect, scene: &Scene, sampler: &mut dyn Sampler, samples: u32, bias: f32, target_at: &impl Fn(f32, f32, f32) -> Vec3) -> f32 {
+    let reach = scene.settings.contact_hardening * scene.settings.world_scale;
+    if reach <= 0.0 {
+        return 1.0;
+    }
+    let (mut total, mut found) = (0.0, 0);
+    for _ in 0..(samples / 4).max(1) {
+        let (u1, u2) = sampler.next_2d();
+        if let Some(distance) = occluder_distance(intersect, &target_at(u1, u2, 1.0), scene, bias) {
+            total += distance;
+            found += 1;
+        }
+    }
+    if found == 0 {
+        return 1.0;
+    }
+    (total / found as f32 / reach).clamp(MIN_PENUMBRA_SCALE, 1.0)
+}
+
+// Fracción de `samples` rayos del hemisferio de la normal, repartidos según el coseno,
+// que chocan con algo a menos de `radius` (0 = hemisferio libre, 1 = rincón cerrado)
+pub fn occlusion(intersect: &Intersect, scene: &Scene, samples: u32, radius: f32, sampler: &mut dyn Sampler) -> f32 {
+    let samples = samples.max(1);
+    let radius = radius * scene.settings.world_scale;
+    let mut occluded = 0;
+    for _ in 0..samples {
+        let (u1, u2) = sampler.next_2d();
+        let dir = sample_cosine_hemisphere(&intersect.normal, u1, u2);
+        let origin = offset_origin(intersect, &dir, scene.settings.bias());
+        if scene.occluder_distance(&origin, &dir, radius).is_some() {
+            occluded += 1;
+        }
+    }
+    occluded as f32 / samples as f32
+}
+
+// Punto uniforme sobre la esfera unitaria
+fn sample_sphere(u1: f32, u2: f32) -> Vec3 {
+    let z = 1.0 - 2.0 * u1;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Dirección aleatoria en el hemisferio de la normal con densidad proporcional al coseno
+pub fn sample_cosine_hemisphere(normal: &Vec3, u1: f32, u2: f32) -> Vec3 {
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    let local = Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - u1).max(0.0).sqrt());
+    to_world(normal, &local)
+}
+
+// Pasa `local`, con z a lo largo de `normal`, a una base ortonormal alrededor de ella
+fn to_world(normal: &Vec3, local: &Vec3) -> Vec3 {
+    let helper = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+
+    (tangent * local.x + bitangent * local.y + normal * local.z).normalize()
+}
+
+// Reflectancia de Fresnel con la aproximación de Schlick. `front_face` indica si el
+// rayo entra en el material; el sentido de la normal no importa
+pub fn fresnel_schlick(incident: &Vec3, normal: &Vec3, refractive_index: f32, front_face: bool) -> f32 {
+    let (n1, n2) = if front_face { (1.0, refractive_index) } else { (refractive_index, 1.0) };
+    let cos_incident = incident.dot(normal).clamp(-1.0, 1.0).abs();
+
+    // Al pasar a un medio menos denso se usa el ángulo transmitido, y puede haber reflexión total
+    let cos = if n1 > n2 {
+        let sin_t = n1 / n2 * (1.0 - cos_incident * cos_incident).max(0.0).sqrt();
+        if sin_t >= 1.0 {
+            return 1.0;
+        }
+        (1.0 - sin_t * sin_t).sqrt()
+    } else {
+        cos_incident
+    };
+
+    let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+}
+
+// Mezcla de los rayos reflejado y refractado de un material transparente:
+// la reflexión pesa `kr * albedo[2]` y la refracción `(1 - kr) * albedo[3]`
+fn fresnel_blend(integrator: &dyn Integrator, intersect: &Intersect, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+    let material = &intersect.material;
+    // Con la normal de sombreado, para que las ondas del agua deformen lo que se ve a través y reflejado
+    let normal = shading_normal(intersect, scene);
+    let kr = fresnel_schlick(ray_direction, &normal, material.refractive_index, intersect.front_face);
+    let mut color = Color::black();
+    ray_debug::log(depth, || format!(
+        "transparente: Fresnel kr {:.3}, refracta {:.3} y refleja {:.3}",
+        kr, (1.0 - kr) * material.albedo[3], kr * material.albedo[2],
+    ));
+
+    if kr < 1.0 && material.albedo[3] > 0.0 {
+        let refracted_dir = frost(&refract(ray_direction, &normal, material.refractive_index, intersect.front_face), &normal, material.transmission_roughness, sampler);
+        let refracted_origin = offset_origin(intersect, &refracted_dir, scene.settings.bias());
+        let refracted_color = integrator.li(&refracted_origin, &refracted_dir, scene, depth + 1, sampler);
+        color += refracted_color * ((1.0 - kr) * material.albedo[3]);
+    }
+
+    if kr > 0.0 && material.albedo[2] > 0.0 {
+        color += mirror(integrator, intersect, &normal, ray_direction, scene, depth, sampler) * (kr * material.albedo[2]);
+    }
+
+    color
+}
+
+// Desvía la dirección refractada a un punto del cono de semiángulo `roughness` por
+// `FROST_ANGLE` a su alrededor, sin dejar que cruce al otro lado de la superficie.
+// Con una muestra por rebote el esmerilado sale con ruido que se promedia entre las
+// muestras por píxel o los fotogramas del modo progresivo
+fn frost(direction: &Vec3, normal: &Vec3, roughness: f32, sampler: &mut dyn Sampler) -> Vec3 {
+    if roughness <= 0.0 {
+        return *direction;
+    }
+    let (u1, u2) = sampler.next_2d();
+    let cos_max = (roughness.min(1.0) * FROST_ANGLE).cos();
+    let cos = 1.0 - u1 * (1.0 - cos_max);
+    let sin = (1.0 - cos * cos).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    let frosted = to_world(direction, &Vec3::new(sin * phi.cos(), sin * phi.sin(), cos));
+    // Si el cono pasa del plano de la cara, se refleja en él para seguir transmitiendo
+    let side = direction.dot(normal);
+    if frosted.dot(normal) * side < 0.0 {
+        (frosted - 2.0 * frosted.dot(normal) * normal).normalize()
+    } else {
+        frosted
+    }
+}
+
+// Color que llega por la reflexión especular perfecta del rayo en `intersect` sobre
+// `normal`. En los materiales opacos `albedo[2]` es la fracción que se refleja como un espejo.
+fn mirror(integrator: &dyn Integrator, intersect: &Intersect, normal: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+    let reflected_dir = reflect(ray_direction, normal).normalize();
+    let reflected_origin = offset_origin(intersect, &reflected_dir, scene.settings.bias());
+    integrator.li(&reflected_origin, &reflected_dir, scene, depth + 1, sampler)
+}
+
+// Mezcla el color de `surface` con lo que se ve detrás de ella según la opacidad del
+// canal alfa del material en el punto; sin alfa o en el último rebote queda opaca
+fn see_through(integrator: &dyn Integrator, intersect: &Intersect, surface: Color, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+    let opacity = intersect.material.opacity(intersect.uv);
+    if opacity >= 1.0 || depth >= scene.settings.max_depth {
+        return surface;
+    }
+    ray_debug::log(depth, || format!("semitransparente: opacidad {:.3}", opacity));
+    let origin = offset_origin(intersect, ray_direction, scene.settings.bias());
+    let behind = integrator.li(&origin, ray_direction, scene, depth + 1, sampler);
+    surface * opacity + behind * (1.0 - opacity)
+}
+
+// Trazado clásico de Whitted: iluminación directa con sombras y refracción recursiva
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn li(&self, ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+        if depth > scene.settings.max_depth {
+            return scene.background(ray_direction);
+        }
+
+        let intersect = primary_or_secondary(scene, ray_origin, ray_direction, depth);
+        if !intersect.is_intersecting {
+            let sky = scene.background(ray_direction);
+            ray_debug::log(depth, || format!("cielo {}", color_text(sky)));
+            return sky;
+        }
+        ray_debug::log(depth, || describe_hit(&intersect, scene));
+
+        let material = &intersect.material;
+        let mut base_color = wet_surface_color(&intersect, scene);
+        let mut final_color;
+
+        let view_dir = (ray_origin - intersect.point).normalize();
+        let normal = shading_normal(&intersect, scene);
+
+        // Si el material tiene un índice de refracción, calculamos la refracción.
+        // En el último nivel de recursión no se lanza otro rayo: la superficie
+        // se sombrea como opaca en lugar de devolver el color del cielo
+        if material.refractive_index > 1.0 && depth < scene.settings.max_depth {
+            let transmitted = fresnel_blend(self, &intersect, ray_direction, scene, depth, sampler);
+            final_color = base_color * (material.albedo[0] * scene.ambient) + transmitted;
+        } else {
+            ray_debug::log(depth, || format!("opaco: color base {}, ambiente {:.3}", color_text(base_color), scene.ambient));
+            // El color base hace de luz ambiental: las esquinas y rendijas reciben menos
+            let settings = &scene.settings;
+            if settings.ao_samples > 0 {
+                let occluded = occlusion(&intersect, scene, settings.ao_samples, settings.ao_radius, sampler);
+                base_color = base_color * (1.0 - settings.ao_strength * occluded);
+            }
+            // De noche queda poca luz ambiental; las luces iluminan el color base completo
+            final_color = base_color * scene.ambient;
+            // Luz que rebota dentro de la casa, aproximada con las sondas más cercanas
+            if let Some(indirect) = scene.light_probes.as_ref().and_then(|probes| probes.irradiance(&intersect.point, &normal)) {
+                final_color += (base_color * indirect) * material.albedo[0];
+            }
+
+            for (index, light) in scene.lights_for(&intersect).enumerate() {
+                let contribution = whitted_light(&intersect, light, base_color, &normal, &view_dir, scene, sampler);
+                ray_debug::log(depth, || format!("luz {}: {}", index, color_text(contribution)));
+                final_color += contribution;
+            }
+
+            // Los materiales pulidos (metal, piedra pulida) reflejan parte de la escena
+            let reflectivity = material.albedo[2].clamp(0.0, 1.0);
+            if reflectivity > 0.0 && depth < settings.max_depth {
+                ray_debug::log(depth, || format!("refleja {:.3}", reflectivity));
+                final_color = final_color * (1.0 - reflectivity) + mirror(self, &intersect, &intersect.normal, ray_direction, scene, depth, sampler) * reflectivity;
+            }
+        }
+
+        let surface = final_color + material.emission * material.emission_intensity;
+        let color = scene.fog(see_through(self, &intersect, surface, ray_direction, scene, depth, sampler), ray_direction, intersect.distance);
+        ray_debug::log(depth, || format!("devuelve {}", color_text(color)));
+        color
+    }
+}
+
+// Difuso más especular de Phong que `light` aporta al punto de `intersect`, ya con su
+// sombra. `base_color` es el color de la superficie y `view_dir` va del punto al ojo.
+pub fn whitted_light(intersect: &Intersect, light: &Light, base_color: Color, normal: &Vec3, view_dir: &Vec3, scene: &Scene, sampler: &mut dyn Sampler) -> Color {
+    let material = &intersect.material;
+    let wet = wetness(intersect, scene);
+    let (light_dir, intensity) = light.illuminate(&intersect.point, scene.settings.world_scale);
+    let reflect_dir = reflect(&-light_dir, normal).normalize();
+    let shadow_intensity = cast_shadow(intersect, light, scene, sampler);
+    let light_intensity = intensity * (1.0 - shadow_intensity);
+
+    let diffuse_intensity = normal.dot(&light_dir).clamp(0.0, 1.0);
+    let diffuse = (base_color * light.color) * material.albedo[0] * diffuse_intensity * light_intensity;
+
+    let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(material.specular + WET_SHININESS * wet);
+    let specular = light.color * (material.albedo[1] + WET_SPECULAR * wet) * specular_intensity * light_intensity;
+
+    diffuse + specular
+}
+
+// Path tracer sencillo: luz directa en cada rebote más un rebote difuso aleatorio
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn li(&self, ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+        if depth > scene.settings.max_depth {
+            return scene.background(ray_direction);
+        }
+
+        let intersect = primary_or_secondary(scene, ray_origin, ray_direction, depth);
+        self.shade(intersect, ray_direction, scene, depth, sampler)
+    }
+}
+
+impl PathTracer {
+    // Color del impacto `intersect` del rayo, ya buscado; el rebote difuso reutiliza
+    // aquí la intersección con la que decide si su dirección ya está muestreada
+    fn shade(&self, intersect: Intersect, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+        if !intersect.is_intersecting {
+            let sky = scene.background(ray_direction);
+            ray_debug::log(depth, || format!("cielo {}", color_text(sky)));
+            return sky;
+        }
+        ray_debug::log(depth, || describe_hit(&intersect, scene));
+
+        let material = &intersect.material;
+        let base_color = wet_surface_color(&intersect, scene);
+        let fog = |color: Color| {
+            let color = scene.fog(color, ray_direction, intersect.distance);
+            ray_debug::log(depth, || format!("devuelve {}", color_text(color)));
+            color
+        };
+
+        let can_recurse = depth < scene.settings.max_depth;
+
+        if material.refractive_index > 1.0 && can_recurse {
+            return fog(base_color * material.albedo[0] + fresnel_blend(self, &intersect, ray_direction, scene, depth, sampler));
+        }
+
+        // Iluminación directa de las luces puntuales. La emisión propia solo se ve
+        // desde la cámara: en los rebotes ya la aportan las luces de los bloques emisivos
+        let normal = shading_normal(&intersect, scene);
+        let mut direct = if depth == 0 { material.emission * material.emission_intensity } else { Color::black() };
+        for light in scene.lights_for(&intersect) {
+            let (light_dir, intensity) = light.illuminate(&intersect.point, scene.settings.world_scale);
+            let shadow_intensity = cast_shadow(&intersect, light, scene, sampler);
+            let diffuse_intensity = normal.dot(&light_dir).clamp(0.0, 1.0);
+            direct += (base_color * light.color) * material.albedo[0] * diffuse_intensity * intensity * (1.0 - shadow_intensity);
+        }
+
+        // Luz del cielo muestreada directamente a través de un portal elegido al azar o,
+        // sin portales, en una dirección elegida según el brillo del cielo
+        if !scene.portals.is_empty() {
+            direct += sky_through_portal(&intersect, scene, sampler) * (base_color * material.albedo[0]);
+        } else if let Some(skybox) = &scene.skybox {
+            direct += sky_from_environment(&intersect, skybox, scene, sampler) * (base_color * material.albedo[0]);
+        }
+
+        // En el último nivel no hay rebote difuso, pero el espejo y la transparencia se
+        // mezclan igual: sus rayos ven el cielo en lugar de seguir rebotando
+        let indirect = if can_recurse {
+            (base_color * self.diffuse_bounce(&intersect, scene, depth, sampler)) * material.albedo[0]
+        } else {
+            Color::black()
+        };
+
+        let mut color = direct + indirect;
+        let reflectivity = material.albedo[2].clamp(0.0, 1.0);
+        if reflectivity > 0.0 {
+            let reflected = mirror(self, &intersect, &intersect.normal, ray_direction, scene, depth, sampler);
+            color = color * (1.0 - reflectivity) + reflected * reflectivity;
+        }
+        fog(see_through(self, &intersect, color, ray_direction, scene, depth, sampler))
+    }
+
+    // Luz que llega a `intersect` por un rebote difuso muestreado según el coseno. Las
+    // direcciones que salen por un portal o, sin portales, al cielo ya las cubre el
+    // muestreo directo del cielo y no aportan nada aquí
+    fn diffuse_bounce(&self, intersect: &Intersect, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+        let (u1, u2) = sampler.next_2d();
+        let bounce_dir = sample_cosine_hemisphere(&intersect.normal, u1, u2);
+        let bounce_origin = offset_origin(intersect, &bounce_dir, scene.settings.bias());
+
+        let bounce = scene.intersect(&bounce_origin, &bounce_dir);
+        if leaves_through_portal(&bounce, &bounce_origin, &bounce_dir, scene) || escapes_to_environment(&bounce, scene) {
+            return Color::black();
+        }
+        self.shade(bounce, &bounce_dir, scene, depth + 1, sampler)
+    }
+}
+
+// Estimación de la luz del cielo que entra por los portales hacia una superficie difusa
+// (ya dividida por π), a partir de un punto uniforme sobre uno de ellos
+fn sky_through_portal(intersect: &Intersect, scene: &Scene, sampler: &mut dyn Sampler) -> Color {
+    let count = scene.portals.len();
+    let index = ((sampler.next_1d() * count as f32) as usize).min(count - 1);
+    let portal = &scene.portals[index];
+
+    let (u1, u2) = sampler.next_2d();
+    let target = portal.sample(u1, u2);
+    let to_portal = target - intersect.point;
+    let distance_sq = to_portal.magnitude_squared();
+    let dir = to_portal.normalize();
+
+    let cos_surface = intersect.normal.dot(&dir);
+    let cos_portal = portal.normal().dot(&dir).abs();
+    if cos_surface <= 0.0 || distance_sq <= 0.0 {
+        return Color::black();
+    }
+
+    let origin = offset_origin(intersect, &dir, scene.settings.bias());
+    if scene.occluder_distance(&origin, &dir, distance_sq.sqrt()).is_some() {
+        return Color::black();
+    }
+
+    // Muestreo por área: pdf en ángulo sólido = d² / (A · cos_portal), y un portal de `count`
+    let weight = cos_surface * cos_portal * portal.area() * count as f32 / (PI * distance_sq);
+    scene.background(&dir) * weight
+}
+
+// Estimación de la luz del cielo hacia una superficie difusa (ya dividida por π) con
+// una dirección elegida según la distribución de brillo del cubemap: con pocas muestras
+// el sol y las zonas claras aportan casi siempre en lugar de salir por casualidad
+fn sky_from_environment(intersect: &Intersect, skybox: &Skybox, scene: &Scene, sampler: &mut dyn Sampler) -> Color {
+    let u1 = sampler.next_1d();
+    let (u2, u3) = sampler.next_2d();
+    let (dir, pdf) = skybox.sample_direction(u1, u2, u3);
+    let cos_surface = intersect.normal.dot(&dir);
+    if cos_surface <= 0.0 || pdf <= 0.0 {
+        return Color::black();
+    }
+
+    let origin = offset_origin(intersect, &dir, scene.settings.bias());
+    if scene.occluder_distance(&origin, &dir, f32::INFINITY).is_some() {
+        return Color::black();
+    }
+    scene.background(&dir) * (cos_surface / (PI * pdf))
+}
+
+// Si el rayo, cuyo impacto con la escena es `hit`, sale al cielo sin chocar con nada en
+// una escena cuyo cielo se muestrea con `sky_from_environment`
+fn escapes_to_environment(hit: &Intersect, scene: &Scene) -> bool {
+    scene.portals.is_empty() && scene.skybox.is_some() && !hit.is_intersecting
+}
+
+// Si el rayo cruza algún portal antes de `hit`, su impacto con la escena
+fn leaves_through_portal(hit: &Intersect, origin: &Vec3, dir: &Vec3, scene: &Scene) -> bool {
+    let portal_distance = scene.portals.iter()
+        .filter_map(|portal| portal.intersect(origin, dir))
+        .fold(f32::INFINITY, f32::min);
+    portal_distance.is_finite() && hit.distance > portal_distance
+}
+
+// Solo oclusión ambiental: blanco donde el hemisferio está libre, oscuro en esquinas
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub radius: f32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: u32, radius: f32) -> Self {
+        AmbientOcclusion { samples, radius }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn li(&self, ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, sampler: &mut dyn Sampler) -> Color {
+        let intersect = primary_or_secondary(scene, ray_origin, ray_direction, depth);
+        if !intersect.is_intersecting {
+            return Color::new(255, 255, 255);
+        }
+
+        let visibility = 1.0 - occlusion(&intersect, scene, self.samples, self.radius, sampler);
+        Color::new(255, 255, 255) * visibility
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    Normals,
+    Depth,
+    Uv,
+}
+
+// Visualiza datos geométricos del primer impacto en lugar de iluminación
+pub struct DebugIntegrator {
+    pub view: DebugView,
+}
+
+impl DebugIntegrator {
+    pub fn new(view: DebugView) -> Self {
+        DebugIntegrator { view }
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn li(&self, ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, depth: u32, _sampler: &mut dyn Sampler) -> Color {
+        let intersect = primary_or_secondary(scene, ray_origin, ray_direction, depth);
+        if !intersect.is_intersecting {
+            return Color::black();
+        }
+
+        match self.view {
+            DebugView::Normals => {
+                let n = (intersect.normal + Vec3::new(1.0, 1.0, 1.0)) * 0.5;
+                Color::new((n.x * 255.0) as u8, (n.y * 255.0) as u8, (n.z * 255.0) as u8)
+            }
+            DebugView::Depth => {
+                // Más cerca = más claro, normalizado a 20 bloques
+                let value = 1.0 - (intersect.distance / (20.0 * scene.settings.world_scale)).min(1.0);
+                Color::new(255, 255, 255) * value
+            }
+            DebugView::Uv => {
+                let (u, v) = intersect.uv.unwrap_or((0.0, 0.0));
+                Color::new((u * 255.0) as u8, (v * 255.0) as u8, 0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::sampler::IndependentSampler;
+    use crate::settings::RenderSettings;
+    use crate::texture::Texture;
+    use std::sync::Arc;
+
+    // Espejo perfecto sin color difuso ni luces bajo un cielo de un solo color: todo lo
+    // que se ve en él es el reflejo del cielo
+    fn mirror_under_sky(max_depth: u32) -> Color {
+        let sky = Color::new(90, 140, 210);
+        let mirror = Arc::new(Material::new(Color::black(), 0.0, [0.0, 0.0, 1.0, 0.0], 0.0, None));
+        let cube = Cube::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0), mirror);
+        let settings = RenderSettings { max_depth, ..RenderSettings::default() };
+        let mut scene = Scene::new(vec![Box::new(cube)], Vec::new(), settings);
+        scene.rebuild_bvh();
+        scene.skybox = Some(Skybox::new(std::array::from_fn(|_| Texture::new(vec![sky], 1, 1))));
+
+        let origin = Vec3::new(0.0, 3.0, 1.0);
+        let direction = Vec3::new(0.0, -1.0, -0.5).normalize();
+        let color = PathTracer.li(&origin, &direction, &scene, 0, &mut IndependentSampler::new(0));
+        let expected = scene.background(&reflect(&direction, &Vec3::new(0.0, 1.0, 0.0)));
+        assert!(expected.luminance() > 0.0);
+        color * (1.0 / expected.luminance())
+    }
+
+    #[test]
+    fn mirror_under_a_skybox_reflects_the_sky() {
+        // El rebote difuso sale al cielo, que ya cuenta el muestreo directo, pero el
+        // espejo se mezcla igual
+        assert!((mirror_under_sky(3).luminance() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn mirror_reflects_the_sky_in_the_last_bounce() {
+        assert!((mirror_under_sky(0).luminance() - 1.0).abs() < 1e-3);
+    }
+}
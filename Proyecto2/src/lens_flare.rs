@@ -0,0 +1,98 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+
+// Reflejos fantasma sobre la línea que va de la luz al centro de la imagen: posición
+// (0 es la luz, 1 el centro, más allá queda al otro lado), radio relativo al alto de
+// la imagen, intensidad y tinte
+const GHOSTS: [(f32, f32, f32, u32); 5] = [
+    (0.5, 0.03, 0.25, 0xFFDC96),
+    (1.2, 0.05, 0.2, 0x78C8FF),
+    (1.5, 0.02, 0.35, 0xFFA05A),
+    (1.8, 0.09, 0.12, 0xA0FFB4),
+    (2.2, 0.04, 0.2, 0xC896FF),
+];
+// Radio del halo alrededor de la luz, relativo al alto de la imagen
+const GLOW_RADIUS: f32 = 0.18;
+// Largo de la estela horizontal, relativo al ancho de la imagen
+const STREAK_LENGTH: f32 = 0.35;
+
+// Una luz vista directamente por la cámara, en píxeles de la imagen
+struct VisibleLight {
+    x: f32,
+    y: f32,
+    color: Color,
+}
+
+// Destello de lente en espacio de pantalla: halo, estela horizontal y reflejos
+// fantasma para cada luz que la cámara ve sin obstáculos. Se suma sobre la imagen ya
+// trazada, así que no depende del integrador. `strength` escala todo el efecto.
+pub fn apply_lens_flare(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, strength: f32) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let lights = visible_lights(scene, camera, width, height);
+    if lights.is_empty() || strength <= 0.0 {
+        return;
+    }
+
+    let (width_f, height_f) = (width as f32, height as f32);
+    let (center_x, center_y) = (width_f * 0.5, height_f * 0.5);
+    let glow_radius = GLOW_RADIUS * height_f;
+    let streak_length = STREAK_LENGTH * width_f;
+
+    framebuffer.par_hdr_rows_mut().for_each(|(y, row)| {
+        let py = y as f32 + 0.5;
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let px = x as f32 + 0.5;
+            let mut flare = Color::black();
+            for light in &lights {
+                let (dx, dy) = (px - light.x, py - light.y);
+
+                // Halo que cae con el cuadrado de la distancia
+                let glow = (1.0 - (dx * dx + dy * dy).sqrt() / glow_radius).max(0.0);
+                let mut amount = glow * glow;
+                // Estela fina en horizontal
+                let streak = (1.0 - dx.abs() / streak_length).max(0.0) * (1.0 - dy.abs() / 1.5).max(0.0);
+                amount += streak * 0.6;
+                flare += light.color * (amount * strength);
+
+                for (position, radius, intensity, tint) in GHOSTS {
+                    let ghost_x = light.x + (center_x - light.x) * position;
+                    let ghost_y = light.y + (center_y - light.y) * position;
+                    let distance = ((px - ghost_x).powi(2) + (py - ghost_y).powi(2)).sqrt();
+                    // Disco de borde suave
+                    let disc = ((radius * height_f - distance) / (radius * height_f * 0.3)).clamp(0.0, 1.0);
+                    if disc > 0.0 {
+                        flare += light.color * Color::from_hex(tint) * (disc * intensity * strength);
+                    }
+                }
+            }
+            for (channel, value) in pixel.iter_mut().zip(flare.linear()) {
+                *channel += value;
+            }
+        }
+    });
+}
+
+// Luces activas delante de la cámara, dentro de la imagen y sin nada entre medio
+fn visible_lights(scene: &Scene, camera: &Camera, width: usize, height: usize) -> Vec<VisibleLight> {
+    scene.active_lights()
+        .filter_map(|light| {
+            let position = light.shadow_target(&camera.eye);
+            let (x, y) = camera.project(&position, width, height)?;
+            if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+                return None;
+            }
+            let offset: Vec3 = position - camera.eye;
+            let distance = offset.magnitude();
+            let hit = scene.intersect_camera(&camera.eye, &(offset / distance));
+            if hit.is_intersecting && hit.distance < distance {
+                return None;
+            }
+            Some(VisibleLight { x, y, color: light.color * light.intensity.min(1.0) })
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+// Biblioteca del trazador: escena, mundo de bloques, integradores y utilidades.
+// El binario (`main.rs`) solo arma el diorama y conecta la entrada con `App`.
+pub mod framebuffer;
+pub mod ray_intersect;
+pub mod cube;
+pub mod block_shape;
+pub mod color;
+pub mod tonemap;
+pub mod fog;
+pub mod camera;
+pub mod light;
+pub mod material;
+pub mod texture;
+pub mod procedural;
+pub mod texture_watch;
+pub mod texture_pack;
+pub mod terminal;
+pub mod rng;
+pub mod integrator;
+pub mod sampler;
+pub mod config;
+pub mod settings;
+pub mod scene;
+pub mod world;
+pub mod prefab;
+pub mod worldgen;
+pub mod bench_scene;
+pub mod noise;
+pub mod selection;
+pub mod bvh;
+pub mod voxel_grid;
+pub mod sphere;
+pub mod mesh;
+pub mod plane;
+pub mod billboard;
+pub mod decal;
+pub mod torch;
+pub mod scene_file;
+pub mod light_profile;
+pub mod portal;
+pub mod lightmap;
+pub mod light_probes;
+pub mod render;
+pub mod ray_debug;
+pub mod lens_flare;
+pub mod post;
+pub mod debug_draw;
+pub mod app;
+pub mod skybox;
+pub mod sky;
+pub mod night_sky;
+pub mod sun;
+pub mod weather;
+pub mod snow;
+pub mod water;
+pub mod particles;
+pub mod input;
+pub mod controls;
+pub mod inspector;
+pub mod pacing;
+pub mod clock;
+pub mod animation;
+#[cfg(feature = "winit-backend")]
+pub mod winit_window;
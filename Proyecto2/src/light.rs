@@ -1,19 +1,229 @@
-
 use nalgebra_glm::Vec3;
+use std::sync::Arc;
 use crate::color::Color;
+use crate::light_profile::LightProfile;
+use crate::scene::DEFAULT_GROUP;
+
+// Cómo calcula cada luz la sombra de los objetos que la tapan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadowModel {
+    // Sombra binaria clásica: cualquier oclusor bloquea toda la luz
+    Hard,
+    // Atenuación según la distancia al oclusor (comportamiento original)
+    Falloff,
+    // Varias muestras sobre una esfera alrededor de la luz para obtener penumbra
+    Soft { samples: u32, radius: f32 },
+}
+
+impl ShadowModel {
+    // Acepta los modelos por nombre, los presets de calidad `low`, `medium` y `high`,
+    // y `soft:<muestras>:<radio>` para elegir la penumbra a mano
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hard" | "low" => Some(ShadowModel::Hard),
+            "falloff" => Some(ShadowModel::Falloff),
+            "soft" | "medium" => Some(ShadowModel::Soft { samples: 8, radius: 0.5 }),
+            "high" => Some(ShadowModel::Soft { samples: 32, radius: 0.75 }),
+            _ => {
+                let mut parts = name.strip_prefix("soft:")?.split(':');
+                let samples = parts.next()?.parse().ok()?;
+                let radius = parts.next()?.parse().ok()?;
+                parts.next().is_none().then_some(ShadowModel::Soft { samples, radius })
+            }
+        }
+    }
+}
+
+// Emisor rectangular centrado en la posición de la luz. Las sombras se calculan
+// muestreando puntos sobre su superficie, lo que produce penumbra.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaLight {
+    pub edge_u: Vec3,
+    pub edge_v: Vec3,
+    pub samples: u32,
+}
+
+impl AreaLight {
+    pub fn new(edge_u: Vec3, edge_v: Vec3, samples: u32) -> Self {
+        AreaLight { edge_u, edge_v, samples }
+    }
+
+    // Punto del rectángulo para (u1, u2) en [0, 1)²
+    pub fn sample(&self, center: &Vec3, u1: f32, u2: f32) -> Vec3 {
+        center + self.edge_u * (u1 - 0.5) + self.edge_v * (u2 - 0.5)
+    }
+}
+
+// Cono de luz que apunta en `direction`: plena intensidad hasta `inner_angle` del eje y
+// se apaga hasta `outer_angle` (en grados) siguiendo una curva de exponente `falloff`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub direction: Vec3,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub falloff: f32,
+}
+
+impl SpotLight {
+    pub fn new(direction: Vec3, inner_angle: f32, outer_angle: f32) -> Self {
+        SpotLight { direction, inner_angle, outer_angle, falloff: 1.0 }
+    }
+
+    // Fracción de la intensidad (0 a 1) en la dirección `to_point`, desde la luz hacia el punto
+    pub fn factor(&self, to_point: &Vec3) -> f32 {
+        let cos_angle = self.direction.normalize().dot(&to_point.normalize());
+        let cos_inner = self.inner_angle.to_radians().cos();
+        let cos_outer = self.outer_angle.max(self.inner_angle).to_radians().cos();
+        // Sin transición entre los dos conos el borde es duro
+        if cos_inner - cos_outer < 1e-6 {
+            return if cos_angle >= cos_outer { 1.0 } else { 0.0 };
+        }
+        ((cos_angle - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0).powf(self.falloff.max(0.0))
+    }
+}
+
+// Caída de la intensidad con la distancia d: 1 / (constant + linear·d + quadratic·d²)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Attenuation {
+    pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+        Attenuation { constant, linear, quadratic }
+    }
+
+    // Sin caída: la luz ilumina igual a cualquier distancia
+    pub fn none() -> Self {
+        Attenuation::new(1.0, 0.0, 0.0)
+    }
+
+    // `none` o los tres coeficientes separados por comas, por ejemplo `1,0.09,0.032`
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name == "none" {
+            return Some(Attenuation::none());
+        }
+        let coefficients: Vec<f32> = name.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()?;
+        match coefficients[..] {
+            [constant, linear, quadratic] => Some(Attenuation::new(constant, linear, quadratic)),
+            _ => None,
+        }
+    }
+
+    pub fn factor(&self, distance: f32) -> f32 {
+        let denominator = self.constant + self.linear * distance + self.quadratic * distance * distance;
+        // Con coeficientes pequeños cerca de la luz no se amplifica la intensidad
+        1.0 / denominator.max(1.0)
+    }
+}
+
+// Distancia a la que se coloca una luz direccional para los rayos de sombra y los destellos
+const DIRECTIONAL_DISTANCE: f32 = 1.0e4;
+
+// Forma en que emite cada luz. Lo común a todas (color, intensidad, grupos, sombras...)
+// está en `Light`, así que la lista de luces de la escena puede mezclar tipos.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    // Emite desde `position` en todas direcciones
+    Point,
+    // Rayos paralelos que viajan en `direction`, como los del sol; no depende de la
+    // posición ni se atenúa con la distancia
+    Directional { direction: Vec3 },
+    // Solo ilumina dentro de su cono
+    Spot(SpotLight),
+    // Las sombras muestrean su superficie en lugar de usar `shadow`
+    Area(AreaLight),
+}
 
 pub struct Light {
+    pub kind: LightKind,
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    pub shadow: ShadowModel,
+    // Sin sombras la luz no lanza rayos de sombra: para luces de relleno baratas
+    pub casts_shadows: bool,
+    // Separación en bloques a lo largo de la normal del origen de los rayos de sombra; si
+    // es None se usa `shadow_normal_bias` de `RenderSettings`. La de la pendiente se suma igual
+    pub shadow_bias: Option<f32>,
+    pub group: usize,
+    // Grupos de objetos que esta luz no ilumina (light linking)
+    pub excluded_groups: Vec<usize>,
+    // Distribución angular de la intensidad; sin perfil emite igual en todas direcciones
+    pub profile: Option<Arc<LightProfile>>,
+    // Distancia a la que la luz se apaga del todo; sin rango ilumina igual a cualquier distancia
+    pub range: Option<f32>,
+    pub attenuation: Attenuation,
 }
 
 impl Light {
     pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
         Light {
+            kind: LightKind::Point,
             position,
             color,
             intensity,
+            shadow: ShadowModel::Falloff,
+            casts_shadows: true,
+            shadow_bias: None,
+            group: DEFAULT_GROUP,
+            excluded_groups: Vec::new(),
+            profile: None,
+            range: None,
+            attenuation: Attenuation::none(),
         }
     }
-}
\ No newline at end of file
+
+    // Dirección unitaria desde `point` hacia la luz e intensidad que le llega
+    pub fn illuminate(&self, point: &Vec3, world_scale: f32) -> (Vec3, f32) {
+        (self.direction_from(point), self.intensity_towards(point, world_scale))
+    }
+
+    pub fn direction_from(&self, point: &Vec3) -> Vec3 {
+        match self.kind {
+            LightKind::Directional { direction } => -direction.normalize(),
+            _ => (self.position - point).normalize(),
+        }
+    }
+
+    // Punto hacia el que van los rayos de sombra desde `point`; las luces direccionales
+    // quedan muy lejos en sentido contrario a sus rayos
+    pub fn shadow_target(&self, point: &Vec3) -> Vec3 {
+        match self.kind {
+            LightKind::Directional { .. } => point + self.direction_from(point) * DIRECTIONAL_DISTANCE,
+            _ => self.position,
+        }
+    }
+
+    // Intensidad que llega a `point`, según el perfil, el cono, la atenuación y el rango si los hay.
+    // La caída y el rango se miden en bloques de `world_scale` unidades
+    pub fn intensity_towards(&self, point: &Vec3, world_scale: f32) -> f32 {
+        let intensity = match &self.profile {
+            Some(profile) => self.intensity * profile.intensity(&(point - self.position)),
+            None => self.intensity,
+        };
+        let intensity = match &self.kind {
+            LightKind::Directional { .. } => return self.intensity,
+            LightKind::Spot(spot) => intensity * spot.factor(&(point - self.position)),
+            LightKind::Point | LightKind::Area(_) => intensity,
+        };
+        let distance = (point - self.position).magnitude() / world_scale;
+        let intensity = intensity * self.attenuation.factor(distance);
+        match self.range {
+            Some(range) => intensity * (1.0 - distance / range).max(0.0).powi(2),
+            None => intensity,
+        }
+    }
+
+    pub fn affects(&self, group: usize) -> bool {
+        !self.excluded_groups.contains(&group)
+    }
+}
@@ -0,0 +1,211 @@
+use nalgebra_glm::{IVec3, Vec3};
+use rayon::prelude::*;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::integrator::{cast_shadow, surface_color};
+use crate::sampler::{IndependentSampler, Sampler};
+use crate::scene::Scene;
+use crate::world::AIR;
+
+// Ejes de las seis caras del cubo de ambiente: +X, -X, +Y, -Y, +Z, -Z
+const AXES: [(usize, f32); 6] = [(0, 1.0), (0, -1.0), (1, 1.0), (1, -1.0), (2, 1.0), (2, -1.0)];
+
+// Sonda: luz que llega a un punto desde cada una de las seis direcciones principales
+// (un "ambient cube"), suficiente para la luz indirecta difusa de un interior
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+    position: Vec3,
+    cube: [Color; 6],
+    // Las sondas que caen dentro de un bloque no ven nada útil y no se usan
+    valid: bool,
+    stale: bool,
+}
+
+// Rejilla dispersa de sondas de luz dentro de una región (el interior de la casa).
+// Cada sonda se captura trazando rayos en todas direcciones y sumando la luz directa
+// de lo que ven; la iluminación de Whitted interpola entre las ocho sondas que rodean
+// el punto para añadir una aproximación de la luz indirecta. Cuando cambian las luces
+// o la geometría las sondas se marcan como viejas y se vuelven a capturar poco a poco,
+// `per_frame` por fotograma, para no congelar el modo interactivo.
+pub struct LightProbes {
+    min: Vec3,
+    max: Vec3,
+    spacing: f32,
+    counts: [usize; 3],
+    probes: Vec<Probe>,
+    // Rayos por sonda
+    pub samples: u32,
+    pub per_frame: usize,
+    pub seed: u64,
+    // Estado de las luces y la geometría con el que se capturaron
+    signature: Vec<f32>,
+}
+
+impl LightProbes {
+    // Sondas cada `spacing` unidades entre `min` y `max`, en los centros de las celdas
+    pub fn new(min: Vec3, max: Vec3, spacing: f32) -> Self {
+        let spacing = spacing.max(0.1);
+        let counts = [0, 1, 2].map(|axis| (((max[axis] - min[axis]) / spacing).round() as usize).max(1));
+        let mut probes = Vec::with_capacity(counts[0] * counts[1] * counts[2]);
+        for z in 0..counts[2] {
+            for y in 0..counts[1] {
+                for x in 0..counts[0] {
+                    let position = min + Vec3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5) * spacing;
+                    probes.push(Probe { position, cube: [Color::black(); 6], valid: false, stale: true });
+                }
+            }
+        }
+        LightProbes { min, max, spacing, counts, probes, samples: 64, per_frame: 8, seed: 0, signature: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.probes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    // Captura todas las sondas de una vez
+    pub fn capture_all(&mut self, scene: &Scene) {
+        self.signature = signature(scene);
+        self.capture(scene, usize::MAX);
+    }
+
+    // Si las luces o la geometría cambiaron, marca todas las sondas como viejas; después
+    // vuelve a capturar como mucho `per_frame`. Devuelve si alguna cambió.
+    pub fn update(&mut self, scene: &Scene) -> bool {
+        let current = signature(scene);
+        if current != self.signature {
+            self.signature = current;
+            for probe in self.probes.iter_mut() {
+                probe.stale = true;
+            }
+        }
+        self.capture(scene, self.per_frame)
+    }
+
+    fn capture(&mut self, scene: &Scene, budget: usize) -> bool {
+        let (samples, seed) = (self.samples.max(1), self.seed);
+        let stale: Vec<usize> = self.probes.iter().enumerate()
+            .filter(|(_, probe)| probe.stale)
+            .map(|(index, _)| index)
+            .take(budget)
+            .collect();
+
+        let captured: Vec<(usize, Probe)> = stale.par_iter()
+            .map(|&index| (index, capture_probe(self.probes[index].position, scene, samples, seed, index)))
+            .collect();
+        let changed = !captured.is_empty();
+        for (index, probe) in captured {
+            self.probes[index] = probe;
+        }
+        changed
+    }
+
+    // Luz indirecta que llega a `point` sobre una superficie con normal `normal`, o None
+    // fuera de la región de las sondas
+    pub fn irradiance(&self, point: &Vec3, normal: &Vec3) -> Option<Color> {
+        if (0..3).any(|axis| point[axis] < self.min[axis] - 1e-3 || point[axis] > self.max[axis] + 1e-3) {
+            return None;
+        }
+
+        // Celda de sondas que contiene el punto y su posición dentro de ella
+        let grid = (point - self.min) / self.spacing - Vec3::new(0.5, 0.5, 0.5);
+        let base = [0, 1, 2].map(|axis| (grid[axis].floor() as i32).clamp(0, self.counts[axis] as i32 - 1));
+        let fraction = [0, 1, 2].map(|axis| (grid[axis] - base[axis] as f32).clamp(0.0, 1.0));
+
+        let mut total = Color::black();
+        let mut weights = 0.0;
+        for corner in 0..8 {
+            let offset = IVec3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+            let cell = [0, 1, 2].map(|axis| (base[axis] + offset[axis]).min(self.counts[axis] as i32 - 1) as usize);
+            let probe = &self.probes[(cell[2] * self.counts[1] + cell[1]) * self.counts[0] + cell[0]];
+            if !probe.valid {
+                continue;
+            }
+            let weight: f32 = (0..3).map(|axis| if offset[axis] == 1 { fraction[axis] } else { 1.0 - fraction[axis] }).product();
+            total += evaluate(&probe.cube, normal) * weight;
+            weights += weight;
+        }
+        (weights > 1e-6).then(|| total * (1.0 / weights))
+    }
+}
+
+// Estado de las luces activas y revisión de la geometría: si cambia, las sondas ya no valen
+fn signature(scene: &Scene) -> Vec<f32> {
+    let mut signature = vec![scene.revision() as f32];
+    for light in scene.active_lights() {
+        signature.extend_from_slice(light.position.as_slice());
+        signature.extend_from_slice(&light.color.linear());
+        signature.push(light.intensity);
+    }
+    signature
+}
+
+fn capture_probe(position: Vec3, scene: &Scene, samples: u32, seed: u64, index: usize) -> Probe {
+    let block = IVec3::new(position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32);
+    if scene.world.get_block(block) != AIR {
+        return Probe { position, cube: [Color::black(); 6], valid: false, stale: false };
+    }
+
+    let mut sampler = IndependentSampler::new(seed);
+    sampler.start_pixel(index, 0, 0);
+    let mut cube = [Color::black(); 6];
+    let mut weights = [0.0; 6];
+    for _ in 0..samples {
+        let (u1, u2) = sampler.next_2d();
+        let z = 1.0 - 2.0 * u1;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        let direction = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+
+        let radiance = incoming(&position, &direction, scene, &mut sampler);
+        for (face, (axis, sign)) in AXES.iter().enumerate() {
+            let weight = (direction[*axis] * sign).max(0.0);
+            cube[face] += radiance * weight;
+            weights[face] += weight;
+        }
+    }
+    for (face, weight) in weights.iter().enumerate() {
+        if *weight > 0.0 {
+            cube[face] = cube[face] * (1.0 / weight);
+        }
+    }
+    Probe { position, cube, valid: true, stale: false }
+}
+
+// Luz que llega a la sonda desde `direction`: la luz directa que recibe lo que ve,
+// su emisión, o el cielo
+fn incoming(origin: &Vec3, direction: &Vec3, scene: &Scene, sampler: &mut dyn Sampler) -> Color {
+    let intersect = scene.intersect(origin, direction);
+    if !intersect.is_intersecting {
+        return scene.background(direction);
+    }
+
+    let material = &intersect.material;
+    let base_color = surface_color(&intersect, scene);
+    let mut radiance = material.emission * material.emission_intensity;
+    for light in scene.lights_for(&intersect) {
+        let (light_dir, intensity) = light.illuminate(&intersect.point, scene.settings.world_scale);
+        let diffuse = intersect.normal.dot(&light_dir).max(0.0);
+        if diffuse <= 0.0 {
+            continue;
+        }
+        let visibility = 1.0 - cast_shadow(&intersect, light, scene, sampler);
+        radiance += (base_color * light.color) * (material.albedo[0] * diffuse * intensity * visibility);
+    }
+    radiance
+}
+
+// Luz del cubo en la dirección de `normal`: las caras se mezclan con el cuadrado de
+// cada componente, que suman 1
+fn evaluate(cube: &[Color; 6], normal: &Vec3) -> Color {
+    let mut color = Color::black();
+    for axis in 0..3 {
+        let face = if normal[axis] >= 0.0 { axis * 2 } else { axis * 2 + 1 };
+        color += cube[face] * (normal[axis] * normal[axis]);
+    }
+    color
+}
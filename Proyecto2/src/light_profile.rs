@@ -0,0 +1,69 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+
+// Perfil angular de intensidad al estilo de los archivos IES, simplificado:
+// intensidad relativa según el ángulo entre la dirección de la lámpara y el
+// punto iluminado. Cada línea del archivo es `ángulo_en_grados intensidad`,
+// con `#` para comentarios; entre dos ángulos se interpola linealmente.
+#[derive(Debug, Clone)]
+pub struct LightProfile {
+    // Hacia dónde apunta la lámpara (ángulo 0)
+    pub direction: Vec3,
+    // Pares (ángulo en radianes, intensidad) ordenados por ángulo
+    samples: Vec<(f32, f32)>,
+}
+
+impl LightProfile {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+        Self::parse(&source).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Por defecto la lámpara apunta hacia abajo
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut samples = Vec::new();
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let values: Vec<f32> = line.split_whitespace()
+                .map(|v| v.parse::<f32>().map_err(|_| format!("línea {}: número inválido '{}'", number + 1, v)))
+                .collect::<Result<_, _>>()?;
+            if values.len() != 2 {
+                return Err(format!("línea {}: se esperaba 'ángulo intensidad'", number + 1));
+            }
+            samples.push((values[0].to_radians(), values[1].max(0.0)));
+        }
+
+        if samples.is_empty() {
+            return Err("el perfil no tiene muestras".to_string());
+        }
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(LightProfile { direction: Vec3::new(0.0, -1.0, 0.0), samples })
+    }
+
+    // Factor de intensidad en la dirección `to_point`, que va de la luz al punto
+    pub fn intensity(&self, to_point: &Vec3) -> f32 {
+        let cos_angle = self.direction.normalize().dot(&to_point.normalize()).clamp(-1.0, 1.0);
+        let angle = cos_angle.acos();
+
+        let (first, last) = (self.samples[0], self.samples[self.samples.len() - 1]);
+        // Un punto justo sobre la luz no tiene dirección y el ángulo sale NaN
+        if angle.is_nan() || angle <= first.0 {
+            return first.1;
+        }
+        if angle >= last.0 {
+            return last.1;
+        }
+
+        let next = self.samples.partition_point(|(a, _)| *a < angle).clamp(1, self.samples.len() - 1);
+        let (a0, i0) = self.samples[next - 1];
+        let (a1, i1) = self.samples[next];
+        let t = if a1 > a0 { (angle - a0) / (a1 - a0) } else { 0.0 };
+        i0 + (i1 - i0) * t
+    }
+}
@@ -0,0 +1,232 @@
+use image::{GrayImage, Luma};
+use nalgebra_glm::{IVec3, Vec3};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::integrator::cast_shadow;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray_intersect::Intersect;
+use crate::sampler::{IndependentSampler, Sampler};
+use crate::scene::Scene;
+
+// Normales de las seis caras de un bloque; una cara se identifica por su índice aquí
+const FACE_NORMALS: [[i32; 3]; 6] = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+
+// Caras por fila del atlas al exportar
+const ATLAS_COLUMNS: usize = 64;
+
+type Face = (IVec3, usize);
+
+// Sombras precalculadas por texel en las caras expuestas de los bloques del mundo,
+// una capa por luz. Evita lanzar rayos de sombra hacia luces que no se han movido.
+pub struct Lightmap {
+    // Texeles por lado de cada cara
+    pub resolution: usize,
+    // Posición de cada luz al hornear; si la luz se mueve su capa deja de usarse
+    light_positions: Vec<Vec3>,
+    // Cara -> fracción de luz bloqueada, `resolution²` texeles por cada luz
+    faces: HashMap<Face, Vec<f32>>,
+}
+
+// Ejes de la cara (normal, s, t)
+fn face_axes(face: usize) -> (usize, usize, usize) {
+    let axis = face / 2;
+    (axis, (axis + 1) % 3, (axis + 2) % 3)
+}
+
+// Caras de bloques sólidos que dan al aire, en orden fijo
+fn exposed_faces(scene: &Scene) -> Vec<Face> {
+    let world = &scene.world;
+    let mut faces = Vec::new();
+    // Los bloques finos no se hornean: sus caras quedan dentro de la celda
+    for pos in world.positions().into_iter().filter(|pos| world.is_full_block(*pos)) {
+        for (face, normal) in FACE_NORMALS.iter().enumerate() {
+            if !world.is_full_block(pos + IVec3::new(normal[0], normal[1], normal[2])) {
+                faces.push((pos, face));
+            }
+        }
+    }
+    faces
+}
+
+impl Lightmap {
+    // Hornea las sombras de todas las luces de la escena para cada cara expuesta
+    pub fn bake(scene: &Scene, resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        let faces = exposed_faces(scene);
+        let material = Arc::new(Material::default());
+
+        let baked: Vec<Vec<f32>> = faces.par_iter().enumerate().map(|(index, &(pos, face))| {
+            let (axis, s_axis, t_axis) = face_axes(face);
+            let normal = FACE_NORMALS[face];
+            let normal = Vec3::new(normal[0] as f32, normal[1] as f32, normal[2] as f32);
+            let mut sampler = IndependentSampler::new(0);
+            let mut texels = Vec::with_capacity(resolution * resolution * scene.lights.len());
+
+            for (light_index, light) in scene.lights.iter().enumerate() {
+                for texel in 0..resolution * resolution {
+                    let mut point = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
+                    point[axis] += if normal[axis] > 0.0 { 1.0 } else { 0.0 };
+                    point[s_axis] += ((texel % resolution) as f32 + 0.5) / resolution as f32;
+                    point[t_axis] += ((texel / resolution) as f32 + 0.5) / resolution as f32;
+
+                    let intersect = Intersect::new(point, normal, 0.0, material.clone());
+                    sampler.start_pixel(index, texel, light_index as u32);
+                    texels.push(cast_shadow(&intersect, light, scene, &mut sampler));
+                }
+            }
+            texels
+        }).collect();
+
+        Lightmap {
+            resolution,
+            light_positions: scene.lights.iter().map(|light| light.position).collect(),
+            faces: faces.into_iter().zip(baked).collect(),
+        }
+    }
+
+    // Sombra horneada para `light` en el punto golpeado, si la cara y la luz están en el lightmap
+    pub fn shadow(&self, intersect: &Intersect, light: &Light) -> Option<f32> {
+        let layer = self.light_positions.iter().position(|position| *position == light.position)?;
+
+        let axis = intersect.normal.iamax();
+        let face = axis * 2 + if intersect.normal[axis] > 0.0 { 0 } else { 1 };
+        let inside = intersect.point - intersect.normal * 0.5;
+        let pos = IVec3::new(inside.x.floor() as i32, inside.y.floor() as i32, inside.z.floor() as i32);
+        let texels = self.faces.get(&(pos, face))?;
+
+        // Interpolación bilineal entre los centros de los texeles de la cara
+        let (_, s_axis, t_axis) = face_axes(face);
+        let size = self.resolution as f32;
+        let s = ((intersect.point[s_axis] - pos[s_axis] as f32) * size - 0.5).clamp(0.0, size - 1.0);
+        let t = ((intersect.point[t_axis] - pos[t_axis] as f32) * size - 0.5).clamp(0.0, size - 1.0);
+        let (s0, t0) = (s.floor() as usize, t.floor() as usize);
+        let (s1, t1) = ((s0 + 1).min(self.resolution - 1), (t0 + 1).min(self.resolution - 1));
+        let (fs, ft) = (s - s0 as f32, t - t0 as f32);
+
+        let offset = layer * self.resolution * self.resolution;
+        let at = |s: usize, t: usize| texels[offset + t * self.resolution + s];
+        let top = at(s0, t0) * (1.0 - fs) + at(s1, t0) * fs;
+        let bottom = at(s0, t1) * (1.0 - fs) + at(s1, t1) * fs;
+        Some(top * (1.0 - ft) + bottom * ft)
+    }
+
+    // Si fue horneado para los mismos bloques expuestos que tiene ahora la escena
+    pub fn matches(&self, scene: &Scene) -> bool {
+        let faces: HashSet<Face> = exposed_faces(scene).into_iter().collect();
+        faces.len() == self.faces.len() && faces.iter().all(|face| self.faces.contains_key(face))
+    }
+
+    // Guarda el lightmap como un atlas en escala de grises (`path`, por ejemplo
+    // `diorama.png`) más un índice de texto con las luces y las caras (`diorama.lightmap`)
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut faces: Vec<&Face> = self.faces.keys().collect();
+        faces.sort_by_key(|(pos, face)| (pos.x, pos.y, pos.z, *face));
+
+        let layers = self.light_positions.len();
+        let tiles = faces.len() * layers;
+        let columns = ATLAS_COLUMNS.min(tiles.max(1));
+        let rows = tiles.div_ceil(columns).max(1);
+        let mut image = GrayImage::new((columns * self.resolution) as u32, (rows * self.resolution) as u32);
+
+        let texels_per_layer = self.resolution * self.resolution;
+        for (face_index, face) in faces.iter().enumerate() {
+            let texels = &self.faces[face];
+            for layer in 0..layers {
+                let tile = layer * faces.len() + face_index;
+                let (tile_x, tile_y) = ((tile % columns) * self.resolution, (tile / columns) * self.resolution);
+                for texel in 0..texels_per_layer {
+                    let x = tile_x + texel % self.resolution;
+                    let y = tile_y + texel / self.resolution;
+                    // Se guarda la luz que llega (blanco = iluminado) para que la imagen se lea a simple vista
+                    let lit = 1.0 - texels[layer * texels_per_layer + texel];
+                    image.put_pixel(x as u32, y as u32, Luma([(lit.clamp(0.0, 1.0) * 255.0).round() as u8]));
+                }
+            }
+        }
+        image.save(path).map_err(|e| format!("No se pudo guardar {}: {}", path, e))?;
+
+        let mut index = format!("# Lightmap: atlas de {} columnas, una capa de caras por luz\n", columns);
+        index.push_str(&format!("resolution {}\n", self.resolution));
+        for position in &self.light_positions {
+            index.push_str(&format!("light {} {} {}\n", position.x, position.y, position.z));
+        }
+        for (pos, face) in faces {
+            index.push_str(&format!("face {} {} {} {}\n", pos.x, pos.y, pos.z, face));
+        }
+        let index_path = index_path(path);
+        fs::write(&index_path, index).map_err(|e| format!("No se pudo guardar {}: {}", index_path, e))
+    }
+
+    // Lee un lightmap guardado con `save`
+    pub fn load(path: &str) -> Result<Self, String> {
+        let index_path = index_path(path);
+        let index = fs::read_to_string(&index_path).map_err(|e| format!("No se pudo leer {}: {}", index_path, e))?;
+
+        let mut resolution = 0;
+        let mut light_positions = Vec::new();
+        let mut face_list = Vec::new();
+        for (number, line) in index.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("{}:{}: línea inválida: {}", index_path, number + 1, line);
+            let mut parts = line.split_whitespace();
+            let command = parts.next().unwrap_or_default();
+            let values: Vec<&str> = parts.collect();
+            match (command, values.as_slice()) {
+                ("resolution", [size]) => resolution = size.parse().map_err(|_| error())?,
+                ("light", [x, y, z]) => {
+                    let parse = |value: &str| value.parse::<f32>().map_err(|_| error());
+                    light_positions.push(Vec3::new(parse(x)?, parse(y)?, parse(z)?));
+                }
+                ("face", [x, y, z, face]) => {
+                    let parse = |value: &str| value.parse::<i32>().map_err(|_| error());
+                    let face: usize = face.parse().map_err(|_| error())?;
+                    if face >= FACE_NORMALS.len() {
+                        return Err(error());
+                    }
+                    face_list.push((IVec3::new(parse(x)?, parse(y)?, parse(z)?), face));
+                }
+                _ => return Err(error()),
+            }
+        }
+        if resolution == 0 {
+            return Err(format!("{}: falta la resolución", index_path));
+        }
+
+        let image = image::open(path).map_err(|e| format!("No se pudo abrir {}: {}", path, e))?.to_luma8();
+        let tiles = face_list.len() * light_positions.len();
+        let columns = ATLAS_COLUMNS.min(tiles.max(1));
+        if (image.width() as usize) < columns * resolution || (image.height() as usize) < tiles.div_ceil(columns) * resolution {
+            return Err(format!("{}: el atlas no coincide con {}", path, index_path));
+        }
+
+        let texels_per_layer = resolution * resolution;
+        let faces = face_list.iter().enumerate().map(|(face_index, face)| {
+            let mut texels = Vec::with_capacity(texels_per_layer * light_positions.len());
+            for layer in 0..light_positions.len() {
+                let tile = layer * face_list.len() + face_index;
+                let (tile_x, tile_y) = ((tile % columns) * resolution, (tile / columns) * resolution);
+                for texel in 0..texels_per_layer {
+                    let x = tile_x + texel % resolution;
+                    let y = tile_y + texel / resolution;
+                    texels.push(1.0 - image.get_pixel(x as u32, y as u32)[0] as f32 / 255.0);
+                }
+            }
+            (*face, texels)
+        }).collect();
+
+        Ok(Lightmap { resolution, light_positions, faces })
+    }
+}
+
+// `diorama.png` -> `diorama.lightmap`
+fn index_path(path: &str) -> String {
+    Path::new(path).with_extension("lightmap").to_string_lossy().into_owned()
+}
@@ -1,367 +1,565 @@
-mod framebuffer;
-mod ray_intersect;
-mod cube;
-mod color;
-mod camera;
-mod light;
-mod material;
-mod texture;
-
-use minifb::{Window, WindowOptions, Key};
-use nalgebra_glm::{Vec3, normalize};
-use std::time::Duration;
-use std::f32::consts::PI;
-
-use crate::color::Color;
-use crate::ray_intersect::{Intersect, RayIntersect};
-use crate::cube::Cube;
-use crate::framebuffer::Framebuffer;
-use crate::camera::Camera;
-use crate::light::Light;
-use crate::material::Material;
-use crate::texture::Texture;
-use image::{DynamicImage, GenericImageView};
-
-const ORIGIN_BIAS: f32 = 1e-4;
-const SKYBOX_COLOR: Color = Color::new(68, 142, 228);
+use nalgebra_glm::{IVec3, Vec3, normalize};
+use std::sync::Arc;
+use std::time::Instant;
+
+use proyecto2::color::Color;
+use proyecto2::app::App;
+use proyecto2::animation::Animation;
+use proyecto2::input::{Input, Key};
+use proyecto2::controls::{camera_controls, light_controls, settings_controls};
+use proyecto2::inspector::Inspector;
+use proyecto2::ray_debug::trace_pixel;
+use proyecto2::camera::Camera;
+use proyecto2::light::{AreaLight, Light, LightKind, ShadowModel, SpotLight};
+use proyecto2::light_profile::LightProfile;
+use proyecto2::portal::Portal;
+use proyecto2::material::{FaceTextures, Material};
+use proyecto2::torch::Torch;
+use proyecto2::texture::{AlphaMask, Texture, TextureFilter};
+use proyecto2::texture_watch::TextureWatcher;
+use proyecto2::texture_pack::{TexturePack, TexturePacks};
+use proyecto2::render::{render, render_progressive};
+use proyecto2::integrator;
+use proyecto2::sampler;
+use proyecto2::config::Config;
+use proyecto2::scene::Scene;
+use proyecto2::bench_scene::BenchScene;
+use proyecto2::block_shape::BlockShape;
+use proyecto2::lightmap::Lightmap;
+use proyecto2::light_probes::LightProbes;
+use proyecto2::scene_file::load_scene;
+use proyecto2::world::BlockEdit;
+use proyecto2::prefab::{load_prefab, Variation};
+use proyecto2::rng::Rng;
+use proyecto2::selection::Selection;
+use proyecto2::skybox::{Skybox, PROCEDURAL_RESOLUTION};
+use proyecto2::night_sky::NightSky;
+use proyecto2::sun::Sun;
+use proyecto2::weather::Weather;
+use proyecto2::snow::Snow;
+use proyecto2::water::Ripples;
+use proyecto2::sphere::Sphere;
+use proyecto2::mesh::Mesh;
+use proyecto2::billboard::Billboard;
+use proyecto2::cube::Face;
+use proyecto2::decal::Decal;
+use proyecto2::world::Axis;
+use proyecto2::worldgen::{generate_path, generate_terrain, generate_tree, PathParams, TerrainBlocks, TreeParams};
 
 fn load_texture_from_file(file_path: &str) -> Texture {
-    // Carga la imagen usando la crate `image`
-    let img = image::open(file_path).expect("Failed to open image");
-    let (width, height) = img.dimensions();
-    
-    // Convertir la imagen a un Vec<Color>
-    let mut pixel_data = Vec::new();
-    if let DynamicImage::ImageRgb8(rgb_image) = img {
-        for pixel in rgb_image.pixels() {
-            // Usar el constructor `new` para crear un color
-            let color = Color::new(pixel[0], pixel[1], pixel[2]);
-            pixel_data.push(color);
-        }
-    }
-    
-    // Crear la textura
-    Texture::new(pixel_data, width as usize, height as usize)
+    Texture::load(file_path).unwrap_or_else(|e| panic!("{}", e))
 }
 
-fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
-    let offset = intersect.normal * ORIGIN_BIAS;
-    if direction.dot(&intersect.normal) < 0.0 {
-        intersect.point - offset
-    } else {
-        intersect.point + offset
-    }
+// Escalera de mano sin colocar: `build_diorama` y el editor la copian en la cara que toque
+fn ladder_decal() -> Decal {
+    let mut texture = load_texture_from_file("src/image/ladder.png");
+    texture.filter = Some(TextureFilter::Nearest);
+    let material = Material::new(Color::black(), 10.0, [0.6, 0.1, 0.0, 0.0], 0.0, Some(texture));
+    let mut ladder = Decal::new(IVec3::zeros(), Face::East, Arc::new(material));
+    ladder.alpha = Some(Arc::new(AlphaMask::load("src/image/ladder.png").unwrap_or_else(|e| panic!("{}", e))));
+    ladder
 }
 
-fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
-    incident - 2.0 * incident.dot(normal) * normal
-}
+fn build_diorama(config: &Config) -> (Scene, Camera) {
+    // Cargar las texturas
+    let dirt_texture = load_texture_from_file("src/image/Dirt.jpg");
+    let grass_texture = load_texture_from_file("src/image/grass.jpg");
+    let grass_side_texture = load_texture_from_file("src/image/grass_side.png");
+    let cobblestone_texture = load_texture_from_file("src/image/cobblestone.jpg");
+    let plank_texture = load_texture_from_file("src/image/Plank.jpg");
+    let glass_texture = load_texture_from_file("src/image/glass.jpg");
+    let door_texture = load_texture_from_file("src/image/door.png"); // Cargar la textura de la puerta
+    let trunk_texture = load_texture_from_file("src/image/trunk.jpg");
+    let leaves_texture = load_texture_from_file("src/image/leaves.jpg");
+
+    let mut scene = Scene::new(Vec::new(), Vec::new(), config.render.clone());
+    let terrain_group = scene.add_group("terrain");
+    let house_group = scene.add_group("house");
+    let props_group = scene.add_group("props");
+    let lights_group = scene.add_group("lights");
+
+    // Crear los materiales y registrarlos como tipos de bloque
+    let palette = &mut scene.world.palette;
+    let dirt = palette.add("dirt", Material::new(Color::black(), 15.0, [0.5, 0.3, 0.0, 0.0], 0.0, Some(dirt_texture.clone())));
+    // Grass como en Minecraft: tapa verde, lados de tierra con borde de pasto y base de tierra
+    let mut grass_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(grass_texture.clone()));
+    grass_material.face_textures = Some(FaceTextures::top_side_bottom(grass_texture, grass_side_texture, dirt_texture));
+    let grass = palette.add("grass", grass_material);
+    // Cobblestone y plank tienen relieve con un mapa de normales sacado de su textura
+    let mut cobblestone_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(cobblestone_texture.clone()));
+    cobblestone_material.normal_map = Some(cobblestone_texture.to_normal_map(3.0));
+    let cobblestone = palette.add("cobblestone", cobblestone_material);
+    // Piedra pulida: la misma textura sin relieve, brillante y reflejando la escena
+    palette.add("polished_stone", Material::new(Color::black(), 60.0, [0.5, 0.6, 0.35, 0.0], 0.0, Some(cobblestone_texture)));
+    let mut plank_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(plank_texture.clone()));
+    plank_material.normal_map = Some(plank_texture.to_normal_map(2.0));
+    palette.add("plank", plank_material.clone());
+    // Postes finos de plank para la cerca
+    palette.add_shaped("fence_post", plank_material, BlockShape::Post);
+    // El cristal refleja un poco lo que tiene delante sin tapar su textura, y su alfa
+    // deja ver a medias el interior con un marco opaco
+    let mut glass_material = Material::new(Color::black(), 15.0, [0.1, 0.1, 0.3, 0.0], 0.0, Some(glass_texture));
+    glass_material.alpha = Some(Arc::new(AlphaMask::load("src/image/glass_alpha.png").unwrap_or_else(|e| panic!("{}", e))));
+    palette.add("glass", glass_material);
+    palette.add("door", Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(door_texture)));
+    palette.add("log", Material::new(Color::black(), 15.0, [0.5, 0.3, 0.0, 0.0], 0.0, Some(trunk_texture)));
+    palette.add("leaves", Material::new(Color::black(), 15.0, [0.5, 0.2, 0.0, 0.0], 0.0, Some(leaves_texture)));
+    // Agua transparente: refracta con índice 1.33, conserva algo de su color y su
+    // superficie se ondula con el reloj de la escena
+    let mut water_material = Material::new(Color::new(40, 90, 180), 80.0, [0.3, 0.4, 1.0, 0.7], 1.33, None);
+    water_material.ripples = Some(Ripples { seed: config.seed, ..Ripples::default() });
+    let water = palette.add("water", water_material);
+    // Bloque luminoso tipo glowstone: brilla por sí mismo e ilumina lo que tiene cerca
+    let mut glowstone_material = Material::new(Color::new(200, 160, 80), 15.0, [0.5, 0.1, 0.0, 0.0], 0.0, None);
+    glowstone_material.emission = Color::new(255, 200, 110);
+    glowstone_material.emission_intensity = 0.8;
+    let glowstone = palette.add("glowstone", glowstone_material);
+
+    // Terreno de 10x10: generado con `--worldgen`, o por defecto una capa de tierra
+    // con cobblestone a la izquierda y grass a la derecha encima
+    let world = &mut scene.world;
+    world.active_group = terrain_group;
+    if let Some(worldgen) = &config.worldgen {
+        let blocks = TerrainBlocks { surface: grass, soil: dirt, stone: cobblestone, water };
+        generate_terrain(world, worldgen, &blocks);
+    } else {
+        world.fill(IVec3::new(-5, -1, -5), IVec3::new(4, -1, 4), dirt);
+        world.fill(IVec3::new(-5, 0, -5), IVec3::new(-1, 0, 4), cobblestone);
+        world.fill(IVec3::new(0, 0, -5), IVec3::new(4, 0, 4), grass);
+        // Estanque en el cobblestone junto a la pared izquierda de la casa
+        world.fill(IVec3::new(-5, 0, -4), IVec3::new(-4, 0, -2), water);
+    }
 
-fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-    let cosi = -incident.dot(normal).max(-1.0).min(1.0);
-    let (n_cosi, eta, n_normal);
+    // Camino de cobblestone que cruza el grass siguiendo la altura del terreno
+    let path_params = PathParams { bend: 2.0, skip: vec![water], ..PathParams::default() };
+    generate_path(world, IVec3::new(3, 0, -5), IVec3::new(1, 0, 4), &path_params, cobblestone);
+
+    // Estructuras definidas en archivos de prefab
+    let load = |path: &str| load_prefab(path, &world.palette).unwrap_or_else(|e| panic!("{}", e));
+    let house = load("src/prefabs/house.prefab");
+    let tree = load("src/prefabs/tree.prefab");
+    let fence = load("src/prefabs/fence.prefab");
+
+    world.active_group = house_group;
+    world.stamp(&house, IVec3::new(-3, 0, -5));
+
+    // Los props se colocan con variaciones aleatorias según `--seed`
+    let mut rng = Rng::new(config.seed);
+    let tree_variation = Variation { rotate: true, jitter: 1, ..Variation::default() };
+    let tree_params = TreeParams { trunk_height: 3, ..TreeParams::default() };
+    let (log, leaves) = (world.palette.id("log").unwrap(), world.palette.id("leaves").unwrap());
+    let fence_variation = Variation {
+        swaps: vec![(world.palette.id("fence_post").unwrap(), world.palette.id("log").unwrap())],
+        swap_chance: 0.5,
+        ..Variation::default()
+    };
 
-    if cosi < 0.0 {
-        n_cosi = -cosi;
-        eta = 1.0 / eta_t;
-        n_normal = -normal;
-    } else {
-        n_cosi = cosi;
-        eta = eta_t;
-        n_normal = *normal;
+    world.active_group = props_group;
+    // Un árbol del prefab y otro generado, para que no salgan iguales
+    let generated = generate_tree(&tree_params, log, leaves, &mut rng);
+    let trees = [(IVec3::new(3, 1, 2), tree), (IVec3::new(-3, 1, 2), generated)];
+    for (position, tree) in trees {
+        let (tree, shift) = tree_variation.apply(&tree, &mut rng);
+        world.stamp(&tree, position + shift);
     }
-    
-    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
-    
-    if k < 0.0 {
-        reflect(incident, &n_normal)
-    } else {
-        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+    // Cerca a lo largo del borde derecho, girada para seguir el eje Z
+    let (fence, _) = fence_variation.apply(&fence.rotated_y(1), &mut rng);
+    world.stamp(&fence, IVec3::new(4, 1, -1));
+    // Farol en la esquina delantera del grass
+    world.set_block(IVec3::new(4, 1, -5), glowstone);
+
+    // Bola de vidrio sobre el cobblestone, junto a la esquina de la casa
+    let ball_material = Material::new(Color::new(200, 220, 255), 80.0, [0.2, 0.5, 1.0, 0.8], 1.5, None);
+    let mut ball = Sphere::new(Vec3::new(-4.5, 1.5, -4.5), 0.5, Arc::new(ball_material));
+    ball.group = props_group;
+    scene.add_object(ball);
+
+    // Roca de triángulos cargada de un OBJ, junto al farol, delante de la casa
+    let rock_material = scene.world.palette.material(cobblestone).unwrap();
+    let mut rock = Mesh::load_obj("src/models/rock.obj", rock_material).unwrap_or_else(|e| panic!("{}", e));
+    rock.transform(Vec3::new(3.5, 1.0, -4.5), 0.8);
+    rock.group = props_group;
+    scene.add_object(rock);
+
+    // Llama en sprite sobre el farol: la silueta sale del alfa de la imagen
+    let mut flame_texture = load_texture_from_file("src/image/flame.png");
+    flame_texture.filter = Some(TextureFilter::Nearest);
+    let mut flame_material = Material::new(Color::black(), 0.0, [0.6, 0.0, 0.0, 0.0], 0.0, Some(flame_texture));
+    flame_material.emission = Color::new(160, 90, 30);
+    flame_material.emission_intensity = 1.0;
+    let mut flame = Billboard::new(Vec3::new(4.5, 2.3, -4.5), (0.6, 0.6), Arc::new(flame_material));
+    flame.alpha = Some(Arc::new(AlphaMask::load("src/image/flame.png").unwrap_or_else(|e| panic!("{}", e))));
+    flame.group = props_group;
+    scene.billboards.push(flame);
+
+    // Antorcha junto a la puerta, con el palo de tronco
+    let mut torch = Torch::new(Vec3::new(1.5, 1.0, -5.5), scene.world.palette.material(log).unwrap());
+    torch.seed = config.seed;
+    torch.group = props_group;
+    scene.add_torch(torch);
+
+    // Escalera de mano subiendo por la esquina derecha de la fachada
+    for y in 1..4 {
+        let mut ladder = ladder_decal();
+        ladder.block = IVec3::new(2, y, -5);
+        ladder.face = Face::North;
+        ladder.group = props_group;
+        scene.add_decal(ladder);
     }
-}
 
-fn cast_shadow(intersect: &Intersect, light: &Light, objects: &[Cube]) -> f32 {
-    let light_dir = (light.position - intersect.point).normalize();
-    let light_distance = (light.position - intersect.point).magnitude();
-    let shadow_ray_origin = offset_origin(intersect, &light_dir);
+    // Portales en la cara interior de las ventanas de la casa, para el path tracer
+    let (x, y, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    scene.portals = vec![
+        Portal::new(Vec3::new(-2.0, 2.0, -4.0), x, y),
+        Portal::new(Vec3::new(1.0, 2.0, -4.0), x, y),
+        Portal::new(Vec3::new(-2.0, 2.0, -2.0), x, y),
+        Portal::new(Vec3::new(1.0, 2.0, -2.0), x, y),
+        Portal::new(Vec3::new(-2.0, 2.0, -4.0), z * 2.0, y),
+        Portal::new(Vec3::new(2.0, 2.0, -4.0), z * 2.0, y),
+        Portal::new(Vec3::new(-2.0, 4.0, -4.0), x * 4.0, z),
+    ];
 
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-            return 1.0 - (shadow_intersect.distance / light_distance).min(1.0).powf(2.0);
-        }
-    }
+    scene.rebuild_blocks();
 
-    0.0
-}
+    // Cielo con nubes generado a partir de la semilla
+    scene.skybox = Some(Skybox::procedural(config.seed, PROCEDURAL_RESOLUTION));
+
+    // Cámara
+    let camera = Camera::new(Vec3::new(0.0, 3.0, -10.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
 
-pub fn cast_ray(ray_origin: &Vec3, ray_direction: &Vec3, objects: &[Cube], lights: &[Light], depth: u32) -> Color {
-    if depth > 3 {
-        return SKYBOX_COLOR;
+    // Luz
+    let mut light = Light::new(Vec3::new(5.0, 5.0, -10.0), Color::new(255, 255, 255), 1.0);
+    light.group = lights_group;
+    scene.add_light(light);
+
+    // Luz cálida dentro de la casa: no debe iluminar el terreno ni los props de fuera
+    let mut interior_light = Light::new(Vec3::new(-0.5, 2.5, -3.5), Color::new(255, 200, 120), 0.5);
+    interior_light.group = lights_group;
+    interior_light.excluded_groups = vec![terrain_group, props_group];
+    let downlight = LightProfile::load("src/profiles/downlight.profile").unwrap_or_else(|e| panic!("{}", e));
+    interior_light.profile = Some(Arc::new(downlight));
+    scene.add_light(interior_light);
+
+    // Farol sobre la puerta: un foco que solo ilumina el umbral y el camino de delante
+    let mut porch_light = Light::new(Vec3::new(0.5, 2.8, -5.6), Color::new(255, 220, 160), 0.8);
+    porch_light.group = lights_group;
+    porch_light.kind = LightKind::Spot(SpotLight::new(Vec3::new(0.0, -1.0, -0.35), 20.0, 35.0));
+    scene.add_light(porch_light);
+
+    // Sondas de luz en el hueco de la casa, entre las paredes, el suelo y el techo
+    if let Some(spacing) = config.light_probes {
+        let mut probes = LightProbes::new(Vec3::new(-2.0, 1.0, -4.0), Vec3::new(2.0, 4.0, -2.0), spacing);
+        probes.samples = config.probe_samples;
+        probes.seed = config.seed;
+        scene.light_probes = Some(probes);
     }
 
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
+    (scene, camera)
+}
 
-    for object in objects {
-        let i = object.ray_intersect(ray_origin, ray_direction);
-        if i.is_intersecting && i.distance < zbuffer {
-            zbuffer = i.distance;
-            intersect = i;
+fn main() {
+    let config = Config::from_args();
+    if let Some(threads) = config.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()
+            .unwrap_or_else(|e| panic!("No se pudo crear el pool de {} hilos: {}", threads, e));
+    }
+    let (width, height) = (config.width, config.height);
+    let integrator = integrator::from_name(&config.integrator)
+        .unwrap_or_else(|| panic!("Integrador desconocido: {}", config.integrator));
+
+    // Escena desde un archivo con `--scene <ruta>`, la de rendimiento con
+    // `--bench-scene <bloques>`, o el diorama incluido
+    let (mut scene, mut camera) = match (&config.scene, config.bench_scene) {
+        (Some(path), _) => load_scene(path, config.render.clone()).unwrap_or_else(|e| panic!("{}", e)),
+        (None, Some(blocks)) => {
+            let bench = BenchScene { blocks, seed: config.seed, as_objects: config.bench_objects };
+            bench.build(config.render.clone())
         }
+        (None, None) => build_diorama(&config),
+    };
+    if let Some(fov) = config.fov {
+        camera.fov = fov.to_radians();
     }
+    // Después de la escena, que puede cambiar las muestras por píxel
+    let sampler = sampler::from_name(&config.sampler, scene.settings.samples_per_pixel, config.seed)
+        .unwrap_or_else(|| panic!("Sampler desconocido: {}", config.sampler));
 
-    if !intersect.is_intersecting {
-        return SKYBOX_COLOR;
+    if let Some(name) = &config.skybox {
+        scene.set_sky(name, config.seed).unwrap_or_else(|e| panic!("{}", e));
     }
 
-    let material = &intersect.material;
-    
-    let mut final_color = if let Some(texture) = &material.texture {
-        let uv = intersect.uv.unwrap_or((0.0, 0.0));
-        texture.get_color_at(uv.0, uv.1)
-    } else {
-        material.diffuse
-    };
-
-    let view_dir = (ray_origin - intersect.point).normalize();
-
-    // Si el material tiene un índice de refracción, calculamos la refracción
-    if material.refractive_index > 1.0 {
-        let refracted_dir = refract(ray_direction, &intersect.normal, material.refractive_index);
-        let refracted_origin = offset_origin(&intersect, &refracted_dir);
-        let refracted_color = cast_ray(&refracted_origin, &refracted_dir, objects, lights, depth + 1);
-        final_color = final_color * material.albedo[0] + refracted_color * material.albedo[3];
-    } else {
-        for light in lights {
-            let light_dir = (light.position - intersect.point).normalize();
-            let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
-            let shadow_intensity = cast_shadow(&intersect, light, objects);
-            let light_intensity = light.intensity * (1.0 - shadow_intensity);
-
-            let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
-            let diffuse = final_color * material.albedo[0] * diffuse_intensity * light_intensity;
-
-            let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(material.specular);
-            let specular = light.color * material.albedo[1] * specular_intensity * light_intensity;
+    // Ciclo de día y noche: el sol sustituye a la luz principal y arrastra con él el cielo
+    if config.time_of_day.is_some() || config.day_length.is_some() {
+        let mut sun = Sun::new(config.time_of_day.unwrap_or(8.0));
+        sun.day_length = config.day_length.unwrap_or(0.0).max(0.0);
+        scene.add_sun(sun);
+    }
 
-            final_color += diffuse + specular;
+    // Modelo de sombras elegido con `--shadows <hard|falloff|soft|low|medium|high|soft:n:r>`
+    let shadow_model = |name: &str| ShadowModel::from_name(name).unwrap_or_else(|| panic!("Modelo de sombras desconocido: {}", name));
+    if let Some(name) = &config.shadows {
+        let shadow = shadow_model(name);
+        for light in scene.lights.iter_mut() {
+            light.shadow = shadow;
+        }
+    }
+    if let Some(attenuation) = config.attenuation {
+        for light in scene.lights.iter_mut() {
+            light.attenuation = attenuation;
+        }
+    }
+    // La luz principal puede tener sombras de más calidad que el resto con `--sun-shadows`
+    if let Some(sun) = scene.lights.first_mut() {
+        if let Some(name) = &config.sun_shadows {
+            sun.shadow = shadow_model(name);
+        }
+        if let Some(bias) = config.sun_shadow_bias {
+            sun.shadow_bias = Some(bias);
+        }
+        if let Some(size) = config.sun_area {
+            let (edge_u, edge_v) = (Vec3::new(size, 0.0, 0.0), Vec3::new(0.0, 0.0, size));
+            sun.kind = LightKind::Area(AreaLight::new(edge_u, edge_v, config.sun_area_samples));
         }
     }
 
-    final_color
-}
+    // Cielo nublado: la cara superior del mundo ilumina como una luz de área
+    if let Some(intensity) = config.sky_ceiling {
+        let color = Color::from_hex(config.sky_ceiling_color.unwrap_or(0xD0D8E8));
+        scene.add_sky_ceiling(color, intensity, 16);
+    }
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera, lights: &[Light]) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    // Noche con estrellas y luna con `--night <0 a 1>`, o siempre que haya ciclo de día y
+    // noche (entonces manda la hora); `--moon-light` fija la luz de la luna
+    if let Some(amount) = config.night.or(scene.sun.as_ref().map(|sun| sun.night())) {
+        let mut night_sky = NightSky::new(config.seed);
+        night_sky.night = amount;
+        night_sky.moon_texture = Some(Texture::load("src/image/moon.png").unwrap_or_else(|e| panic!("{}", e)));
+        if let Some(intensity) = config.moon_light {
+            night_sky.moon_light = intensity;
+        }
+        scene.add_night_sky(night_sky);
+        scene.update_sun(0.0);
+    }
 
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
+    // Tiempo inicial con `--weather <clear|rain|storm>`; `--weather-cycle <segundos>` lo va cambiando
+    if let Some(kind) = config.weather {
+        scene.weather = Weather::new(kind, config.seed);
+    }
+    scene.weather.cycle = config.weather_cycle;
 
-            let ray_direction = normalize(&Vec3::new(screen_x * aspect_ratio * perspective_scale, screen_y * perspective_scale, -1.0));
-            let rotated_direction = camera.base_change(&ray_direction);
+    if let Some(coverage) = config.snow {
+        let mut snow = Snow::new(coverage);
+        snow.seed = config.seed;
+        snow.texture = Some(Texture::load("src/image/snow.png").unwrap_or_else(|e| panic!("{}", e)));
+        scene.snow = Some(snow);
+    }
 
-            let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, lights, 0);
+    // Reutiliza el lightmap de una sesión anterior, o lo hornea y lo guarda
+    if let Some(path) = &config.lightmap {
+        let loaded = match Lightmap::load(path) {
+            Ok(lightmap) if lightmap.matches(&scene) => Some(lightmap),
+            Ok(_) => {
+                println!("El lightmap {} no corresponde a la escena, se vuelve a hornear", path);
+                None
+            }
+            Err(_) => None,
+        };
+        scene.lightmap = Some(loaded.unwrap_or_else(|| {
+            let lightmap = Lightmap::bake(&scene, config.lightmap_resolution);
+            lightmap.save(path).unwrap_or_else(|e| panic!("{}", e));
+            lightmap
+        }));
+    }
 
-            framebuffer.set_current_color(pixel_color.to_hex());
-            framebuffer.point(x, y);
-        }
+    // Las sondas se capturan enteras antes del primer fotograma; después solo las que cambian
+    if let Some(mut probes) = scene.light_probes.take() {
+        probes.capture_all(&scene);
+        scene.light_probes = Some(probes);
     }
-}
 
-fn main() {
-    let window_width = 200;
-    let window_height = 100;
-    let framebuffer_width = 200;
-    let framebuffer_height = 100;
-    let frame_delay = Duration::from_millis(16);
+    let mut app = App::new(scene, camera, integrator, sampler, width, height);
+    app.progressive = config.progressive;
+    app.hidpi = config.hidpi;
+    app.scale_factor = config.scale_factor;
+    app.pixel_scale = config.pixel_scale;
+    app.window_size = (
+        config.window_width.unwrap_or(width * config.pixel_scale),
+        config.window_height.unwrap_or(height * config.pixel_scale),
+    );
+    app.motion_scale = config.motion_scale;
+    app.target_fps = Some(config.target_fps);
+    app.inspector = config.inspect.then(Inspector::new);
+
+    if let Some((x, y)) = config.trace_pixel {
+        app.scene.prepare_view(&app.camera, app.framebuffer.height);
+        let size = (app.framebuffer.width, app.framebuffer.height);
+        println!("{}", trace_pixel(&app.scene, &app.camera, app.integrator.as_ref(), app.sampler.as_ref(), size, x, y));
+        return;
+    }
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new("Diorama", window_width, window_height, WindowOptions::default()).unwrap();
+    if let Some(frames) = config.dolly_zoom {
+        export_dolly_zoom(&mut app, &config, frames);
+        return;
+    }
 
-    // Cargar las texturas
-    let dirt_texture = load_texture_from_file("src/image/Dirt.jpg");
-    let grass_texture = load_texture_from_file("src/image/Grass.jpg");
-    let cobblestone_texture = load_texture_from_file("src/image/Cobblestone.jpg");
-    let plank_texture = load_texture_from_file("src/image/Plank.jpg");
-    let glass_texture = load_texture_from_file("src/image/Glass.jpg");
-    let door_texture = load_texture_from_file("src/image/door.png"); // Cargar la textura de la puerta
+    if let Some(frames) = config.animate {
+        export_animation(&mut app, &config, frames);
+        return;
+    }
 
-    // Crear los materiales
-    let dirt_material = Material::new(Color::black(), 15.0, [0.5, 0.3, 0.0, 0.0], 0.0, Some(dirt_texture));
-    let grass_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(grass_texture));
-    let cobblestone_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(cobblestone_texture));
-    let plank_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(plank_texture));
-    let glass_material = Material::new(Color::black(), 15.0, [0.1, 0.1, 0.8, 0.0], 0.0, Some(glass_texture));
-    let door_material = Material::new(Color::black(), 15.0, [0.5, 0.5, 0.0, 0.0], 0.0, Some(door_texture)); // Crear material de la puerta
-
-    // Generar cubos de tierra (suelo)
-    let mut objects: Vec<Cube> = Vec::new();
-    let grid_size = 10; // Tamaño de la cuadrícula (10x10)
-    let cube_size = 1.0; // Tamaño de cada cubo de tierra
-
-    // Crear la cuadrícula de cubos de tierra
-    for x in 0..grid_size {
-        for z in 0..grid_size {
-            let x_pos = (x as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-            let z_pos = (z as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-            let y_pos = -1.0; // Todos los cubos de tierra estarán en la misma altura
-
-            let cube = Cube::new(
-                Vec3::new(x_pos, y_pos, z_pos),                // Posición inicial
-                Vec3::new(x_pos + cube_size, y_pos + cube_size, z_pos + cube_size), // Posición final
-                dirt_material.clone().into(), // Usar el material de tierra
-            );
-
-            objects.push(cube);
-        }
+    if let Some(path) = &config.render_output {
+        render_offline(&mut app, path);
+        return;
     }
 
-    // Crear cubos a la izquierda con textura de cobblestone
-    for x in 0..(grid_size / 2) {
-        for z in 0..grid_size {
-            let x_pos = (x as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-            let z_pos = (z as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-            let y_pos = 0.0; // Altura de los cubos de cobblestone
+    if config.watch_textures {
+        let mut watcher = TextureWatcher::new(&app.scene);
+        app.on_update(move |scene, _| watcher.poll(scene));
+    }
 
-            let cube = Cube::new(
-                Vec3::new(x_pos, y_pos, z_pos),                // Posición inicial
-                Vec3::new(x_pos + cube_size, y_pos + cube_size, z_pos + cube_size), // Posición final
-                cobblestone_material.clone().into(), // Usar el material de cobblestone
-            );
+    let packs = config.texture_packs.iter()
+        .map(|dir| TexturePack::load(dir).unwrap_or_else(|e| panic!("{}", e)))
+        .collect();
+    let mut packs = TexturePacks::new(&app.scene, packs);
 
-            objects.push(cube);
-        }
+    let mut selection = Selection::new();
+    let ladder = ladder_decal();
+    let input = |input: &dyn Input, camera: &mut Camera, scene: &mut Scene| {
+        handle_input(input, camera, scene, &mut selection, &mut packs, &ladder);
+    };
+    if config.terminal {
+        // Backend de terminal para sesiones sin servidor gráfico
+        app.run_terminal(input);
+    } else {
+        app.run_window("Diorama", input);
     }
+}
 
-    // Crear cubos a la derecha con textura de grass
-    for x in (grid_size / 2)..grid_size {
-        for z in 0..grid_size {
-            let x_pos = (x as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-            let z_pos = (z as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-            let y_pos = 0.0; // Altura de los cubos de grass
-
-            let cube = Cube::new(
-                Vec3::new(x_pos, y_pos, z_pos),                // Posición inicial
-                Vec3::new(x_pos + cube_size, y_pos + cube_size, z_pos + cube_size), // Posición final
-                grass_material.clone().into(), // Usar el material de grass
-            );
+// Renderiza los fotogramas del dolly zoom como `frame_0000.png`, `frame_0001.png`...
+fn export_dolly_zoom(app: &mut App, config: &Config, frames: u32) {
+    std::fs::create_dir_all(&config.frames_dir).unwrap_or_else(|e| panic!("No se pudo crear {}: {}", config.frames_dir, e));
+    let end_fov = config.dolly_fov.to_radians();
+    let target = config.dolly_target.unwrap_or(app.camera.center);
+
+    for frame in 0..frames {
+        let t = if frames > 1 { frame as f32 / (frames - 1) as f32 } else { 1.0 };
+        let frame_camera = app.camera.dolly_zoom(&target, end_fov, t);
+        app.scene.prepare_view(&frame_camera, app.framebuffer.height);
+        render(&mut app.framebuffer, &app.scene, &frame_camera, app.integrator.as_ref(), app.sampler.as_ref());
+
+        let path = format!("{}/frame_{:04}.png", config.frames_dir, frame);
+        app.framebuffer.save(&path).unwrap_or_else(|e| panic!("{}", e));
+        println!("{} ({}/{})", path, frame + 1, frames);
+    }
+}
 
-            objects.push(cube);
-        }
+// Renderiza la animación como `frame_0001.png`, `frame_0002.png`... con el reloj de la
+// escena en el instante de cada fotograma, para montarla luego con ffmpeg
+fn export_animation(app: &mut App, config: &Config, frames: u32) {
+    let animation = match &config.animation {
+        Some(path) => Animation::load(path).unwrap_or_else(|e| panic!("{}", e)),
+        None => Animation::turntable(&app.camera, config.turntable_seconds),
+    };
+    std::fs::create_dir_all(&config.frames_dir).unwrap_or_else(|e| panic!("No se pudo crear {}: {}", config.frames_dir, e));
+    let start = Instant::now();
+
+    for frame in 0..frames {
+        let time = animation.frame_time(frame, frames);
+        app.scene.clock.set_time(time);
+        animation.apply(time, &mut app.camera, &mut app.scene);
+        app.scene.update_sun(app.scene.clock.delta);
+        app.scene.update_weather(app.scene.clock.delta);
+        app.scene.update_torches(time);
+        app.scene.update_light_probes();
+        app.scene.prepare_view(&app.camera, app.framebuffer.height);
+        render(&mut app.framebuffer, &app.scene, &app.camera, app.integrator.as_ref(), app.sampler.as_ref());
+
+        let path = format!("{}/frame_{:04}.png", config.frames_dir, frame + 1);
+        app.framebuffer.save(&path).unwrap_or_else(|e| panic!("{}", e));
+        println!("{} ({}/{}, {:.2} s de escena, {:.1} s)", path, frame + 1, frames, time, start.elapsed().as_secs_f32());
     }
+}
 
-    // Definir el tamaño y posición de la casa
-    let house_width = 6;
-    let house_height = 5;
-    let house_depth = 4;
-    let cube_size = 1.0;
-
-    // Crear la fachada de la casa con cubos de plank, con la puerta al frente
-    for y in 0..house_height {
-        for x in 0..house_width {
-            for z in 0..house_depth {
-                let x_pos = (x as f32) * cube_size - (grid_size as f32 * cube_size / 4.0); // Centrando la casa
-                let z_pos = (z as f32) * cube_size - (grid_size as f32 * cube_size / 2.0);
-                let y_pos = y as f32; // Altura
-
-                // Colocar la puerta en la fachada delantera
-                let material = if y == 0 && x == house_width / 2 && z == 0 {
-                    door_material.clone().into() // Puerta en la parte delantera
-                // Ventanas de 4 cubos de glass ahora en los niveles y = 2 y y = 3
-                } else if y == 2 && (x == 1 || x == house_width - 2) && (z == 0 || z == house_depth - 1) {
-                    glass_material.clone().into() // Parte inferior de las ventanas más altas
-                } else if y == 3 && (x == 1 || x == house_width - 2) && (z == 0 || z == house_depth - 1) {
-                    glass_material.clone().into() // Parte superior de las ventanas más altas
-                // Ventanas laterales
-                } else if (y == 2 || y == 3) && (x == 0 || x == house_width - 1) && (z == house_depth / 2) {
-                    glass_material.clone().into() // Ventana lateral
-                } else if y == 2 && (x == 0 || x == house_width - 1) && (z == house_depth / 2 + 1) {
-                    plank_material.clone().into() // Cubo de madera entre las ventanas laterales
-                } else if (y == 2 || y == 3) && (x == 0 || x == house_width - 1) && (z == house_depth / 2 - 1) {
-                    glass_material.clone().into() // Ventana lateral
-                // Ventana en el techo
-                } else if y == house_height - 1 && (x >= 1 && x <= 4) && z == 1 {
-                    glass_material.clone().into() // Ventana en el techo
-                } else {
-                    plank_material.clone().into() // Pared de plank
-                };
-
-                let cube = Cube::new(
-                    Vec3::new(x_pos, y_pos, z_pos), // Posición inicial
-                    Vec3::new(x_pos + cube_size, y_pos + cube_size, z_pos + cube_size), // Posición final
-                    material,
-                );
-
-                objects.push(cube);
-            }
-        }
+// Render final sin ventana: suma las muestras por píxel de una en una, informando del
+// avance, y guarda la imagen en `path`
+fn render_offline(app: &mut App, path: &str) {
+    let samples = app.scene.settings.samples_per_pixel.max(1);
+    let (width, height) = (app.framebuffer.width, app.framebuffer.height);
+    let start = Instant::now();
+    app.scene.prepare_view(&app.camera, app.framebuffer.height);
+    app.framebuffer.reset_accumulation();
+    for sample in 0..samples {
+        render_progressive(&mut app.framebuffer, &app.scene, &app.camera, app.integrator.as_ref(), app.sampler.as_ref());
+        println!("Muestra {}/{} ({:.1} s)", sample + 1, samples, start.elapsed().as_secs_f32());
     }
+    app.framebuffer.save(path).unwrap_or_else(|e| panic!("{}", e));
+    println!("{} ({}x{}, {} muestras por píxel) en {:.1} s, suma {:016x}", path, width, height, samples, start.elapsed().as_secs_f32(), app.framebuffer.checksum());
+}
 
-    // Cámara
-    let mut camera = Camera::new(Vec3::new(0.0, 3.0, -10.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+// Archivo donde se exporta la región seleccionada
+const SELECTION_PREFAB: &str = "selection.prefab";
 
-    // Luz
-    let mut lights = [
-        Light::new(Vec3::new(5.0, 5.0, -10.0), Color::new(255, 255, 255), 1.0),
-    ];
+// Controles de cámara, luz, grupos y selección; las teclas mantenidas mueven de forma
+// continua y las recién pulsadas actúan como conmutadores
+fn handle_input(input: &dyn Input, camera: &mut Camera, scene: &mut Scene, selection: &mut Selection, packs: &mut TexturePacks, ladder: &Decal) {
+    camera_controls(input, camera, scene.settings.world_scale);
+    light_controls(input, scene);
+    settings_controls(input, scene);
 
-    // Bucle principal
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        // Control de rotación de la cámara
-        let rotation_speed = PI / 10.0;
-        if window.is_key_down(Key::Left) {
-            camera.orbit(rotation_speed, 0.0);
-        }
-        if window.is_key_down(Key::Right) {
-            camera.orbit(-rotation_speed, 0.0);
-        }
-        if window.is_key_down(Key::Up) {
-            camera.orbit(0.0, -rotation_speed);
-        }
-        if window.is_key_down(Key::Down) {
-            camera.orbit(0.0, rotation_speed);
-        }
+    // T cambia al siguiente paquete de texturas de `--texture-packs`
+    if input.is_key_pressed(Key::T) {
+        packs.next(scene);
+        eprintln!("Paquete de texturas: {}", packs.current().name);
+    }
 
-        // Control de zoom
-        if window.is_key_down(Key::W) {
-            camera.zoom(0.1);  
-        }
-        if window.is_key_down(Key::S) {
-            camera.zoom(-0.1);  
+    // Edición por selección: se apunta con el centro de la pantalla.
+    // B y N eligen las esquinas, C copia, X corta, V pega, M mueve,
+    // R gira 90°, F refleja en X y E exporta como prefab la región seleccionada
+    let view_direction = normalize(&(camera.center - camera.eye));
+    let target = scene.pick_block(&camera.eye, &view_direction);
+    let mut edited = false;
+    const EDIT_KEYS: [Key; 9] = [Key::B, Key::N, Key::C, Key::X, Key::V, Key::M, Key::R, Key::F, Key::E];
+    for key in EDIT_KEYS.into_iter().filter(|key| input.is_key_pressed(*key)) {
+        match (key, target) {
+            (Key::B, Some((block, _))) => {
+                selection.first = Some(block);
+                eprintln!("Primera esquina: ({}, {}, {})", block.x, block.y, block.z);
+            }
+            (Key::N, Some((block, _))) => {
+                selection.second = Some(block);
+                eprintln!("Segunda esquina: ({}, {}, {})", block.x, block.y, block.z);
+            }
+            (Key::C, _) => {
+                selection.copy(&scene.world);
+            }
+            (Key::X, _) => edited |= selection.cut(&mut scene.world),
+            (Key::V, Some((_, free))) => edited |= selection.paste(&mut scene.world, free),
+            (Key::M, Some((_, free))) => edited |= selection.move_to(&mut scene.world, free),
+            (Key::R, _) => edited |= selection.rotate(&mut scene.world),
+            (Key::F, _) => edited |= selection.mirror(&mut scene.world, Axis::X),
+            (Key::E, _) => match selection.export(&scene.world, SELECTION_PREFAB) {
+                Ok(()) => eprintln!("Selección exportada a {}", SELECTION_PREFAB),
+                Err(e) => eprintln!("{}", e),
+            },
+            _ => {}
         }
+    }
+    if edited {
+        scene.rebuild_blocks();
+    }
 
-        // Control de la luz
-        if window.is_key_down(Key::I) {
-            lights[0].position.y += 0.1;
-        }
-        if window.is_key_down(Key::K) {
-            lights[0].position.y -= 0.1;
-        }
-        if window.is_key_down(Key::J) {
-            lights[0].position.x -= 0.1;
+    // G cuelga una escalera de mano en la cara apuntada, o quita la calcomanía apuntada
+    if input.is_key_pressed(Key::G) {
+        if let Some(index) = scene.pick_decal(&camera.eye, &view_direction) {
+            scene.remove_decal(index);
+            eprintln!("Calcomanía quitada");
+        } else if let Some((block, free)) = scene.pick_block(&camera.eye, &view_direction) {
+            let normal = free - block;
+            let mut decal = ladder.clone();
+            decal.block = block;
+            decal.face = Face::from_normal(&Vec3::new(normal.x as f32, normal.y as f32, normal.z as f32));
+            eprintln!("Escalera en ({}, {}, {}), cara {:?}", block.x, block.y, block.z, decal.face);
+            scene.add_decal(decal);
         }
-        if window.is_key_down(Key::L) {
-            lights[0].position.x += 0.1;
-        }
-        if window.is_key_down(Key::U) {
-            lights[0].position.z += 0.1;
-        }
-        if window.is_key_down(Key::O) {
-            lights[0].position.z -= 0.1;
-        }
-
-        render(&mut framebuffer, &objects, &camera, &lights);
-
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
-        std::thread::sleep(frame_delay);
     }
 }
@@ -1,12 +1,88 @@
-use crate::{color::Color, Texture};
+use std::sync::Arc;
+
+use crate::{color::Color, cube::{Face, UvOrientation}, texture::{AlphaMask, Texture}, water::Ripples};
+
+// Texturas por cara de un bloque: la tapa del grass, sus lados con el borde verde y la
+// base de tierra. Las caras sin textura propia usan la `texture` del material.
+#[derive(Debug, Clone, Default)]
+pub struct FaceTextures {
+    textures: [Option<Texture>; 6],
+}
+
+impl FaceTextures {
+    pub fn new() -> Self {
+        FaceTextures::default()
+    }
+
+    pub fn top_side_bottom(top: Texture, side: Texture, bottom: Texture) -> Self {
+        let mut faces = FaceTextures::new();
+        for face in Face::ALL {
+            let texture = match face {
+                Face::Top => top.clone(),
+                Face::Bottom => bottom.clone(),
+                _ => side.clone(),
+            };
+            faces.set(face, texture);
+        }
+        faces
+    }
+
+    // Recorta las caras de un atlas de `columns` x `rows` casillas iguales; `cells` da la
+    // casilla (contando por filas desde la esquina superior izquierda) de cada cara en el
+    // orden de `Face::ALL`
+    pub fn from_atlas(atlas: &Texture, columns: usize, rows: usize, cells: [usize; 6]) -> Self {
+        let (width, height) = atlas.size();
+        let (cell_width, cell_height) = (width / columns, height / rows);
+        let mut faces = FaceTextures::new();
+        for (face, cell) in Face::ALL.into_iter().zip(cells) {
+            let (column, row) = (cell % columns, cell / columns);
+            faces.set(face, atlas.crop(column * cell_width, row * cell_height, cell_width, cell_height));
+        }
+        faces
+    }
+
+    pub fn set(&mut self, face: Face, texture: Texture) {
+        self.textures[face.index()] = Some(texture);
+    }
+
+    pub fn get(&self, face: Face) -> Option<&Texture> {
+        self.textures[face.index()].as_ref()
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = &Texture> {
+        self.textures.iter().flatten()
+    }
+
+    pub fn textures_mut(&mut self) -> impl Iterator<Item = &mut Texture> {
+        self.textures.iter_mut().flatten()
+    }
+}
 
 #[derive(Debug, Clone)] // Solo Debug, sin Clone
 pub struct Material {
     pub diffuse: Color,
     pub specular: f32,
-    pub albedo: [f32; 4],
+    pub albedo: [f32; 4], // Pesos: difuso, especular, reflectividad y transparencia
     pub refractive_index: f32,
+    // Rugosidad de la transmisión (0 a 1): desvía cada rayo refractado dentro de un cono
+    // para el vidrio esmerilado o el hielo; 0 es vidrio transparente
+    pub transmission_roughness: f32,
     pub texture: Option<Texture>, // Campo texture definido aquí
+    pub normal_map: Option<Texture>, // Normales en espacio tangente que perturban la de la geometría
+    pub face_textures: Option<FaceTextures>, // Textura distinta por cara en cubos y bloques
+    // Giro y reflejo de la textura en cada cara de cubos y bloques, en el orden de `Face::ALL`
+    pub face_orientation: [UvOrientation; 6],
+    pub emission: Color, // Luz propia que se suma al color de la superficie
+    pub emission_intensity: f32, // Si es mayor que 0 los bloques con este material iluminan su entorno
+    // Canal alfa de la textura: los texeles casi transparentes dejan pasar el rayo y los
+    // semitransparentes mezclan la superficie con lo que hay detrás
+    pub alpha: Option<Arc<AlphaMask>>,
+    // Ondas animadas que inclinan la normal de las caras que miran arriba, para el agua
+    pub ripples: Option<Ripples>,
+    // Lámina fina sin interior (hojas, cristales, planos y mallas de una cara): la normal
+    // se gira hacia el rayo para que por detrás no quede negra. No sirve para materiales
+    // que refractan, que necesitan saber si el rayo entra o sale
+    pub two_sided: bool,
 }
 
 impl Material {
@@ -22,7 +98,16 @@ impl Material {
             specular,
             albedo,
             refractive_index,
+            transmission_roughness: 0.0,
             texture, // Inicialización del campo texture
+            normal_map: None,
+            face_textures: None,
+            face_orientation: [UvOrientation::default(); 6],
+            emission: Color::black(),
+            emission_intensity: 0.0,
+            alpha: None,
+            ripples: None,
+            two_sided: false,
         }
     }
 
@@ -32,8 +117,64 @@ impl Material {
             specular: 0.0,
             albedo: [0.0, 0.0, 0.0, 0.0],
             refractive_index: 0.0,
+            transmission_roughness: 0.0,
             texture: None, // Inicializa texture como None
+            normal_map: None,
+            face_textures: None,
+            face_orientation: [UvOrientation::default(); 6],
+            emission: Color::black(),
+            emission_intensity: 0.0,
+            alpha: None,
+            ripples: None,
+            two_sided: false,
+        }
+    }
+
+    // Si el rayo choca con la superficie en (u, v) o pasa por un hueco del canal alfa
+    pub fn covers(&self, uv: Option<(f32, f32)>) -> bool {
+        match (&self.alpha, uv) {
+            (Some(alpha), Some((u, v))) => alpha.covers(u, v),
+            _ => true,
+        }
+    }
+
+    // Opacidad de la superficie en (u, v), de 0 a 1
+    pub fn opacity(&self, uv: Option<(f32, f32)>) -> f32 {
+        match (&self.alpha, uv) {
+            (Some(alpha), Some((u, v))) => alpha.opacity(u, v),
+            _ => 1.0,
+        }
+    }
+
+    // Archivos de los que salen sus texturas
+    pub fn texture_paths(&self) -> impl Iterator<Item = &str> {
+        let faces = self.face_textures.iter().flat_map(FaceTextures::textures);
+        [&self.texture, &self.normal_map].into_iter().flatten().chain(faces)
+            .filter_map(|texture| texture.source.as_ref())
+            .map(|source| source.path())
+    }
+
+    // Copia del material con las texturas que salen de `path` regeneradas a partir de
+    // `image`, o None si ninguna sale de ese archivo
+    pub fn with_reloaded_texture(&self, path: &str, image: &Texture) -> Option<Material> {
+        let texture = self.texture.as_ref().and_then(|texture| texture.reloaded(path, image));
+        let normal_map = self.normal_map.as_ref().and_then(|texture| texture.reloaded(path, image));
+        let mut face_textures = self.face_textures.clone();
+        let mut faces_changed = false;
+        for face in face_textures.iter_mut().flat_map(FaceTextures::textures_mut) {
+            if let Some(reloaded) = face.reloaded(path, image) {
+                *face = reloaded;
+                faces_changed = true;
+            }
+        }
+        if texture.is_none() && normal_map.is_none() && !faces_changed {
+            return None;
         }
+        let mut material = self.clone();
+        material.texture = texture.or(material.texture);
+        material.normal_map = normal_map.or(material.normal_map);
+        material.face_textures = face_textures;
+        Some(material)
     }
 }
 
@@ -0,0 +1,284 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+use std::sync::Arc;
+
+use crate::bvh::Bvh;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::scene::DEFAULT_GROUP;
+
+// Por debajo de este determinante el rayo se considera paralelo al triángulo
+const PARALLEL_EPSILON: f32 = 1e-8;
+
+// Triángulo con normales y coordenadas UV opcionales por vértice
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub uvs: Option<[(f32, f32); 3]>,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vec3; 3]) -> Self {
+        Triangle { vertices, normals: None, uvs: None }
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let [a, b, c] = &self.vertices;
+        (a.inf(b).inf(c), a.sup(b).sup(c))
+    }
+
+    // Möller–Trumbore: distancia y coordenadas baricéntricas (u, v) del impacto
+    fn intersect(&self, origin: &Vec3, direction: &Vec3) -> Option<(f32, f32, f32)> {
+        let [v0, v1, v2] = &self.vertices;
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+        let p = direction.cross(&edge2);
+        let determinant = edge1.dot(&p);
+        if determinant.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let s = origin - v0;
+        let u = s.dot(&p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&edge1);
+        let v = direction.dot(&q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = edge2.dot(&q) * inverse;
+        (distance > 0.0).then_some((distance, u, v))
+    }
+
+    // Direcciones en las que crecen u y v sobre el triángulo, para los mapas de normales
+    fn tangent(&self) -> Option<(Vec3, Vec3)> {
+        let [uv0, uv1, uv2] = self.uvs?;
+        let edge1 = self.vertices[1] - self.vertices[0];
+        let edge2 = self.vertices[2] - self.vertices[0];
+        let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+        let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let tangent = (edge1 * dv2 - edge2 * dv1) / determinant;
+        let bitangent = (edge2 * du1 - edge1 * du2) / determinant;
+        Some((tangent.normalize(), bitangent.normalize()))
+    }
+}
+
+// Modelo de triángulos, normalmente cargado de un archivo OBJ. Tiene su propia BVH
+// sobre los triángulos, así que para la escena es un único objeto con una caja.
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+    bvh: Bvh,
+    min: Vec3,
+    max: Vec3,
+    pub material: Arc<Material>,
+    pub casts_shadows: bool, // Si es falso, los rayos de sombra lo atraviesan
+    pub visible_to_camera: bool, // Si es falso, los rayos primarios lo ignoran
+    pub group: usize, // Grupo de la escena al que pertenece
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>, material: Arc<Material>) -> Self {
+        let mut mesh = Mesh {
+            triangles,
+            bvh: Bvh::new(),
+            min: Vec3::zeros(),
+            max: Vec3::zeros(),
+            material,
+            casts_shadows: true,
+            visible_to_camera: true,
+            group: DEFAULT_GROUP,
+        };
+        mesh.rebuild();
+        mesh
+    }
+
+    pub fn load_obj(path: &str, material: Arc<Material>) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+        let triangles = parse_obj(&source).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Mesh::new(triangles, material))
+    }
+
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    // Escala el modelo respecto a su origen y lo lleva a `translation`
+    pub fn transform(&mut self, translation: Vec3, scale: f32) {
+        for triangle in self.triangles.iter_mut() {
+            for vertex in triangle.vertices.iter_mut() {
+                *vertex = *vertex * scale + translation;
+            }
+        }
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let bounds: Vec<(Vec3, Vec3)> = self.triangles.iter().map(Triangle::bounds).collect();
+        self.bvh = Bvh::build(&bounds);
+        self.min = bounds.iter().fold(Vec3::repeat(f32::INFINITY), |min, bound| min.inf(&bound.0));
+        self.max = bounds.iter().fold(Vec3::repeat(f32::NEG_INFINITY), |max, bound| max.sup(&bound.1));
+    }
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+        self.bvh.traverse(origin, direction, f32::INFINITY, |index| {
+            let (distance, u, v) = self.triangles[index].intersect(origin, direction)?;
+            if closest.is_none_or(|(_, best, _, _)| distance < best) {
+                closest = Some((index, distance, u, v));
+            }
+            Some(distance)
+        });
+
+        let Some((index, distance, u, v)) = closest else {
+            return Intersect::empty();
+        };
+        let triangle = &self.triangles[index];
+        let w = 1.0 - u - v;
+
+        // Normal suavizada si el OBJ trae normales; si no, la del plano del triángulo
+        let [v0, v1, v2] = &triangle.vertices;
+        let face_normal = (v1 - v0).cross(&(v2 - v0));
+        let normal = match &triangle.normals {
+            Some([n0, n1, n2]) => (n0 * w + n1 * u + n2 * v).normalize(),
+            None => face_normal.normalize(),
+        };
+
+        let point = origin + direction * distance;
+        let mut intersect = Intersect::new(point, normal, distance, self.material.clone());
+        intersect.uv = Some(match triangle.uvs {
+            Some([uv0, uv1, uv2]) => (uv0.0 * w + uv1.0 * u + uv2.0 * v, uv0.1 * w + uv1.1 * u + uv2.1 * v),
+            None => (u, v),
+        });
+        intersect.tangent = triangle.tangent();
+        // Con los vértices en sentido antihorario vistos desde fuera, el rayo entra si va contra la cara
+        intersect.front_face = face_normal.dot(direction) < 0.0;
+        intersect
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (self.min, self.max)
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Arc<Material> {
+        &mut self.material
+    }
+}
+
+// Lee los vértices (`v`), coordenadas de textura (`vt`), normales (`vn`) y caras (`f`)
+// de un OBJ. Las caras de más de tres vértices se dividen en abanico; el resto de
+// líneas (grupos, materiales, suavizado) se ignoran.
+pub fn parse_obj(source: &str) -> Result<Vec<Triangle>, String> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<(f32, f32)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut triangles = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let error = |message: &str| format!("línea {}: {}", number + 1, message);
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else { continue };
+        let values: Vec<&str> = parts.collect();
+        let floats = || {
+            values.iter()
+                .map(|value| value.parse::<f32>().map_err(|_| error(&format!("número inválido: {}", value))))
+                .collect::<Result<Vec<f32>, String>>()
+        };
+
+        match keyword {
+            "v" | "vn" => {
+                let coords = floats()?;
+                if coords.len() < 3 {
+                    return Err(error("se esperaban tres coordenadas"));
+                }
+                let vector = Vec3::new(coords[0], coords[1], coords[2]);
+                if keyword == "v" { positions.push(vector) } else { normals.push(vector.normalize()) }
+            }
+            "vt" => {
+                let coords = floats()?;
+                if coords.is_empty() {
+                    return Err(error("coordenada de textura vacía"));
+                }
+                // En OBJ v crece hacia arriba y en las texturas hacia abajo
+                uvs.push((coords[0], 1.0 - coords.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(error("una cara necesita al menos tres vértices"));
+                }
+                let corners = values.iter()
+                    .map(|corner| parse_corner(corner, positions.len(), uvs.len(), normals.len()).map_err(|e| error(&e)))
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                for i in 1..corners.len() - 1 {
+                    let [a, b, c] = [corners[0], corners[i], corners[i + 1]];
+                    let mut triangle = Triangle::new([positions[a.0], positions[b.0], positions[c.0]]);
+                    if let (Some(ua), Some(ub), Some(uc)) = (a.1, b.1, c.1) {
+                        triangle.uvs = Some([uvs[ua], uvs[ub], uvs[uc]]);
+                    }
+                    if let (Some(na), Some(nb), Some(nc)) = (a.2, b.2, c.2) {
+                        triangle.normals = Some([normals[na], normals[nb], normals[nc]]);
+                    }
+                    triangles.push(triangle);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if triangles.is_empty() {
+        return Err("el modelo no tiene caras".to_string());
+    }
+    Ok(triangles)
+}
+
+// Vértice de una cara en cualquiera de sus formas: `v`, `v/vt`, `v//vn` o `v/vt/vn`
+fn parse_corner(corner: &str, positions: usize, uvs: usize, normals: usize) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut fields = corner.split('/');
+    let position = fields.next().filter(|field| !field.is_empty()).ok_or_else(|| format!("vértice inválido: {}", corner))?;
+    let position = resolve_index(position, positions)?;
+    let uv = match fields.next() {
+        Some(field) if !field.is_empty() => Some(resolve_index(field, uvs)?),
+        _ => None,
+    };
+    let normal = match fields.next() {
+        Some(field) if !field.is_empty() => Some(resolve_index(field, normals)?),
+        _ => None,
+    };
+    Ok((position, uv, normal))
+}
+
+// Los índices de OBJ empiezan en 1; los negativos cuentan desde el último elemento leído
+fn resolve_index(field: &str, count: usize) -> Result<usize, String> {
+    let index: i64 = field.parse().map_err(|_| format!("índice inválido: {}", field))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("índice fuera de rango: {}", field));
+    }
+    Ok(resolved as usize)
+}
@@ -0,0 +1,131 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::light::Light;
+use crate::noise::lattice;
+use crate::texture::{Texture, TextureFilter};
+
+// Color del cielo en noche cerrada, que sustituye poco a poco al del día
+const NIGHT_COLOR: u32 = 0x0A0E1C;
+// Celdas por unidad de dirección en la rejilla de estrellas; más celdas, estrellas más pequeñas
+const STAR_GRID: f32 = 180.0;
+// Radio de cada estrella en celdas de la rejilla
+const STAR_RADIUS: f32 = 0.18;
+// Brillo lineal de la cara de la luna, por encima de 1 para que el tonemap la deje casi blanca
+const MOON_BRIGHTNESS: f32 = 1.6;
+// Distancia a la que se coloca la luz de la luna: lo bastante lejos para que sus rayos
+// lleguen casi paralelos a todo el mundo
+const MOON_DISTANCE: f32 = 1000.0;
+const MOON_LIGHT_COLOR: u32 = 0xB4C8FF;
+
+// Cielo nocturno que se pinta encima del cielo de día en los rayos que no golpean nada:
+// estrellas generadas por hash de la dirección y una luna con textura. `night` va de 0
+// (de día, sin efecto) a 1 (noche cerrada) y lo mueve el ciclo de día y noche.
+pub struct NightSky {
+    pub night: f32,
+    pub seed: u64,
+    // Dirección hacia la luna y su radio angular en radianes
+    pub moon_direction: Vec3,
+    pub moon_radius: f32,
+    // Sin textura la luna es un disco liso
+    pub moon_texture: Option<Texture>,
+    // Fracción de celdas de la rejilla con estrella y brillo de la más intensa
+    pub star_density: f32,
+    pub star_brightness: f32,
+    // Intensidad en noche cerrada de la luz que proyecta la luna; 0 no añade luz
+    pub moon_light: f32,
+}
+
+impl NightSky {
+    pub fn new(seed: u64) -> Self {
+        NightSky {
+            night: 1.0,
+            seed,
+            moon_direction: Vec3::new(-0.4, 0.5, -0.75).normalize(),
+            moon_radius: 0.06,
+            moon_texture: None,
+            star_density: 0.12,
+            star_brightness: 1.2,
+            moon_light: 0.15,
+        }
+    }
+
+    // Cielo visto en `direction` con `day` como color de día
+    pub fn sample(&self, direction: &Vec3, day: Color) -> Color {
+        if self.night <= 0.0 {
+            return day;
+        }
+        let direction = direction.normalize();
+        let sky = day * (1.0 - self.night) + Color::from_hex(NIGHT_COLOR) * self.night;
+        // La luna tapa las estrellas que quedan detrás
+        let light = self.moon(&direction).unwrap_or_else(|| self.stars(&direction));
+        sky + light * self.night
+    }
+
+    // Luz puntual muy lejana en la dirección de la luna, con la intensidad de noche cerrada
+    pub fn light(&self, center: Vec3) -> Light {
+        Light::new(center + self.moon_direction.normalize() * MOON_DISTANCE, Color::from_hex(MOON_LIGHT_COLOR), self.moon_light)
+    }
+
+    fn moon(&self, direction: &Vec3) -> Option<Color> {
+        let toward = self.moon_direction.normalize();
+        if direction.dot(&toward) <= self.moon_radius.cos() {
+            return None;
+        }
+
+        // Coordenadas sobre el disco, de -1 a 1, con y hacia arriba en el cielo
+        let helper = if toward.y.abs() > 0.99 { Vec3::new(0.0, 0.0, -1.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+        let right = toward.cross(&helper).normalize();
+        let up = right.cross(&toward);
+        let scale = self.moon_radius.tan();
+        let (x, y) = (direction.dot(&right) / scale, direction.dot(&up) / scale);
+        if x * x + y * y > 1.0 {
+            return None;
+        }
+
+        let face = match &self.moon_texture {
+            Some(texture) => texture.sample(x * 0.5 + 0.5, 0.5 - y * 0.5, TextureFilter::Bilinear),
+            None => Color::new(230, 230, 220),
+        };
+        Some(face * MOON_BRIGHTNESS)
+    }
+
+    // Una estrella como mucho por celda de una rejilla 3D que atraviesa la esfera de
+    // direcciones, en un punto al azar de la celda. Se apagan cerca del horizonte.
+    fn stars(&self, direction: &Vec3) -> Color {
+        if direction.y <= 0.0 {
+            return Color::black();
+        }
+
+        let point = direction * STAR_GRID;
+        let cell = point.map(f32::floor);
+        let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let presence = lattice(self.seed, x, y, z);
+        if presence >= self.star_density {
+            return Color::black();
+        }
+
+        // Centro de la estrella lejos de los bordes para que no quede cortada por la celda
+        let jitter = |salt: u64| 0.3 + 0.4 * lattice(self.seed.wrapping_add(salt), x, y, z);
+        let center = cell + Vec3::new(jitter(1), jitter(2), jitter(3));
+        // Distancia del centro a la recta de visión, en celdas
+        let offset = center - point;
+        let distance = (offset - direction * offset.dot(direction)).magnitude();
+        if distance >= STAR_RADIUS {
+            return Color::black();
+        }
+
+        let falloff = (1.0 - distance / STAR_RADIUS).powi(2);
+        // Las más raras son las más brillantes; algunas tiran a azul y otras a amarillo
+        let magnitude = (1.0 - presence / self.star_density).powi(3);
+        let tint = if lattice(self.seed.wrapping_add(4), x, y, z) < 0.5 { Color::new(200, 215, 255) } else { Color::new(255, 240, 210) };
+        let horizon = (direction.y / 0.15).min(1.0);
+        tint * (falloff * magnitude * self.star_brightness * horizon)
+    }
+}
+
+impl Default for NightSky {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
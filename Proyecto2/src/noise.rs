@@ -0,0 +1,94 @@
+// Ruido de valor (value noise) en 2D y 3D, con suma fractal de octavas.
+// Es determinista: la misma semilla y posición dan siempre el mismo valor.
+
+// Valor pseudoaleatorio en [0, 1) de un punto entero
+pub fn lattice(seed: u64, x: i32, y: i32, z: i32) -> f32 {
+    let mut h = seed ^ 0x9E37_79B9_7F4A_7C15;
+    for value in [x, y, z] {
+        h ^= value as u32 as u64;
+        h = h.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h ^= h >> 31;
+    }
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Valor en [0, 1) interpolado suavemente entre los puntos enteros vecinos
+pub fn value_noise_3d(seed: u64, x: f32, y: f32, z: f32) -> f32 {
+    let (x0, y0, z0) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+    let (tx, ty, tz) = (smooth(x - x0 as f32), smooth(y - y0 as f32), smooth(z - z0 as f32));
+
+    let corner = |dx: i32, dy: i32, dz: i32| lattice(seed, x0 + dx, y0 + dy, z0 + dz);
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), tx);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), tx);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), tx);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), tx);
+
+    lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+}
+
+// Suma de octavas de ruido 3D normalizada a [0, 1)
+pub fn fbm_3d(seed: u64, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for octave in 0..octaves.max(1) {
+        let octave_seed = seed.wrapping_add((octave as u64).wrapping_mul(0x632B_E59B_D9B4_E019));
+        total += value_noise_3d(octave_seed, x * frequency, y * frequency, z * frequency) * amplitude;
+        max_value += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    total / max_value
+}
+
+pub fn fbm_2d(seed: u64, x: f32, y: f32, octaves: u32) -> f32 {
+    fbm_3d(seed, x, y, 0.0, octaves)
+}
+
+// Ruido de gradiente (Perlin) en 2D: cada punto entero tiene una dirección
+// pseudoaleatoria y el valor sale del producto con la distancia a cada esquina.
+// Da formas más redondeadas que el ruido de valor, sin la rejilla marcada.
+pub fn perlin_2d(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let corner = |dx: i32, dy: i32| {
+        let angle = lattice(seed, x0 + dx, y0 + dy, 0) * std::f32::consts::TAU;
+        angle.cos() * (fx - dx as f32) + angle.sin() * (fy - dy as f32)
+    };
+
+    let (tx, ty) = (smooth(fx), smooth(fy));
+    let value = lerp(lerp(corner(0, 0), corner(1, 0), tx), lerp(corner(0, 1), corner(1, 1), tx), ty);
+    // En 2D el valor queda en [-√½, √½]; se lleva a [0, 1]
+    (value * std::f32::consts::FRAC_1_SQRT_2 + 0.5).clamp(0.0, 1.0)
+}
+
+// Suma de octavas de ruido de Perlin normalizada a [0, 1]
+pub fn perlin_fbm_2d(seed: u64, x: f32, y: f32, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+
+    for octave in 0..octaves.max(1) {
+        let octave_seed = seed.wrapping_add((octave as u64).wrapping_mul(0x632B_E59B_D9B4_E019));
+        total += perlin_2d(octave_seed, x * frequency, y * frequency) * amplitude;
+        max_value += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    total / max_value
+}
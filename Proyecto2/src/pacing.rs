@@ -0,0 +1,44 @@
+use std::time::{Duration, Instant};
+
+// Ritmo del bucle de la ventana: mide cuánto dura cada fotograma y solo espera lo
+// que falte para el intervalo objetivo. Si el render tarda más que el intervalo no
+// se añade ninguna pausa, así que la entrada se lee en cuanto acaba el fotograma.
+// Ni minifb ni softbuffer exponen vsync, de modo que el ritmo lo marca el reloj.
+pub struct FramePacer {
+    // Duración objetivo de cada fotograma; None para no esperar nunca
+    pub interval: Option<Duration>,
+    last_frame: Instant,
+    // Tiempo entre los dos últimos fotogramas, incluida la espera
+    pub frame_time: Duration,
+}
+
+impl FramePacer {
+    // `target_fps` de 0 o menos equivale a no limitar
+    pub fn new(target_fps: Option<f32>) -> Self {
+        FramePacer {
+            interval: target_fps.filter(|fps| *fps > 0.0).map(|fps| Duration::from_secs_f32(1.0 / fps)),
+            last_frame: Instant::now(),
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    // Marca el comienzo de un fotograma y devuelve los segundos desde el anterior
+    pub fn begin_frame(&mut self) -> f32 {
+        let now = Instant::now();
+        self.frame_time = now - self.last_frame;
+        self.last_frame = now;
+        self.frame_time.as_secs_f32()
+    }
+
+    // Instante en que debería empezar el siguiente fotograma
+    pub fn next_frame(&self) -> Instant {
+        self.last_frame + self.interval.unwrap_or(Duration::ZERO)
+    }
+
+    // Duerme hasta `next_frame`, o nada si ya pasó
+    pub fn wait(&self) {
+        if let Some(remaining) = self.next_frame().checked_duration_since(Instant::now()) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+
+// Segundos de movimiento que abarca la estela de una partícula, como el desenfoque
+// de una cámara con ese tiempo de exposición
+const STREAK_SECONDS: f32 = 0.03;
+
+// Partícula suelta (gota de lluvia, chispa...) que se dibuja como un trazo sobre la
+// imagen ya trazada, en lugar de añadir miles de objetos diminutos a la escena
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub color: Color,
+    pub opacity: f32,
+}
+
+// Dibuja cada partícula como una línea desde su posición hacia atrás en su
+// velocidad, mezclada con el color lineal del framebuffer. Las que tapa la escena
+// vista desde la cámara no se dibujan.
+pub fn draw_particles(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, particles: &[Particle]) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    for particle in particles {
+        let tail = particle.position - particle.velocity * STREAK_SECONDS;
+        let (Some(head), Some(tail)) = (camera.project(&particle.position, width, height), camera.project(&tail, width, height)) else {
+            continue;
+        };
+
+        let to_particle = particle.position - camera.eye;
+        let distance = to_particle.magnitude();
+        let hit = scene.intersect_camera(&camera.eye, &(to_particle / distance));
+        if hit.is_intersecting && hit.distance < distance {
+            continue;
+        }
+
+        let steps = (head.0 - tail.0).abs().max((head.1 - tail.1).abs()).ceil().max(1.0) as usize;
+        let color = particle.color.linear();
+        let opacity = particle.opacity.clamp(0.0, 1.0);
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (tail.0 + (head.0 - tail.0) * t, tail.1 + (head.1 - tail.1) * t);
+            if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+                continue;
+            }
+            let pixel = &mut framebuffer.hdr[y as usize * width + x as usize];
+            for (channel, value) in pixel.iter_mut().zip(color) {
+                *channel = *channel * (1.0 - opacity) + value * opacity;
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use nalgebra_glm::Vec3;
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::scene::DEFAULT_GROUP;
+use std::sync::Arc;
+
+// Grosor de la caja envolvente en el eje de la normal, para que no quede vacía
+const BOUNDS_THICKNESS: f32 = 1e-3;
+
+// Plano cuadrado centrado en `point`, para suelos o superficies de agua grandes sin
+// llenarlas de bloques. Con un `half_size` muy grande se comporta como uno infinito
+// pero conserva una caja finita para la BVH. La textura se repite cada `tile_size`
+// unidades a lo largo de dos ejes del plano.
+pub struct Plane {
+    pub point: Vec3,
+    normal: Vec3,
+    // Ejes del plano en los que crecen u y v
+    axis_u: Vec3,
+    axis_v: Vec3,
+    pub half_size: f32,
+    pub tile_size: f32,
+    pub material: Arc<Material>,
+    pub casts_shadows: bool, // Si es falso, los rayos de sombra lo atraviesan
+    pub visible_to_camera: bool, // Si es falso, los rayos primarios lo ignoran
+    pub group: usize, // Grupo de la escena al que pertenece
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, half_size: f32, material: Arc<Material>) -> Self {
+        let normal = normal.normalize();
+        // Como en las caras de los cubos: en un suelo u crece hacia +X y v hacia +Z, y en
+        // una pared la parte superior de la textura queda arriba
+        let helper = if normal.y.abs() > 0.5 { Vec3::new(0.0, 0.0, 1.0) } else { Vec3::new(0.0, -1.0, 0.0) };
+        let axis_u = normal.cross(&helper).normalize();
+        let axis_v = axis_u.cross(&normal).normalize();
+        Plane {
+            point,
+            normal,
+            axis_u,
+            axis_v,
+            half_size,
+            tile_size: 1.0,
+            material,
+            casts_shadows: true,
+            visible_to_camera: true,
+            group: DEFAULT_GROUP,
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+}
+
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let denominator = self.normal.dot(direction);
+        if denominator.abs() < 1e-6 {
+            return Intersect::empty();
+        }
+        let distance = (self.point - origin).dot(&self.normal) / denominator;
+        if distance <= 0.0 {
+            return Intersect::empty();
+        }
+
+        let point = origin + direction * distance;
+        let local = point - self.point;
+        let (u, v) = (local.dot(&self.axis_u), local.dot(&self.axis_v));
+        if u.abs() > self.half_size || v.abs() > self.half_size {
+            return Intersect::empty();
+        }
+
+        // La normal es la del plano por ambos lados, igual que la de un cubo visto desde dentro
+        let mut intersect = Intersect::new(point, self.normal, distance, self.material.clone());
+        let tile = self.tile_size.max(1e-6);
+        intersect.uv = Some(((u / tile).rem_euclid(1.0), (v / tile).rem_euclid(1.0)));
+        intersect.tangent = Some((self.axis_u, self.axis_v));
+        intersect.front_face = denominator < 0.0;
+        intersect
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let extent = (self.axis_u.abs() + self.axis_v.abs()) * self.half_size + self.normal.abs() * BOUNDS_THICKNESS;
+        (self.point - extent, self.point + extent)
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Arc<Material> {
+        &mut self.material
+    }
+}
@@ -0,0 +1,50 @@
+use nalgebra_glm::Vec3;
+
+// Rectángulo que marca una abertura (ventana, puerta) por la que entra la luz
+// del cielo. El path tracer muestrea el cielo a través de los portales en lugar
+// de esperar a que un rebote aleatorio salga por ellos, lo que reduce mucho el
+// ruido en interiores.
+#[derive(Debug, Clone)]
+pub struct Portal {
+    pub corner: Vec3,
+    pub edge_u: Vec3,
+    pub edge_v: Vec3,
+}
+
+impl Portal {
+    pub fn new(corner: Vec3, edge_u: Vec3, edge_v: Vec3) -> Self {
+        Portal { corner, edge_u, edge_v }
+    }
+
+    pub fn area(&self) -> f32 {
+        self.edge_u.cross(&self.edge_v).magnitude()
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.edge_u.cross(&self.edge_v).normalize()
+    }
+
+    // Punto uniforme sobre el rectángulo
+    pub fn sample(&self, u1: f32, u2: f32) -> Vec3 {
+        self.corner + self.edge_u * u1 + self.edge_v * u2
+    }
+
+    // Distancia a la que el rayo cruza el rectángulo, si lo cruza
+    pub fn intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+        let normal = self.normal();
+        let denom = normal.dot(ray_direction);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+
+        let t = normal.dot(&(self.corner - ray_origin)) / denom;
+        if t <= 0.0 {
+            return None;
+        }
+
+        let local = ray_origin + ray_direction * t - self.corner;
+        let u = local.dot(&self.edge_u) / self.edge_u.magnitude_squared();
+        let v = local.dot(&self.edge_v) / self.edge_v.magnitude_squared();
+        ((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)).then_some(t)
+    }
+}
@@ -0,0 +1,88 @@
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::debug_draw::draw_debug_overlay;
+use crate::framebuffer::{Framebuffer, HdrPixel};
+use crate::lens_flare::apply_lens_flare;
+use crate::particles::draw_particles;
+use crate::scene::Scene;
+
+// Efectos sobre el color lineal ya trazado, y el paso final a `buffer`. El bloom va
+// después de las partículas y el destello para que también ellos brillen; la capa de
+// depuración se dibuja al final, sin tonemap, para que sus colores no cambien.
+pub fn post_process(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
+    let settings = &scene.settings;
+    draw_particles(framebuffer, scene, camera, scene.weather.particles());
+    if settings.lens_flare > 0.0 {
+        apply_lens_flare(framebuffer, scene, camera, settings.lens_flare);
+    }
+    if settings.bloom_intensity > 0.0 {
+        apply_bloom(framebuffer, settings.bloom_threshold, settings.bloom_intensity, settings.bloom_radius);
+    }
+    framebuffer.resolve(settings.tonemap, settings.exposure);
+    if settings.debug_overlay {
+        draw_debug_overlay(framebuffer, scene, camera);
+    }
+}
+
+// Resplandor alrededor de lo que pasa de `threshold` en luminancia (bloques emisivos,
+// brillos especulares): se separa esa parte, se difumina con un desenfoque gaussiano
+// horizontal y luego vertical, y se suma a la imagen multiplicada por `intensity`.
+// `radius` es la desviación del desenfoque relativa al alto de la imagen.
+pub fn apply_bloom(framebuffer: &mut Framebuffer, threshold: f32, intensity: f32, radius: f32) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // Solo el exceso sobre el umbral, conservando el tono del píxel
+    let bright: Vec<HdrPixel> = framebuffer.hdr.par_iter()
+        .map(|&[r, g, b]| {
+            let luminance = Color::from_linear(r, g, b).luminance();
+            let excess = (luminance - threshold).max(0.0) / luminance.max(1e-6);
+            [r * excess, g * excess, b * excess]
+        })
+        .collect();
+
+    let kernel = gaussian_kernel(radius * height as f32);
+    let horizontal = blur(&bright, width, height, &kernel, (1, 0));
+    let glow = blur(&horizontal, width, height, &kernel, (0, 1));
+
+    framebuffer.hdr.par_iter_mut().zip(glow.par_iter()).for_each(|(pixel, glow)| {
+        for (channel, value) in pixel.iter_mut().zip(glow) {
+            *channel += value * intensity;
+        }
+    });
+}
+
+// Pesos normalizados de una gaussiana de desviación `sigma`, de -3σ a 3σ
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let sigma = sigma.max(0.5);
+    let half = (sigma * 3.0).ceil() as i32;
+    let weights: Vec<f32> = (-half..=half).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+// Convolución de `source` con `kernel` en la dirección `step` (1, 0) u (0, 1); fuera de
+// la imagen se repite el borde
+fn blur(source: &[HdrPixel], width: usize, height: usize, kernel: &[f32], step: (usize, usize)) -> Vec<HdrPixel> {
+    let half = (kernel.len() / 2) as isize;
+    let mut target = vec![[0.0; 3]; source.len()];
+    target.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let mut sum = [0.0; 3];
+            for (i, weight) in kernel.iter().enumerate() {
+                let offset = i as isize - half;
+                let sx = (x as isize + offset * step.0 as isize).clamp(0, width as isize - 1) as usize;
+                let sy = (y as isize + offset * step.1 as isize).clamp(0, height as isize - 1) as usize;
+                for (channel, value) in sum.iter_mut().zip(source[sy * width + sx]) {
+                    *channel += value * weight;
+                }
+            }
+            *pixel = sum;
+        }
+    });
+    target
+}
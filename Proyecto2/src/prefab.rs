@@ -0,0 +1,140 @@
+use nalgebra_glm::IVec3;
+use std::collections::BTreeSet;
+use std::fs;
+
+use crate::rng::Rng;
+use crate::world::{Axis, BlockEdit, BlockId, Palette, Structure};
+
+// Estructuras reutilizables definidas como texto. Cada línea es un comando con
+// coordenadas relativas al origen de la estructura y el nombre del bloque:
+//
+//   # comentario
+//   set    x y z bloque
+//   fill   x0 y0 z0 x1 y1 z1 bloque
+//   hollow x0 y0 z0 x1 y1 z1 bloque
+//   line   x0 y0 z0 x1 y1 z1 bloque
+//   mirror eje suma
+//
+// Los nombres de bloque se buscan en la paleta; "air" borra. `mirror` duplica
+// todo lo anterior reflejado: la coordenada `c` en el eje (x, y o z) pasa a `suma - c`.
+pub fn load_prefab(path: &str, palette: &Palette) -> Result<Structure, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+    parse_prefab(&source, palette).map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn parse_prefab(source: &str, palette: &Palette) -> Result<Structure, String> {
+    let mut structure = Structure::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let error = |message: &str| format!("línea {}: {}", number + 1, message);
+
+        let block = |name: &str| -> Result<BlockId, String> {
+            palette.id(name).ok_or_else(|| error(&format!("bloque desconocido '{}'", name)))
+        };
+        let coords = |values: &[&str]| -> Result<Vec<i32>, String> {
+            values.iter()
+                .map(|v| v.parse::<i32>().map_err(|_| error(&format!("coordenada inválida '{}'", v))))
+                .collect()
+        };
+
+        match (tokens[0], tokens.len()) {
+            ("set", 5) => {
+                let c = coords(&tokens[1..4])?;
+                structure.set_block(IVec3::new(c[0], c[1], c[2]), block(tokens[4])?);
+            }
+            ("fill" | "hollow" | "line", 8) => {
+                let c = coords(&tokens[1..7])?;
+                let (from, to) = (IVec3::new(c[0], c[1], c[2]), IVec3::new(c[3], c[4], c[5]));
+                let id = block(tokens[7])?;
+                match tokens[0] {
+                    "fill" => structure.fill(from, to, id),
+                    "hollow" => structure.hollow_box(from, to, id),
+                    _ => structure.line(from, to, id),
+                }
+            }
+            ("mirror", 3) => {
+                let axis = Axis::from_name(tokens[1]).ok_or_else(|| error(&format!("eje inválido '{}'", tokens[1])))?;
+                let sum = coords(&tokens[2..3])?;
+                structure.mirror_copy(axis, sum[0]);
+            }
+            _ => return Err(error(&format!("comando inválido '{}'", line))),
+        }
+    }
+
+    Ok(structure)
+}
+
+// Guarda una estructura en el formato de `load_prefab`, con un `set` por bloque
+pub fn save_prefab(path: &str, structure: &Structure, palette: &Palette) -> Result<(), String> {
+    let source = write_prefab(structure, palette)?;
+    fs::write(path, source).map_err(|e| format!("No se pudo escribir {}: {}", path, e))
+}
+
+pub fn write_prefab(structure: &Structure, palette: &Palette) -> Result<String, String> {
+    let name = |id: BlockId| palette.name(id).ok_or_else(|| format!("bloque sin nombre en la paleta: {}", id));
+
+    // Orden fijo para que exportar dos veces la misma región dé el mismo archivo
+    let mut blocks: Vec<(&IVec3, &BlockId)> = structure.blocks.iter().collect();
+    blocks.sort_by_key(|(pos, _)| (pos.y, pos.z, pos.x));
+
+    // Cabecera con la paleta usada, para saber qué bloques necesita la escena que lo cargue
+    let used: BTreeSet<&str> = blocks.iter().map(|(_, id)| name(**id)).collect::<Result<_, _>>()?;
+    let mut source = format!("# Bloques: {}\n", used.into_iter().collect::<Vec<_>>().join(" "));
+    for (pos, id) in blocks {
+        source.push_str(&format!("set {} {} {} {}\n", pos.x, pos.y, pos.z, name(*id)?));
+    }
+    Ok(source)
+}
+
+// Variación aleatoria al colocar un prefab, para que varias copias no se vean idénticas.
+// Con la misma semilla del `Rng` el resultado es siempre el mismo.
+#[derive(Debug, Clone, Default)]
+pub struct Variation {
+    // Girar la estructura un número aleatorio de cuartos de vuelta
+    pub rotate: bool,
+    // Desplazamiento aleatorio máximo en X y Z
+    pub jitter: i32,
+    // Pares (original, alternativo): cada par se aplica a toda la copia con `swap_chance`
+    pub swaps: Vec<(BlockId, BlockId)>,
+    pub swap_chance: f32,
+}
+
+impl Variation {
+    // Devuelve la estructura variada y el desplazamiento extra a sumar al colocarla
+    pub fn apply(&self, structure: &Structure, rng: &mut Rng) -> (Structure, IVec3) {
+        let mut varied = if self.rotate {
+            structure.rotated_y((rng.next_u32() % 4) as i32)
+        } else {
+            structure.clone()
+        };
+
+        for (original, alternative) in &self.swaps {
+            if rng.next_f32() < self.swap_chance {
+                for block in varied.blocks.values_mut() {
+                    if block == original {
+                        *block = *alternative;
+                    }
+                }
+            }
+        }
+
+        let shift = if self.jitter > 0 {
+            let span = (2 * self.jitter + 1) as u32;
+            IVec3::new(
+                (rng.next_u32() % span) as i32 - self.jitter,
+                0,
+                (rng.next_u32() % span) as i32 - self.jitter,
+            )
+        } else {
+            IVec3::new(0, 0, 0)
+        };
+
+        (varied, shift)
+    }
+}
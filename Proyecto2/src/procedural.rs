@@ -0,0 +1,100 @@
+use crate::color::Color;
+use crate::noise::{fbm_2d, perlin_fbm_2d};
+use crate::texture::Texture;
+
+// Lado en texeles de la imagen que se genera cuando hace falta una textura de verdad,
+// por ejemplo para sacar un mapa de normales
+pub const BAKE_RESOLUTION: usize = 256;
+
+// Dibujo de una textura procedural; los dos colores de `Procedural` son el fondo y
+// el detalle de cada uno
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    // Ruido de valor con `octaves` octavas entre los dos colores
+    ValueNoise { octaves: u32 },
+    // Ruido de Perlin con `octaves` octavas, más suave que el de valor
+    Perlin { octaves: u32 },
+    // Tablero de ajedrez
+    Checker,
+    // Ladrillos del primer color con juntas del segundo; `mortar` es el grosor de la
+    // junta en fracción de ladrillo
+    Brick { mortar: f32 },
+    // Vetas de madera: anillos deformados por ruido, `rings` por repetición
+    Wood { rings: f32 },
+}
+
+impl Pattern {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "value" | "noise" => Some(Pattern::ValueNoise { octaves: 4 }),
+            "perlin" => Some(Pattern::Perlin { octaves: 4 }),
+            "checker" => Some(Pattern::Checker),
+            "brick" => Some(Pattern::Brick { mortar: 0.06 }),
+            "wood" => Some(Pattern::Wood { rings: 6.0 }),
+            _ => None,
+        }
+    }
+}
+
+// Textura calculada en cada punto en lugar de leída de una imagen: no tiene resolución
+// fija, así que no se pixela por cerca que se mire. `scale` es cuántas veces se repite
+// el dibujo a lo largo de la cara, y la misma semilla da siempre el mismo dibujo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Procedural {
+    pub pattern: Pattern,
+    pub first: Color,
+    pub second: Color,
+    pub scale: f32,
+    pub seed: u64,
+}
+
+impl Procedural {
+    pub fn new(pattern: Pattern, first: Color, second: Color) -> Self {
+        Procedural { pattern, first, second, scale: 4.0, seed: 0 }
+    }
+
+    pub fn color_at(&self, u: f32, v: f32) -> Color {
+        let (x, y) = (u * self.scale, v * self.scale);
+        let mix = |t: f32| self.first * (1.0 - t) + self.second * t;
+
+        match self.pattern {
+            Pattern::ValueNoise { octaves } => mix(fbm_2d(self.seed, x, y, octaves)),
+            Pattern::Perlin { octaves } => mix(perlin_fbm_2d(self.seed, x, y, octaves)),
+            Pattern::Checker => {
+                if (x.floor() as i32 + y.floor() as i32).rem_euclid(2) == 0 { self.first } else { self.second }
+            }
+            Pattern::Brick { mortar } => {
+                // Ladrillos el doble de anchos que de altos, con las filas impares
+                // desplazadas medio ladrillo
+                let row = y.floor();
+                let column = x * 0.5 + if (row as i32).rem_euclid(2) == 0 { 0.0 } else { 0.5 };
+                let (bx, by) = (column.fract(), y.fract());
+                if bx < mortar * 0.5 || by < mortar || bx > 1.0 - mortar * 0.5 || by > 1.0 - mortar {
+                    return self.second;
+                }
+                // Cada ladrillo con un tono algo distinto
+                let shade = fbm_2d(self.seed, column.floor() * 7.3, row * 3.1, 1);
+                self.first * (0.85 + 0.3 * shade)
+            }
+            Pattern::Wood { rings } => {
+                // Distancia al eje del tronco, que corre a lo largo de v, torcida por ruido
+                let warp = perlin_fbm_2d(self.seed, x * 0.5, y * 4.0, 3);
+                let ring = ((u - 0.5).abs() * rings + warp * 1.5).fract();
+                mix(ring * ring)
+            }
+        }
+    }
+
+    // Imagen de `resolution` x `resolution` texeles con el dibujo, para lo que necesita
+    // texeles (mapas de normales, recortes de atlas)
+    pub fn bake(&self, resolution: usize) -> Texture {
+        let mut data = Vec::with_capacity(resolution * resolution);
+        for y in 0..resolution {
+            for x in 0..resolution {
+                let (u, v) = ((x as f32 + 0.5) / resolution as f32, (y as f32 + 0.5) / resolution as f32);
+                data.push(self.color_at(u, v));
+            }
+        }
+        Texture::new(data, resolution, resolution)
+    }
+}
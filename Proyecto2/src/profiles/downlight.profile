@@ -0,0 +1,10 @@
+# Foco de techo: haz estrecho hacia abajo con un anillo de luz algo más brillante
+# ángulo intensidad
+0   0.9
+10  1.0
+20  1.0
+30  0.6
+40  0.25
+55  0.05
+90  0.0
+180 0.0
@@ -0,0 +1,79 @@
+use nalgebra_glm::Vec3;
+use std::cell::RefCell;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::integrator::Integrator;
+use crate::ray_intersect::Intersect;
+use crate::render::camera_ray;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+// Depurador de un solo rayo: mientras `record` está activo, los integradores anotan
+// cada rebote (qué golpea, qué rama toma el material, qué color devuelve) en un
+// registro del hilo actual. Fuera de `record` las anotaciones no cuestan más que
+// comprobar que no hay registro, así que el render normal no se entera.
+thread_local! {
+    static LOG: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+// Anota `message` con la sangría del rebote `depth`; el texto solo se construye si se está grabando
+pub fn log(depth: u32, message: impl FnOnce() -> String) {
+    LOG.with(|log| {
+        if let Some(lines) = log.borrow_mut().as_mut() {
+            lines.push(format!("{}[{}] {}", "  ".repeat(depth as usize), depth, message()));
+        }
+    });
+}
+
+// Ejecuta `f` grabando las anotaciones de este hilo y las devuelve con su resultado
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    LOG.with(|log| *log.borrow_mut() = Some(Vec::new()));
+    let result = f();
+    let lines = LOG.with(|log| log.borrow_mut().take()).unwrap_or_default();
+    (result, lines)
+}
+
+// Traza el rayo primario que pasa por el centro del píxel (x, y) de una imagen de
+// `width` x `height` y devuelve el registro de todos sus rebotes
+pub fn trace_pixel(scene: &Scene, camera: &Camera, integrator: &dyn Integrator, sampler: &dyn Sampler, (width, height): (usize, usize), x: usize, y: usize) -> String {
+    let direction = camera_ray(camera, width, height, x as f32 + 0.5, y as f32 + 0.5);
+    let mut sampler = sampler.clone_box();
+    sampler.start_pixel(x, y, 0);
+    let (color, lines) = record(|| integrator.li(&camera.eye, &direction, scene, 0, sampler.as_mut()));
+
+    let mut report = format!("Rayo del píxel ({}, {}) desde {} hacia {}", x, y, vector(&camera.eye), vector(&direction));
+    for line in lines {
+        report += "\n";
+        report += &line;
+    }
+    report += &format!("\nColor {}", color_text(color));
+    report
+}
+
+// Qué golpeó el rayo y con qué material, en una línea
+pub fn describe_hit(intersect: &Intersect, scene: &Scene) -> String {
+    let material = &intersect.material;
+    let group = scene.groups.get(intersect.group).map_or("?", |group| group.name.as_str());
+    let face = intersect.face.map_or(String::new(), |face| format!(" cara {:?}", face));
+    let texture = if material.texture.is_some() || material.face_textures.is_some() { " con textura" } else { "" };
+    format!(
+        "golpe a {:.3} en {} normal {}{} del grupo '{}': difuso {}{}, albedo {:?}, índice {}",
+        intersect.distance, vector(&intersect.point), vector(&intersect.normal), face, group,
+        hex(material.diffuse), texture, material.albedo, material.refractive_index,
+    )
+}
+
+pub fn vector(v: &Vec3) -> String {
+    format!("({:.2}, {:.2}, {:.2})", v.x, v.y, v.z)
+}
+
+pub fn hex(color: Color) -> String {
+    format!("#{:06X}", color.to_hex())
+}
+
+// Hex en sRGB y los canales lineales sin recortar, que pueden pasar de 1
+pub fn color_text(color: Color) -> String {
+    let [r, g, b] = color.linear();
+    format!("{} ({:.3}, {:.3}, {:.3})", hex(color), r, g, b)
+}
@@ -1,5 +1,7 @@
 use nalgebra_glm::Vec3;
+use crate::cube::Face;
 use crate::material::Material;
+use crate::scene::DEFAULT_GROUP;
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -11,6 +13,10 @@ pub struct Intersect {
     pub normal: Vec3,
     pub material: Arc<Material>, // Usar Arc para compartir el material
     pub uv: Option<(f32, f32)>, // Coordenas UV opcionales
+    pub tangent: Option<(Vec3, Vec3)>, // Direcciones en las que crecen u y v sobre la superficie
+    pub face: Option<Face>, // Cara exterior golpeada, si el objeto es un cubo o un bloque
+    pub front_face: bool, // Si el rayo llega desde fuera del objeto; falso cuando sale de él
+    pub group: usize, // Grupo del objeto golpeado, lo rellena la escena
 }
 
 impl Intersect {
@@ -22,6 +28,10 @@ impl Intersect {
             is_intersecting: true,
             material,
             uv: None,
+            tangent: None,
+            face: None,
+            front_face: true,
+            group: DEFAULT_GROUP,
         }
     }
 
@@ -33,6 +43,10 @@ impl Intersect {
             normal: Vec3::new(0.0, 0.0, 0.0),
             material: Arc::new(Material::default()),
             uv: None,
+            tangent: None,
+            face: None,
+            front_face: true,
+            group: DEFAULT_GROUP,
         }
     }
 
@@ -44,6 +58,17 @@ impl Intersect {
     }
 }
 
-pub trait RayIntersect {
+// Cualquier objeto que se pueda colocar en la escena. Además de la intersección,
+// expone su caja envolvente para la BVH y las propiedades que usa la escena
+// para decidir qué rayos lo tienen en cuenta.
+pub trait RayIntersect: Send + Sync {
     fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+    // Esquinas (min, max) de la caja alineada a los ejes que lo contiene
+    fn bounds(&self) -> (Vec3, Vec3);
+    fn casts_shadows(&self) -> bool;
+    fn visible_to_camera(&self) -> bool;
+    fn group(&self) -> usize;
+    fn material(&self) -> &Arc<Material>;
+    // Para sustituir el material, p. ej. al recargar una textura
+    fn material_mut(&mut self) -> &mut Arc<Material>;
 }
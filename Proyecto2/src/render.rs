@@ -0,0 +1,178 @@
+use nalgebra_glm::{normalize, Vec3};
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::integrator::Integrator;
+use crate::post::post_process;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+
+// Traza un fotograma completo de la escena vista desde `camera` y le aplica los efectos de pantalla
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, integrator: &dyn Integrator, sampler: &dyn Sampler) {
+    trace_frame(framebuffer, scene, camera, integrator, sampler);
+    post_process(framebuffer, scene, camera);
+}
+
+// Cada píxel reinicia el sampler con `start_pixel` y escribe solo su propio valor, y los
+// promedios se suman en orden de muestra dentro del píxel: el resultado es idéntico
+// bit a bit con cualquier número de hilos y cualquier reparto de filas entre ellos
+fn trace_frame(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, integrator: &dyn Integrator, sampler: &dyn Sampler) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let samples_per_pixel = scene.settings.samples_per_pixel;
+
+    // Promedio de `samples` muestras del píxel (x, y)
+    let shade_pixel = |x: usize, y: usize, samples: u32, sampler: &mut dyn Sampler| {
+        let mut colors = Vec::with_capacity(samples as usize);
+
+        for sample_index in 0..samples {
+            sampler.start_pixel(x, y, sample_index);
+
+            // Con una sola muestra se mantiene la esquina del píxel; con varias se reparte dentro de él
+            let (offset_x, offset_y) = if samples > 1 { sampler.next_2d() } else { (0.0, 0.0) };
+            let direction = camera_ray(camera, width, height, x as f32 + offset_x, y as f32 + offset_y);
+            colors.push(integrator.li(&camera.eye, &direction, scene, 0, sampler));
+        }
+
+        Color::average(&colors).linear()
+    };
+
+    let threshold = match scene.settings.adaptive_threshold {
+        Some(threshold) if samples_per_pixel > 1 => threshold,
+        _ => {
+            // Cada fila se traza en un hilo del pool de rayon con su propia copia del sampler
+            framebuffer.par_hdr_rows_mut().for_each(|(y, row)| {
+                let mut sampler = sampler.clone_box();
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = shade_pixel(x, y, samples_per_pixel, sampler.as_mut());
+                }
+            });
+            return;
+        }
+    };
+
+    // Primera pasada con una muestra por píxel. Las diferencias se miden en la imagen
+    // ya resuelta, que es la que se ve
+    framebuffer.par_hdr_rows_mut().for_each(|(y, row)| {
+        let mut sampler = sampler.clone_box();
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = shade_pixel(x, y, 1, sampler.as_mut());
+        }
+    });
+    framebuffer.resolve(scene.settings.tonemap, scene.settings.exposure);
+
+    // Se refinan los píxeles que difieren de su vecino derecho o inferior (y ese vecino),
+    // que son los bordes de los cubos y los cambios bruscos de textura o sombra
+    let (columns, rows) = (framebuffer.width, framebuffer.height);
+    let mut refine = vec![false; columns * rows];
+    for y in 0..rows {
+        for x in 0..columns {
+            let index = y * columns + x;
+            for neighbor in [(x + 1 < columns).then_some(index + 1), (y + 1 < rows).then_some(index + columns)].into_iter().flatten() {
+                if channel_difference(framebuffer.buffer[index], framebuffer.buffer[neighbor]) > threshold {
+                    refine[index] = true;
+                    refine[neighbor] = true;
+                }
+            }
+        }
+    }
+
+    framebuffer.par_hdr_rows_mut().for_each(|(y, row)| {
+        let mut sampler = sampler.clone_box();
+        for (x, pixel) in row.iter_mut().enumerate() {
+            if refine[y * columns + x] {
+                *pixel = shade_pixel(x, y, samples_per_pixel, sampler.as_mut());
+            }
+        }
+    });
+}
+
+// Modo progresivo: suma una muestra con jitter por píxel a la acumulación del framebuffer
+// y muestra el promedio, de modo que la imagen se refina mientras la vista no cambie
+pub fn render_progressive(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, integrator: &dyn Integrator, sampler: &dyn Sampler) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let sample_index = framebuffer.accumulated_frames;
+    let count = (sample_index + 1) as f32;
+
+    framebuffer.par_accumulation_rows_mut().for_each(|(y, (row, accumulation))| {
+        let mut sampler = sampler.clone_box();
+        for (x, (pixel, sum)) in row.iter_mut().zip(accumulation.iter_mut()).enumerate() {
+            sampler.start_pixel(x, y, sample_index);
+            let (offset_x, offset_y) = sampler.next_2d();
+            let direction = camera_ray(camera, width, height, x as f32 + offset_x, y as f32 + offset_y);
+            let color = integrator.li(&camera.eye, &direction, scene, 0, sampler.as_mut());
+
+            // Se acumula el color lineal, antes del tonemap
+            for (channel, value) in sum.iter_mut().zip(color.linear()) {
+                *channel += value;
+            }
+            *pixel = sum.map(|channel| channel / count);
+        }
+    });
+    framebuffer.accumulated_frames += 1;
+    post_process(framebuffer, scene, camera);
+}
+
+// Dirección del rayo primario que pasa por el punto (x, y) de la imagen, en píxeles
+pub(crate) fn camera_ray(camera: &Camera, width: usize, height: usize, x: f32, y: f32) -> Vec3 {
+    let (width, height) = (width as f32, height as f32);
+    let aspect_ratio = width / height;
+    let perspective_scale = (camera.fov * 0.5).tan();
+
+    let screen_x = (2.0 * x) / width - 1.0;
+    let screen_y = -(2.0 * y) / height + 1.0;
+    let ray_direction = normalize(&Vec3::new(screen_x * aspect_ratio * perspective_scale, screen_y * perspective_scale, -1.0));
+    camera.base_change(&ray_direction)
+}
+
+// Mayor diferencia entre los canales de dos píxeles 0xRRGGBB, de 0 a 1
+fn channel_difference(a: u32, b: u32) -> f32 {
+    [16, 8, 0].iter()
+        .map(|shift| ((a >> shift) & 0xFF).abs_diff((b >> shift) & 0xFF))
+        .max()
+        .unwrap_or(0) as f32 / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench_scene::BenchScene;
+    use crate::integrator;
+    use crate::sampler;
+    use crate::settings::RenderSettings;
+
+    const WIDTH: usize = 48;
+    const HEIGHT: usize = 32;
+
+    // Suma de la imagen de la escena de prueba renderizada con `threads` hilos
+    fn checksum_with_threads(threads: usize, settings: &RenderSettings, progressive: bool) -> u64 {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| {
+            let (scene, camera) = BenchScene { blocks: 100, ..BenchScene::new(0) }.build(settings.clone());
+            let integrator = integrator::from_name("path").unwrap();
+            let sampler = sampler::from_name("stratified", settings.samples_per_pixel, 7).unwrap();
+            let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+            if progressive {
+                for _ in 0..settings.samples_per_pixel {
+                    render_progressive(&mut framebuffer, &scene, &camera, integrator.as_ref(), sampler.as_ref());
+                }
+            } else {
+                render(&mut framebuffer, &scene, &camera, integrator.as_ref(), sampler.as_ref());
+            }
+            framebuffer.checksum()
+        })
+    }
+
+    #[test]
+    fn adaptive_render_matches_across_thread_counts() {
+        let settings = RenderSettings { samples_per_pixel: 8, adaptive_threshold: Some(0.05), ..RenderSettings::default() };
+        assert_eq!(checksum_with_threads(1, &settings, false), checksum_with_threads(4, &settings, false));
+    }
+
+    #[test]
+    fn progressive_render_matches_across_thread_counts() {
+        let settings = RenderSettings { samples_per_pixel: 4, ..RenderSettings::default() };
+        assert_eq!(checksum_with_threads(1, &settings, true), checksum_with_threads(4, &settings, true));
+    }
+}
@@ -0,0 +1,34 @@
+// Generador pseudoaleatorio xorshift64*, suficiente para muestreo Monte Carlo
+// y sin depender de crates externas.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // El estado nunca puede ser cero en xorshift
+        let mut rng = Rng { state: seed ^ 0x9E37_79B9_7F4A_7C15 };
+        if rng.state == 0 {
+            rng.state = 0x9E37_79B9_7F4A_7C15;
+        }
+        rng.next_u64();
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    // Número en el rango [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
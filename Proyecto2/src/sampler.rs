@@ -0,0 +1,259 @@
+use crate::rng::Rng;
+
+// Fuente de números en [0, 1) para todas las decisiones aleatorias del render
+// (posición dentro del píxel, rebotes, sombras suaves). Cada muestra de cada
+// píxel comienza con `start_pixel`, y a partir de ahí cada llamada consume una
+// nueva dimensión de la secuencia.
+pub trait Sampler: Send + Sync {
+    fn start_pixel(&mut self, x: usize, y: usize, sample_index: u32);
+    fn next_1d(&mut self) -> f32;
+    fn next_2d(&mut self) -> (f32, f32);
+    // Copia independiente para otro hilo; como cada píxel reinicia la secuencia,
+    // el resultado no depende de qué hilo procese cada fila
+    fn clone_box(&self) -> Box<dyn Sampler>;
+}
+
+// Devuelve el sampler correspondiente al nombre usado en la configuración
+pub fn from_name(name: &str, samples_per_pixel: u32, seed: u64) -> Option<Box<dyn Sampler>> {
+    match name {
+        "independent" | "random" => Some(Box::new(IndependentSampler::new(seed))),
+        "stratified" => Some(Box::new(StratifiedSampler::new(samples_per_pixel, seed))),
+        "halton" => Some(Box::new(HaltonSampler::new(seed))),
+        "sobol" => Some(Box::new(SobolSampler::new(seed))),
+        _ => None,
+    }
+}
+
+// Mezcla de enteros (splitmix64) para derivar semillas por píxel y dimensión
+fn hash(values: &[u64]) -> u64 {
+    let mut h = 0x9E37_79B9_7F4A_7C15u64;
+    for &value in values {
+        h ^= value.wrapping_add(0x9E37_79B9_7F4A_7C15).wrapping_add(h << 6).wrapping_add(h >> 2);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+    }
+    h
+}
+
+fn u32_to_unit(value: u32) -> f32 {
+    (value >> 8) as f32 / (1u32 << 24) as f32
+}
+
+// Números independientes: cada píxel tiene su propio flujo pseudoaleatorio
+#[derive(Clone)]
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        IndependentSampler { seed, rng: Rng::new(seed) }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+
+    fn start_pixel(&mut self, x: usize, y: usize, sample_index: u32) {
+        self.rng = Rng::new(hash(&[self.seed, x as u64, y as u64, sample_index as u64]));
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.rng.next_f32()
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.rng.next_f32(), self.rng.next_f32())
+    }
+}
+
+// Estratificado con jitter: las muestras de un píxel caen en celdas distintas
+// de una cuadrícula, permutadas por dimensión para no correlacionarlas
+#[derive(Clone)]
+pub struct StratifiedSampler {
+    samples_per_pixel: u32,
+    strata: u32,
+    seed: u64,
+    pixel: (usize, usize),
+    sample_index: u32,
+    dimension: u64,
+    rng: Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        StratifiedSampler {
+            samples_per_pixel,
+            strata: (samples_per_pixel as f32).sqrt().ceil() as u32,
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    // Estrato asignado a la muestra actual en la dimensión actual
+    fn stratum(&mut self, count: u32) -> u32 {
+        let offset = hash(&[self.seed, self.pixel.0 as u64, self.pixel.1 as u64, self.dimension]);
+        self.dimension += 1;
+        ((self.sample_index as u64 + offset) % count as u64) as u32
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+
+    fn start_pixel(&mut self, x: usize, y: usize, sample_index: u32) {
+        self.pixel = (x, y);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Rng::new(hash(&[self.seed, x as u64, y as u64, sample_index as u64]));
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let count = self.samples_per_pixel;
+        let stratum = self.stratum(count);
+        (stratum as f32 + self.rng.next_f32()) / count as f32
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let n = self.strata;
+        let stratum = self.stratum(n * n);
+        let (sx, sy) = (stratum % n, stratum / n);
+        (
+            (sx as f32 + self.rng.next_f32()) / n as f32,
+            (sy as f32 + self.rng.next_f32()) / n as f32,
+        )
+    }
+}
+
+const PRIMES: [u32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+fn radical_inverse(base: u32, mut index: u64) -> f32 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv = inv_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base as u64) as f64 * inv;
+        index /= base as u64;
+        inv *= inv_base;
+    }
+    result.min(1.0 - f64::EPSILON) as f32
+}
+
+// Secuencia de Halton con rotación aleatoria (Cranley-Patterson) por píxel
+#[derive(Clone)]
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (usize, usize),
+    index: u64,
+    dimension: u64,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        HaltonSampler { seed, pixel: (0, 0), index: 0, dimension: 0 }
+    }
+
+    fn sample(&mut self) -> f32 {
+        let base = PRIMES[(self.dimension % PRIMES.len() as u64) as usize];
+        let shift = u32_to_unit(hash(&[self.seed, self.pixel.0 as u64, self.pixel.1 as u64, self.dimension]) as u32);
+        self.dimension += 1;
+        (radical_inverse(base, self.index) + shift).fract()
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+
+    fn start_pixel(&mut self, x: usize, y: usize, sample_index: u32) {
+        self.pixel = (x, y);
+        // El índice 0 es siempre 0 en todas las bases, así que empezamos en 1
+        self.index = sample_index as u64 + 1;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.sample()
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.sample(), self.sample())
+    }
+}
+
+// Pares de Sobol (0,2) con scrambling XOR distinto por píxel y por dimensión
+#[derive(Clone)]
+pub struct SobolSampler {
+    seed: u64,
+    pixel: (usize, usize),
+    index: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        SobolSampler { seed, pixel: (0, 0), index: 0, dimension: 0 }
+    }
+
+    fn scramble(&mut self) -> u32 {
+        let value = hash(&[self.seed, self.pixel.0 as u64, self.pixel.1 as u64, self.dimension]) as u32;
+        self.dimension += 1;
+        value
+    }
+}
+
+// Primera dimensión de Sobol: van der Corput en base 2
+fn sobol_first(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+// Segunda dimensión de Sobol a partir de sus números de dirección
+fn sobol_second(mut index: u32) -> u32 {
+    let mut v = 1u32 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+impl Sampler for SobolSampler {
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        Box::new(self.clone())
+    }
+
+    fn start_pixel(&mut self, x: usize, y: usize, sample_index: u32) {
+        self.pixel = (x, y);
+        self.index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let scramble = self.scramble();
+        u32_to_unit(sobol_first(self.index) ^ scramble)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let scramble_x = self.scramble();
+        let scramble_y = self.scramble();
+        (
+            u32_to_unit(sobol_first(self.index) ^ scramble_x),
+            u32_to_unit(sobol_second(self.index) ^ scramble_y),
+        )
+    }
+}
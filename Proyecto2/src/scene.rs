@@ -0,0 +1,648 @@
+use nalgebra_glm::{IVec3, Vec3};
+use std::sync::Arc;
+
+use crate::billboard::Billboard;
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::clock::Clock;
+use crate::cube::Cube;
+use crate::color::Color;
+use crate::decal::Decal;
+use crate::light::{AreaLight, Light, LightKind, ShadowModel};
+use crate::lightmap::Lightmap;
+use crate::light_probes::LightProbes;
+use crate::material::Material;
+use crate::night_sky::NightSky;
+use crate::portal::Portal;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::settings::RenderSettings;
+use crate::snow::Snow;
+use crate::sun::{Sun, SUN_DISTANCE};
+use crate::sky::{FlatSky, ProceduralSky, SkyModel};
+use crate::skybox::Skybox;
+use crate::texture::Texture;
+use crate::torch::Torch;
+use crate::voxel_grid::VoxelGrid;
+use crate::weather::Weather;
+use crate::world::{BlockId, World, AIR};
+
+// Grupo con nombre (terreno, casa, luces...) que se puede ocultar en tiempo de ejecución
+pub struct Group {
+    pub name: String,
+    pub visible: bool,
+}
+
+// Todo lo que los integradores necesitan para trazar rayos
+pub struct Scene {
+    // Objetos sueltos de cualquier tipo (cubos, esferas...)
+    pub objects: Vec<Box<dyn RayIntersect>>,
+    // Sprites orientados hacia la cámara; fuera de la BVH porque cambian cada fotograma
+    pub billboards: Vec<Billboard>,
+    // Láminas pegadas a las caras de los bloques; se editan con `add_decal` y `remove_decal`
+    pub decals: Vec<Decal>,
+    pub lights: Vec<Light>,
+    // Aberturas por las que el path tracer muestrea la luz del cielo
+    pub portals: Vec<Portal>,
+    pub settings: RenderSettings,
+    pub groups: Vec<Group>,
+    pub world: World,
+    // Cielo devuelto por `background`: el cubemap si hay uno, o si no `sky`, que por
+    // defecto es el degradado con el disco del sol
+    pub sky: Box<dyn SkyModel>,
+    pub skybox: Option<Skybox>,
+    // Estrellas y luna sobre el cielo de día; se añade con `add_night_sky`
+    pub night_sky: Option<NightSky>,
+    // Ciclo de día y noche; se añade con `add_sun` y lo avanza `App` en cada fotograma
+    pub sun: Option<Sun>,
+    // Fracción del color base que Whitted muestra sin luz directa; el sol la baja de noche
+    pub ambient: f32,
+    // Lluvia, cielo cubierto, niebla y superficies mojadas; lo avanza `App` en cada fotograma
+    pub weather: Weather,
+    // Nieve sobre las caras que miran arriba de todos los materiales
+    pub snow: Option<Snow>,
+    // Sombras horneadas que sustituyen a los rayos de sombra en los bloques
+    pub lightmap: Option<Lightmap>,
+    // Luz indirecta del interior para Whitted; la recaptura poco a poco `App` en cada fotograma
+    pub light_probes: Option<LightProbes>,
+    // Tiempo de la escena; lo avanza `App` en cada fotograma
+    pub clock: Clock,
+    // Ángulo en radianes que abarca un píxel desde la cámara, para elegir el nivel de
+    // mipmap de las texturas según la distancia; lo fija `prepare_view` y 0 los desactiva
+    pub pixel_angle: f32,
+    // Rejilla de bloques generada a partir de `world` por `rebuild_blocks`
+    voxels: VoxelGrid,
+    // Luces de los bloques con material emisivo, también generadas por `rebuild_blocks`
+    block_lights: Vec<Light>,
+    // Aceleración sobre `objects`; los bloques se recorren aparte en `voxels`
+    bvh: Bvh,
+    // Antorchas colocadas con `add_torch` y el índice de su luz en `lights`
+    torches: Vec<(usize, Torch)>,
+    // Índice en `lights` de la luz de la luna, si el cielo nocturno la tiene
+    moon_light: Option<usize>,
+    // Índice en `lights` de la luz del sol y centro del mundo alrededor del que gira
+    sun_light: Option<(usize, Vec3)>,
+    // Cambia cada vez que se reconstruye la geometría, para saber si un render acumulado sigue valiendo
+    revision: u64,
+}
+
+// Resultado de `Scene::trace`: lo primero que encuentra un rayo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub group: usize,
+    // Bloque del mundo golpeado, o None si fue un objeto suelto (esfera...)
+    pub block: Option<IVec3>,
+}
+
+// Qué objetos tiene en cuenta una consulta de intersección
+#[derive(Debug, Clone, Copy)]
+enum RayKind {
+    Any,
+    // Rayos primarios: se saltan los objetos ocultos para la cámara
+    Camera,
+    // Rayos de sombra: solo los objetos que proyectan sombra
+    Shadow,
+}
+
+impl RayKind {
+    fn accepts(self, object: &dyn RayIntersect) -> bool {
+        match self {
+            RayKind::Any => true,
+            RayKind::Camera => object.visible_to_camera(),
+            RayKind::Shadow => object.casts_shadows(),
+        }
+    }
+
+    // Los bloques siempre se ven; los emisivos son luces y no deben tapar los rayos
+    // de sombra hacia su centro
+    fn accepts_block(self, material: &Material) -> bool {
+        !matches!(self, RayKind::Shadow) || material.emission_intensity <= 0.0
+    }
+}
+
+// Alcance en bloques de la luz de un bloque emisivo con intensidad 1
+pub const EMISSIVE_LIGHT_RANGE: f32 = 6.0;
+
+// Índice del grupo al que pertenecen los objetos y luces que no indican otro
+pub const DEFAULT_GROUP: usize = 0;
+
+impl Scene {
+    pub fn new(objects: Vec<Box<dyn RayIntersect>>, lights: Vec<Light>, settings: RenderSettings) -> Self {
+        let groups = vec![Group { name: "default".to_string(), visible: true }];
+        Scene {
+            objects,
+            billboards: Vec::new(),
+            decals: Vec::new(),
+            lights,
+            portals: Vec::new(),
+            settings,
+            groups,
+            world: World::new(),
+            sky: Box::new(ProceduralSky::default()),
+            skybox: None,
+            night_sky: None,
+            sun: None,
+            ambient: 1.0,
+            sun_light: None,
+            weather: Weather::default(),
+            snow: None,
+            moon_light: None,
+            torches: Vec::new(),
+            lightmap: None,
+            light_probes: None,
+            clock: Clock::new(),
+            pixel_angle: 0.0,
+            voxels: VoxelGrid::new(),
+            block_lights: Vec::new(),
+            bvh: Bvh::new(),
+            revision: 0,
+        }
+    }
+
+    // Crea un grupo (o devuelve el existente con ese nombre) y retorna su índice
+    pub fn add_group(&mut self, name: &str) -> usize {
+        if let Some(index) = self.groups.iter().position(|group| group.name == name) {
+            return index;
+        }
+        self.groups.push(Group { name: name.to_string(), visible: true });
+        self.groups.len() - 1
+    }
+
+    pub fn toggle_group(&mut self, index: usize) {
+        if let Some(group) = self.groups.get_mut(index) {
+            group.visible = !group.visible;
+        }
+    }
+
+    pub fn is_group_visible(&self, index: usize) -> bool {
+        self.groups.get(index).is_none_or(|group| group.visible)
+    }
+
+    // Regenera los cubos de los bloques; hay que llamarlo tras editar `world`.
+    // El lightmap se descarta porque sus sombras ya no corresponden a los bloques.
+    pub fn rebuild_blocks(&mut self) {
+        self.lightmap = None;
+        // Las calcomanías de los bloques borrados se caen con ellos
+        self.decals.retain(|decal| self.world.get_block(decal.block) != AIR);
+        self.voxels = VoxelGrid::from_world(&self.world);
+        self.block_lights = self.world.positions().into_iter()
+            .filter_map(|pos| self.voxels.get(pos).map(|(placed, material)| (pos, placed, material)))
+            .filter(|(_, _, material)| material.emission_intensity > 0.0)
+            .map(|(pos, placed, material)| {
+                let center = Vec3::new(pos.x as f32 + 0.5, pos.y as f32 + 0.5, pos.z as f32 + 0.5);
+                let mut light = Light::new(center, material.emission, material.emission_intensity);
+                light.shadow = ShadowModel::Hard;
+                light.range = Some(EMISSIVE_LIGHT_RANGE * material.emission_intensity.sqrt());
+                light.group = placed.group;
+                light
+            })
+            .collect();
+        self.rebuild_bvh();
+    }
+
+    // Reconstruye la BVH; hay que llamarlo tras añadir, quitar o mover `objects`
+    pub fn rebuild_bvh(&mut self) {
+        let bounds: Vec<(Vec3, Vec3)> = self.objects.iter().map(|object| object.bounds()).collect();
+        self.bvh = Bvh::build(&bounds);
+        self.revision += 1;
+    }
+
+    // Añade un objeto suelto y devuelve su índice en `objects`. La BVH no se reconstruye
+    // aquí: al cargar se llama a `rebuild_blocks` una vez cuando ya está todo
+    pub fn add_object(&mut self, object: impl RayIntersect + 'static) -> usize {
+        self.objects.push(Box::new(object));
+        self.objects.len() - 1
+    }
+
+    pub fn add_cube(&mut self, min: Vec3, max: Vec3, material: Arc<Material>) -> usize {
+        self.add_object(Cube::new(min, max, material))
+    }
+
+    // Añade una luz y devuelve su índice en `lights`, el que usan las animaciones
+    pub fn add_light(&mut self, light: Light) -> usize {
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+
+    // Archivos de textura que usan los bloques y los objetos, sin repetir
+    pub fn texture_paths(&self) -> Vec<String> {
+        let objects = self.objects.iter().map(|object| object.material())
+            .chain(self.billboards.iter().map(|billboard| &billboard.material))
+            .chain(self.decals.iter().map(|decal| &decal.material));
+        let mut paths: Vec<String> = self.world.palette.materials().chain(objects)
+            .flat_map(|material| material.texture_paths().map(str::to_string))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    // Sustituye las texturas que salen de `path` por la imagen recién cargada `image`.
+    // A diferencia de `rebuild_blocks` conserva el lightmap: las sombras no cambian.
+    // Devuelve si algún material usaba ese archivo.
+    pub fn reload_texture(&mut self, path: &str, image: &Texture) -> bool {
+        let mut blocks_changed = false;
+        for material in self.world.palette.materials_mut() {
+            if let Some(reloaded) = material.with_reloaded_texture(path, image) {
+                *material = Arc::new(reloaded);
+                blocks_changed = true;
+            }
+        }
+        let mut objects_changed = false;
+        for material in self.object_materials_mut() {
+            if let Some(reloaded) = material.with_reloaded_texture(path, image) {
+                *material = Arc::new(reloaded);
+                objects_changed = true;
+            }
+        }
+
+        if blocks_changed {
+            self.voxels = VoxelGrid::from_world(&self.world);
+        }
+        if blocks_changed || objects_changed {
+            self.revision += 1;
+        }
+        blocks_changed || objects_changed
+    }
+
+    // Sustituye el material de varios tipos de bloque; los objetos que compartían el
+    // material anterior (una malla con el de cobblestone...) pasan a usar el nuevo
+    pub fn replace_block_materials(&mut self, materials: Vec<(BlockId, Material)>) {
+        if materials.is_empty() {
+            return;
+        }
+        for (id, material) in materials {
+            let previous = self.world.palette.material(id);
+            let name = self.world.palette.name(id).unwrap().to_string();
+            self.world.palette.add(&name, material);
+            let (Some(previous), Some(current)) = (previous, self.world.palette.material(id)) else { continue };
+            for material in self.object_materials_mut() {
+                if Arc::ptr_eq(material, &previous) {
+                    *material = current.clone();
+                }
+            }
+        }
+        self.voxels = VoxelGrid::from_world(&self.world);
+        self.revision += 1;
+    }
+
+    // Materiales de los objetos sueltos, de los sprites y de las calcomanías
+    fn object_materials_mut(&mut self) -> impl Iterator<Item = &mut Arc<Material>> {
+        let objects = self.objects.iter_mut().map(|object| object.material_mut());
+        objects.chain(self.billboards.iter_mut().map(|billboard| &mut billboard.material))
+            .chain(self.decals.iter_mut().map(|decal| &mut decal.material))
+    }
+
+    // Pega una calcomanía; sustituye a la que hubiera en la misma cara
+    pub fn add_decal(&mut self, decal: Decal) {
+        self.decals.retain(|other| other.block != decal.block || other.face != decal.face);
+        self.decals.push(decal);
+        self.revision += 1;
+    }
+
+    pub fn remove_decal(&mut self, index: usize) -> Option<Decal> {
+        (index < self.decals.len()).then(|| {
+            self.revision += 1;
+            self.decals.remove(index)
+        })
+    }
+
+    // Gira los sprites hacia la cámara; `App` lo hace antes de cada fotograma
+    pub fn face_billboards(&mut self, eye: &Vec3) {
+        for billboard in self.billboards.iter_mut() {
+            billboard.face(eye);
+        }
+    }
+
+    // Deja la escena lista para un fotograma de `height` píxeles de alto visto desde `camera`
+    pub fn prepare_view(&mut self, camera: &Camera, height: usize) {
+        self.face_billboards(&camera.eye);
+        self.pixel_angle = camera.fov / height.max(1) as f32;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Convierte la cara superior de los límites del mundo en una gran luz de área,
+    // para una iluminación nublada suave sin muestrear todo el cielo.
+    // Queda en el grupo "sky" para poder apagarla; devuelve el índice de la luz.
+    pub fn add_sky_ceiling(&mut self, color: Color, intensity: f32, samples: u32) -> Option<usize> {
+        // Margen alrededor del mundo para que los bordes también reciban luz de lado
+        const MARGIN: f32 = 2.0;
+
+        let (min, max) = self.world.bounds()?;
+        let width = (max.x - min.x + 1) as f32 + 2.0 * MARGIN;
+        let depth = (max.z - min.z + 1) as f32 + 2.0 * MARGIN;
+        let center = Vec3::new(
+            (min.x + max.x + 1) as f32 * 0.5,
+            (max.y + 1) as f32 + MARGIN,
+            (min.z + max.z + 1) as f32 * 0.5,
+        );
+
+        let mut ceiling = Light::new(center, color, intensity);
+        ceiling.kind = LightKind::Area(AreaLight::new(Vec3::new(width, 0.0, 0.0), Vec3::new(0.0, 0.0, depth), samples));
+        ceiling.group = self.add_group("sky");
+        self.lights.push(ceiling);
+        Some(self.lights.len() - 1)
+    }
+
+    // Pone estrellas y luna sobre el cielo y, si la luna da luz, la añade a `lights`
+    // centrada sobre el mundo, con la intensidad que corresponde a `night_sky.night`
+    pub fn add_night_sky(&mut self, night_sky: NightSky) {
+        if night_sky.moon_light > 0.0 {
+            let center = self.world.bounds()
+                .map_or(Vec3::zeros(), |(min, max)| (min + max).map(|c| c as f32 + 1.0) * 0.5);
+            let mut moon = night_sky.light(center);
+            moon.intensity *= night_sky.night;
+            moon.group = self.add_group("sky");
+            self.lights.push(moon);
+            self.moon_light = Some(self.lights.len() - 1);
+        }
+        self.night_sky = Some(night_sky);
+    }
+
+    // Pone en marcha el ciclo de día y noche. El sol toma el papel de la luz principal
+    // (`lights[0]`), conservando su modelo de sombras, o se añade si no hay luces.
+    pub fn add_sun(&mut self, sun: Sun) {
+        let center = self.world.bounds()
+            .map_or(Vec3::zeros(), |(min, max)| (min + max).map(|c| c as f32 + 1.0) * 0.5);
+        if self.lights.is_empty() {
+            self.lights.push(Light::new(center, sun.light_color(), 0.0));
+        }
+        self.sun_light = Some((0, center));
+        self.sun = Some(sun);
+        self.update_sun(0.0);
+    }
+
+    // Coloca una antorcha: su palo y su punta en `objects` y su luz en `lights`
+    pub fn add_torch(&mut self, torch: Torch) {
+        for cube in torch.cubes() {
+            self.add_object(cube);
+        }
+        let light = self.add_light(torch.light());
+        self.torches.push((light, torch));
+        self.rebuild_bvh();
+    }
+
+    // Ajusta el brillo de las antorchas al instante `time` para que parpadeen
+    pub fn update_torches(&mut self, time: f32) {
+        for (index, torch) in &self.torches {
+            if let Some(light) = self.lights.get_mut(*index) {
+                light.intensity = torch.intensity_at(time);
+            }
+        }
+    }
+
+    // Brillo de la luz `index` sin el parpadeo de las antorchas. Es el que cuenta para
+    // reiniciar el render progresivo, que así promedia el parpadeo en lugar de empezar
+    // de nuevo en cada fotograma
+    pub fn steady_intensity(&self, index: usize) -> f32 {
+        match self.torches.iter().find(|(light, _)| *light == index) {
+            Some((_, torch)) => torch.intensity,
+            None => self.lights[index].intensity,
+        }
+    }
+
+    // Avanza la hora `dt` segundos de escena y coloca la luz, el ambiente y la noche según ella
+    pub fn update_sun(&mut self, dt: f32) {
+        let Some(sun) = &mut self.sun else { return };
+        sun.advance(dt);
+        let sun = sun.clone();
+        if let Some((light, center)) = self.sun_light.and_then(|(index, center)| Some((self.lights.get_mut(index)?, center))) {
+            light.position = center + sun.direction() * SUN_DISTANCE;
+            light.color = sun.light_color();
+            light.intensity = sun.light_intensity();
+        }
+        self.ambient = sun.ambient();
+        self.set_night(sun.night());
+    }
+
+    // Cuánto es de noche (0 a 1): oscurece el cielo, muestra las estrellas y ajusta la luz de la luna
+    pub fn set_night(&mut self, amount: f32) {
+        let Some(night_sky) = &mut self.night_sky else { return };
+        night_sky.night = amount.clamp(0.0, 1.0);
+        if let Some(moon) = self.moon_light.and_then(|index| self.lights.get_mut(index)) {
+            moon.intensity = night_sky.moon_light * night_sky.night;
+        }
+    }
+
+    // Luces de los grupos visibles, incluidas las de los bloques emisivos
+    pub fn active_lights(&self) -> impl Iterator<Item = &Light> {
+        self.lights.iter().chain(&self.block_lights).filter(|light| self.is_group_visible(light.group))
+    }
+
+    // Luces visibles que iluminan el objeto golpeado, según sus exclusiones
+    pub fn lights_for<'a>(&'a self, intersect: &'a Intersect) -> impl Iterator<Item = &'a Light> {
+        self.active_lights().filter(|light| light.affects(intersect.group))
+    }
+
+    // Busca la intersección más cercana del rayo con los objetos de la escena
+    pub fn intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.closest_hit(ray_origin, ray_direction, RayKind::Any)
+    }
+
+    // Consulta de visibilidad sin renderizar: primer objeto visible en la dirección
+    // del rayo, con el mismo criterio que los rayos secundarios del trazador
+    pub fn trace(&self, origin: &Vec3, direction: &Vec3) -> Option<Hit> {
+        let (intersect, block) = self.closest_hit_block(origin, &direction.normalize(), f32::INFINITY, RayKind::Any);
+        if !intersect.is_intersecting {
+            return None;
+        }
+        Some(Hit {
+            distance: intersect.distance,
+            point: intersect.point,
+            normal: intersect.normal,
+            group: intersect.group,
+            block,
+        })
+    }
+
+    // Bloque del mundo que toca el rayo y la posición libre junto a la cara golpeada
+    pub fn pick_block(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(IVec3, IVec3)> {
+        let hit = self.intersect_camera(ray_origin, ray_direction);
+        if !hit.is_intersecting {
+            return None;
+        }
+        // Medio bloque hacia dentro de la cara golpeada cae en el interior del cubo
+        let inside = hit.point - hit.normal * 0.5;
+        let block = IVec3::new(inside.x.floor() as i32, inside.y.floor() as i32, inside.z.floor() as i32);
+        let normal = IVec3::new(hit.normal.x.round() as i32, hit.normal.y.round() as i32, hit.normal.z.round() as i32);
+        Some((block, block + normal))
+    }
+
+    // Calcomanía que se ve en primer plano a lo largo del rayo, si la hay
+    pub fn pick_decal(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<usize> {
+        let hit = self.intersect_camera(ray_origin, ray_direction);
+        self.decals.iter().enumerate()
+            .filter(|(_, decal)| self.is_group_visible(decal.group) && decal.visible_to_camera)
+            .map(|(index, decal)| (index, decal.ray_intersect(ray_origin, ray_direction)))
+            .filter(|(_, i)| i.is_intersecting && i.distance <= hit.distance + 1e-4)
+            .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+            .map(|(index, _)| index)
+    }
+
+    // Igual que `intersect`, pero ignora los objetos ocultos para la cámara
+    pub fn intersect_camera(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.closest_hit(ray_origin, ray_direction, RayKind::Camera)
+    }
+
+    // Distancia al objeto que proyecta sombra más cercano antes de `max_distance`, si lo hay
+    pub fn occluder_distance(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32) -> Option<f32> {
+        let hit = self.closest_hit_within(ray_origin, ray_direction, max_distance, RayKind::Shadow);
+        hit.is_intersecting.then_some(hit.distance)
+    }
+
+    fn closest_hit(&self, ray_origin: &Vec3, ray_direction: &Vec3, kind: RayKind) -> Intersect {
+        self.closest_hit_within(ray_origin, ray_direction, f32::INFINITY, kind)
+    }
+
+    fn closest_hit_within(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32, kind: RayKind) -> Intersect {
+        self.closest_hit_block(ray_origin, ray_direction, max_distance, kind).0
+    }
+
+    // Intersección más cercana entre los objetos sueltos y los bloques, y la posición
+    // del bloque si lo golpeado fue uno
+    fn closest_hit_block(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32, kind: RayKind) -> (Intersect, Option<IVec3>) {
+        let mut intersect = Intersect::empty();
+        let mut zbuffer = max_distance;
+        let mut closest_index = usize::MAX;
+
+        self.bvh.traverse(ray_origin, ray_direction, max_distance, |index| {
+            let object = self.objects[index].as_ref();
+            if !self.is_group_visible(object.group()) || !kind.accepts(object) {
+                return None;
+            }
+            let mut i = object.ray_intersect(ray_origin, ray_direction);
+            i.group = object.group();
+            if !i.is_intersecting || i.distance > zbuffer || !i.material.covers(i.uv) {
+                return None;
+            }
+            // Con caras compartidas a igual distancia gana el primer objeto de la lista,
+            // igual que en el recorrido lineal, para que el resultado no dependa del árbol
+            if i.distance < zbuffer || index < closest_index {
+                zbuffer = i.distance;
+                closest_index = index;
+                intersect = i;
+            }
+            Some(zbuffer)
+        });
+
+        // Los sprites y las calcomanías son pocos y se recorren uno a uno
+        let loose = self.billboards.iter().map(|billboard| billboard as &dyn RayIntersect)
+            .chain(self.decals.iter().map(|decal| decal as &dyn RayIntersect));
+        for object in loose {
+            if !self.is_group_visible(object.group()) || !kind.accepts(object) {
+                continue;
+            }
+            let mut i = object.ray_intersect(ray_origin, ray_direction);
+            if i.is_intersecting && i.distance < zbuffer {
+                i.group = object.group();
+                zbuffer = i.distance;
+                intersect = i;
+            }
+        }
+
+        // Con una cara compartida a igual distancia gana el objeto suelto
+        let block = self.voxels.traverse(ray_origin, ray_direction, zbuffer, |placed, material| {
+            self.is_group_visible(placed.group) && kind.accepts_block(material)
+        });
+        let (mut intersect, block) = match block {
+            Some(hit) if hit.intersect.distance < zbuffer => (hit.intersect, Some(hit.position)),
+            _ => (intersect, None),
+        };
+        // Las láminas de dos caras se iluminan por el lado desde el que se miran
+        if intersect.is_intersecting && intersect.material.two_sided && intersect.normal.dot(ray_direction) > 0.0 {
+            intersect.normal = -intersect.normal;
+        }
+        (intersect, block)
+    }
+
+    // Color del entorno visto en la dirección del rayo, usado en los fallos
+    // y cuando se agota la profundidad de recursión
+    pub fn background(&self, ray_direction: &Vec3) -> Color {
+        let day = match &self.skybox {
+            Some(skybox) => skybox.sample(ray_direction),
+            None => self.sky.color(&ray_direction.normalize(), self.sun_disk()),
+        };
+        let day = match &self.sun {
+            Some(sun) => sun.sky(ray_direction, day),
+            None => day,
+        };
+        let sky = match &self.night_sky {
+            Some(night_sky) => night_sky.sample(ray_direction, day),
+            None => day,
+        };
+        let sky = self.weather.atmosphere.overcast(sky);
+        match &self.settings.fog {
+            Some(fog) => fog.horizon(sky, ray_direction),
+            None => sky,
+        }
+    }
+
+    // Dirección hacia el sol y color de su luz para el cielo: los del ciclo de día si lo
+    // hay, o los de la primera luz direccional
+    fn sun_disk(&self) -> Option<(Vec3, Color)> {
+        if let Some(sun) = &self.sun {
+            return Some((sun.direction(), sun.light_color()));
+        }
+        self.lights.iter().find_map(|light| match light.kind {
+            LightKind::Directional { direction } => Some((-direction.normalize(), light.color)),
+            _ => None,
+        })
+    }
+
+    // Cielo elegido por nombre: `flat` (color liso), `gradient` (el degradado con el sol
+    // de `ProceduralSky`), `procedural` (cubemap con nubes) o la ruta de una imagen en cruz
+    pub fn set_sky(&mut self, name: &str, seed: u64) -> Result<(), String> {
+        match name {
+            "flat" => self.sky = Box::new(FlatSky::default()),
+            "gradient" => self.sky = Box::new(ProceduralSky::default()),
+            _ => {
+                self.skybox = Skybox::from_name(name, seed)?;
+                return Ok(());
+            }
+        }
+        self.skybox = None;
+        Ok(())
+    }
+
+    // Niebla sobre `color`, visto a `distance` en la dirección del rayo: la del tiempo
+    // se mezcla con el cielo de esa dirección para que el horizonte no tenga corte, y
+    // la de `settings.fog` con su propio color, que es también el del horizonte
+    pub fn fog(&self, color: Color, ray_direction: &Vec3, distance: f32) -> Color {
+        let distance = distance / self.settings.world_scale;
+        let atmosphere = &self.weather.atmosphere;
+        let color = if atmosphere.fog_density > 0.0 {
+            atmosphere.fog(color, self.background(ray_direction), distance)
+        } else {
+            color
+        };
+        match &self.settings.fog {
+            Some(fog) => fog.apply(color, distance),
+            None => color,
+        }
+    }
+
+    // Avanza el tiempo `dt` segundos; la lluvia cae sobre los límites del mundo y cada
+    // gota se detiene en el primer obstáculo bajo ella
+    pub fn update_weather(&mut self, dt: f32) {
+        let area = self.world.bounds().map(|(min, max)| (min.map(|c| c as f32), max.map(|c| c as f32 + 1.0)));
+        let ground = area.map_or(0.0, |(min, _)| min.y);
+        let mut weather = std::mem::take(&mut self.weather);
+        weather.update(dt, area, |point| {
+            self.trace(point, &Vec3::new(0.0, -1.0, 0.0)).map_or(ground, |hit| hit.point.y)
+        });
+        self.weather = weather;
+    }
+
+    // Recaptura las sondas de luz que quedaron viejas; devuelve si alguna cambió
+    pub fn update_light_probes(&mut self) -> bool {
+        let Some(mut probes) = self.light_probes.take() else { return false };
+        let changed = probes.update(self);
+        self.light_probes = Some(probes);
+        changed
+    }
+}
@@ -0,0 +1,797 @@
+use nalgebra_glm::{IVec3, Vec3};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+use crate::billboard::Billboard;
+use crate::block_shape::BlockShape;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::light::{AreaLight, Attenuation, Light, LightKind, ShadowModel, SpotLight};
+use crate::light_profile::LightProfile;
+use crate::cube::{Face, UvOrientation};
+use crate::decal::Decal;
+use crate::material::{FaceTextures, Material};
+use crate::mesh::Mesh;
+use crate::night_sky::NightSky;
+use crate::plane::Plane;
+use crate::portal::Portal;
+use crate::prefab::load_prefab;
+use crate::scene::{Scene, DEFAULT_GROUP};
+use crate::settings::RenderSettings;
+use crate::sky::{FlatSky, SKY_COLOR};
+use crate::snow::Snow;
+use crate::sphere::Sphere;
+use crate::fog::{Fog, DEFAULT_FOG_COLOR};
+use crate::procedural::{Pattern, Procedural};
+use crate::texture::{AlphaMask, Texture, TextureFilter};
+use crate::water::Ripples;
+use crate::torch::Torch;
+use crate::world::BlockEdit;
+
+// Descripción de una escena en JSON para editar el diorama sin recompilar.
+// Los materiales se registran como tipos de bloque con su nombre, y los grupos
+// se crean la primera vez que se nombran.
+#[derive(Deserialize)]
+struct SceneFile {
+    camera: CameraDesc,
+    // Color del cielo sin cubemap; con `skybox_bottom` es la parte de arriba de un
+    // degradado vertical que baja hasta ese otro color
+    #[serde(default)]
+    skybox: Option<[u8; 3]>,
+    #[serde(default)]
+    skybox_bottom: Option<[u8; 3]>,
+    // Cielo: "flat", "gradient", "procedural" (cubemap con nubes) o la ruta de una imagen
+    // en cruz; sin él ni `skybox` es el degradado con el disco del sol
+    #[serde(default)]
+    sky: Option<String>,
+    // Nombre de la textura -> ruta de la imagen
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    #[serde(default)]
+    materials: Vec<MaterialDesc>,
+    #[serde(default)]
+    blocks: Vec<BlockDesc>,
+    #[serde(default)]
+    prefabs: Vec<PrefabDesc>,
+    #[serde(default)]
+    spheres: Vec<SphereDesc>,
+    #[serde(default)]
+    meshes: Vec<MeshDesc>,
+    #[serde(default)]
+    planes: Vec<PlaneDesc>,
+    #[serde(default)]
+    billboards: Vec<BillboardDesc>,
+    #[serde(default)]
+    torches: Vec<TorchDesc>,
+    #[serde(default)]
+    decals: Vec<DecalDesc>,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+    #[serde(default)]
+    portals: Vec<PortalDesc>,
+    #[serde(default)]
+    sky_ceiling: Option<SkyCeilingDesc>,
+    #[serde(default)]
+    night: Option<NightDesc>,
+    #[serde(default)]
+    snow: Option<SnowDesc>,
+    // Unidades del mundo por bloque; sustituye a `--world-scale`
+    #[serde(default)]
+    world_scale: Option<f32>,
+    // Ajustes del render propios de la escena; sustituyen a los de la línea de órdenes
+    #[serde(default)]
+    settings: Option<SettingsDesc>,
+}
+
+// Lo que no se indica conserva el valor de la línea de órdenes
+#[derive(Deserialize)]
+struct SettingsDesc {
+    #[serde(default)]
+    max_depth: Option<u32>,
+    #[serde(default)]
+    bias: Option<f32>,
+    #[serde(default)]
+    samples_per_pixel: Option<u32>,
+    #[serde(default)]
+    shadow_samples: Option<u32>,
+    #[serde(default)]
+    contact_hardening: Option<f32>,
+    #[serde(default)]
+    shadow_normal_bias: Option<f32>,
+    #[serde(default)]
+    shadow_slope_bias: Option<f32>,
+    #[serde(default)]
+    fog: Option<FogDesc>,
+    #[serde(default)]
+    ao_samples: Option<u32>,
+    #[serde(default)]
+    exposure: Option<f32>,
+}
+
+// Niebla exponencial con `density`, o lineal de `start` a `end` bloques
+#[derive(Deserialize)]
+struct FogDesc {
+    #[serde(default)]
+    density: Option<f32>,
+    #[serde(default)]
+    start: Option<f32>,
+    #[serde(default)]
+    end: Option<f32>,
+    #[serde(default)]
+    color: Option<[u8; 3]>,
+}
+
+// Estrellas y luna; lo que no se indica toma el valor de `NightSky::new`
+#[derive(Deserialize)]
+struct NightDesc {
+    #[serde(default = "default_night")]
+    amount: f32,
+    #[serde(default)]
+    moon_direction: Option<[f32; 3]>,
+    // Radio angular de la luna en grados
+    #[serde(default)]
+    moon_size: Option<f32>,
+    // Nombre de una textura de `textures`
+    #[serde(default)]
+    moon_texture: Option<String>,
+    #[serde(default)]
+    star_density: Option<f32>,
+    #[serde(default)]
+    moon_light: Option<f32>,
+}
+
+fn default_night() -> f32 {
+    1.0
+}
+
+// Nieve sobre las caras que miran arriba
+#[derive(Deserialize)]
+struct SnowDesc {
+    #[serde(default = "default_coverage")]
+    coverage: f32,
+    // Nombre de una textura de `textures`
+    #[serde(default)]
+    texture: Option<String>,
+}
+
+fn default_coverage() -> f32 {
+    1.0
+}
+
+// Techo emisivo sobre los límites del mundo
+#[derive(Deserialize)]
+struct SkyCeilingDesc {
+    #[serde(default = "default_light_color")]
+    color: [u8; 3],
+    #[serde(default = "default_intensity")]
+    intensity: f32,
+    #[serde(default = "default_area_samples")]
+    samples: u32,
+}
+
+// Rectángulo con una esquina y sus dos lados
+#[derive(Deserialize)]
+struct PortalDesc {
+    corner: [f32; 3],
+    edge_u: [f32; 3],
+    edge_v: [f32; 3],
+}
+
+#[derive(Deserialize)]
+struct CameraDesc {
+    eye: [f32; 3],
+    center: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    // Campo de visión vertical en grados
+    #[serde(default)]
+    fov: Option<f32>,
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+struct MaterialDesc {
+    name: String,
+    #[serde(default)]
+    diffuse: [u8; 3],
+    #[serde(default)]
+    specular: f32,
+    albedo: [f32; 4],
+    #[serde(default)]
+    refractive_index: f32,
+    // 0 vidrio transparente, hasta 1 esmerilado
+    #[serde(default)]
+    transmission_roughness: f32,
+    #[serde(default)]
+    texture: Option<String>,
+    // Tablero de ajedrez con esos dos colores en lugar de una textura
+    #[serde(default)]
+    checker: Option<[[u8; 3]; 2]>,
+    // Textura generada sin imagen: ruido, tablero, ladrillo o madera
+    #[serde(default)]
+    procedural: Option<ProceduralDesc>,
+    // Textura por cara: "top", "bottom", "side" (los cuatro lados) o "north", "south",
+    // "east" y "west" -> nombre de la textura; o las seis caras recortadas de un atlas
+    #[serde(default)]
+    faces: HashMap<String, String>,
+    #[serde(default)]
+    atlas: Option<AtlasDesc>,
+    // Orientación de la textura por cara, con los mismos nombres que `faces`
+    #[serde(default)]
+    orientation: HashMap<String, OrientationDesc>,
+    // "nearest" o "bilinear"; si no se indica se usa el filtro global
+    #[serde(default)]
+    filter: Option<String>,
+    // Textura con un mapa de normales, o `bump` para derivarlo del brillo de `texture`
+    #[serde(default)]
+    normal_map: Option<String>,
+    #[serde(default)]
+    bump: Option<f32>,
+    // Imagen con canal alfa: recorta los texeles transparentes y mezcla los semitransparentes
+    #[serde(default)]
+    alpha: Option<String>,
+    // Luz propia; con intensidad mayor que 0 los bloques de este material iluminan su entorno
+    #[serde(default)]
+    emission: [u8; 3],
+    #[serde(default)]
+    emission_intensity: f32,
+    // Ondas animadas en la superficie, para el agua
+    #[serde(default)]
+    ripples: Option<RipplesDesc>,
+    // Lámina de dos caras, iluminada también por detrás
+    #[serde(default)]
+    two_sided: bool,
+    // Forma del bloque: "full" (por defecto), "pane_x", "pane_y", "pane_z" o "post"
+    #[serde(default)]
+    shape: Option<String>,
+}
+
+// `rotate` en grados múltiplos de 90 en sentido horario; `flip` refleja en horizontal antes de girar
+#[derive(Deserialize)]
+struct OrientationDesc {
+    #[serde(default)]
+    rotate: i32,
+    #[serde(default)]
+    flip: bool,
+}
+
+// Parámetros de `Ripples`; los que falten toman su valor por defecto
+#[derive(Deserialize)]
+struct RipplesDesc {
+    #[serde(default)]
+    amplitude: Option<f32>,
+    #[serde(default)]
+    wavelength: Option<f32>,
+    #[serde(default)]
+    speed: Option<f32>,
+}
+
+// `pattern` es "value", "perlin", "checker", "brick" o "wood"; `colors` son el fondo
+// y el detalle (en el ladrillo, la junta) y `scale` las repeticiones por cara
+#[derive(Deserialize)]
+struct ProceduralDesc {
+    pattern: String,
+    colors: [[u8; 3]; 2],
+    #[serde(default)]
+    scale: Option<f32>,
+    #[serde(default)]
+    seed: u64,
+}
+
+// Atlas de `columns` x `rows` casillas; `cells` es la casilla de cada cara en el
+// orden este, oeste, arriba, abajo, sur, norte
+#[derive(Deserialize)]
+struct AtlasDesc {
+    texture: String,
+    columns: usize,
+    rows: usize,
+    cells: [usize; 6],
+}
+
+// Mismas operaciones que los prefabs: `set` usa solo `from`
+#[derive(Deserialize)]
+struct BlockDesc {
+    op: String,
+    from: [i32; 3],
+    #[serde(default)]
+    to: Option<[i32; 3]>,
+    block: String,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PrefabDesc {
+    path: String,
+    at: [i32; 3],
+    // Cuartos de vuelta alrededor del eje Y
+    #[serde(default)]
+    rotate: i32,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SphereDesc {
+    center: [f32; 3],
+    radius: f32,
+    material: String,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+// Modelo OBJ escalado y colocado con su origen en `at`
+#[derive(Deserialize)]
+struct MeshDesc {
+    path: String,
+    material: String,
+    at: [f32; 3],
+    #[serde(default = "default_scale")]
+    scale: f32,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+// Plano cuadrado de lado 2·`half_size` (por defecto casi infinito) con la textura
+// repetida cada `tile_size` unidades
+#[derive(Deserialize)]
+struct PlaneDesc {
+    point: [f32; 3],
+    #[serde(default = "default_up")]
+    normal: [f32; 3],
+    #[serde(default = "default_half_size")]
+    half_size: f32,
+    #[serde(default = "default_scale")]
+    tile_size: f32,
+    material: String,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+fn default_half_size() -> f32 {
+    1000.0
+}
+
+// Sprite hacia la cámara con el color del material y, si se indica, la silueta del
+// canal alfa de `alpha` (normalmente la misma imagen que la textura)
+#[derive(Deserialize)]
+struct BillboardDesc {
+    position: [f32; 3],
+    size: [f32; 2],
+    material: String,
+    #[serde(default)]
+    alpha: Option<String>,
+    #[serde(default = "default_upright")]
+    upright: bool,
+    #[serde(default)]
+    casts_shadows: bool,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+fn default_upright() -> bool {
+    true
+}
+
+// Lámina pegada a la cara `face` ("east", "top", "north"...) del bloque `block`
+#[derive(Deserialize)]
+struct DecalDesc {
+    block: [i32; 3],
+    face: String,
+    material: String,
+    #[serde(default)]
+    size: Option<[f32; 2]>,
+    #[serde(default)]
+    alpha: Option<String>,
+    #[serde(default = "default_decal_shadows")]
+    casts_shadows: bool,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+fn default_decal_shadows() -> bool {
+    true
+}
+
+// Antorcha con su luz; `material` es el del palo y lo demás toma el valor de `Torch::new`
+#[derive(Deserialize)]
+struct TorchDesc {
+    position: [f32; 3],
+    material: String,
+    #[serde(default)]
+    height: Option<f32>,
+    #[serde(default)]
+    color: Option<[u8; 3]>,
+    #[serde(default)]
+    intensity: Option<f32>,
+    #[serde(default)]
+    flicker: Option<f32>,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LightDesc {
+    position: [f32; 3],
+    #[serde(default = "default_light_color")]
+    color: [u8; 3],
+    #[serde(default = "default_intensity")]
+    intensity: f32,
+    #[serde(default)]
+    shadow: Option<String>,
+    #[serde(default)]
+    shadow_bias: Option<f32>,
+    // Con false la luz no proyecta sombras, para luces de relleno
+    #[serde(default = "default_light_shadows")]
+    casts_shadows: bool,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    excluded_groups: Vec<String>,
+    // Perfil angular de intensidad y hacia dónde apunta
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(default)]
+    direction: Option<[f32; 3]>,
+    #[serde(default)]
+    area: Option<AreaDesc>,
+    // Coeficientes constante, lineal y cuadrático de la caída con la distancia
+    #[serde(default)]
+    attenuation: Option<[f32; 3]>,
+    #[serde(default)]
+    spot: Option<SpotDesc>,
+    // Luz direccional cuyos rayos viajan en esta dirección, como los del sol
+    #[serde(default)]
+    directional: Option<[f32; 3]>,
+}
+
+// Foco: eje del cono y ángulos interior y exterior en grados
+#[derive(Deserialize)]
+struct SpotDesc {
+    direction: [f32; 3],
+    inner_angle: f32,
+    outer_angle: f32,
+    #[serde(default = "default_spot_falloff")]
+    falloff: f32,
+}
+
+fn default_spot_falloff() -> f32 {
+    1.0
+}
+
+// Emisor rectangular centrado en la posición de la luz
+#[derive(Deserialize)]
+struct AreaDesc {
+    edge_u: [f32; 3],
+    edge_v: [f32; 3],
+    #[serde(default = "default_area_samples")]
+    samples: u32,
+}
+
+fn default_area_samples() -> u32 {
+    16
+}
+
+fn default_light_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+fn default_light_shadows() -> bool {
+    true
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0], v[1], v[2])
+}
+
+fn ivec3(v: [i32; 3]) -> IVec3 {
+    IVec3::new(v[0], v[1], v[2])
+}
+
+fn color(c: [u8; 3]) -> Color {
+    Color::new(c[0], c[1], c[2])
+}
+
+// Índice del grupo con ese nombre, o el grupo por defecto si no se indica
+fn group(scene: &mut Scene, name: &Option<String>) -> usize {
+    name.as_deref().map_or(DEFAULT_GROUP, |name| scene.add_group(name))
+}
+
+pub fn load_scene(path: &str, settings: RenderSettings) -> Result<(Scene, Camera), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path, e))?;
+    let file: SceneFile = serde_json::from_str(&source).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut scene = Scene::new(Vec::new(), Vec::new(), settings);
+    if let Some(scale) = file.world_scale {
+        scene.settings.world_scale = scale.max(1e-4);
+    }
+    if let Some(desc) = &file.settings {
+        let settings = &mut scene.settings;
+        settings.max_depth = desc.max_depth.unwrap_or(settings.max_depth);
+        settings.origin_bias = desc.bias.unwrap_or(settings.origin_bias);
+        settings.samples_per_pixel = desc.samples_per_pixel.unwrap_or(settings.samples_per_pixel).max(1);
+        settings.shadow_samples = desc.shadow_samples.map(|samples| samples.max(1)).or(settings.shadow_samples);
+        settings.contact_hardening = desc.contact_hardening.unwrap_or(settings.contact_hardening).max(0.0);
+        settings.shadow_normal_bias = desc.shadow_normal_bias.unwrap_or(settings.shadow_normal_bias).max(0.0);
+        settings.shadow_slope_bias = desc.shadow_slope_bias.unwrap_or(settings.shadow_slope_bias).max(0.0);
+        settings.ao_samples = desc.ao_samples.unwrap_or(settings.ao_samples);
+        if let Some(fog) = &desc.fog {
+            let fog_color = fog.color.map_or(Color::from_hex(DEFAULT_FOG_COLOR), color);
+            settings.fog = Some(match (fog.density, fog.start, fog.end) {
+                (_, Some(start), Some(end)) => Fog::linear(fog_color, start, end),
+                (Some(density), None, None) => Fog::exponential(fog_color, density),
+                _ => return Err("La niebla necesita `density` o `start` y `end`".to_string()),
+            });
+        }
+        settings.exposure = desc.exposure.unwrap_or(settings.exposure).max(0.0);
+    }
+    if let Some(sky) = &file.sky {
+        scene.set_sky(sky, 0)?;
+    }
+    if file.skybox.is_some() || file.skybox_bottom.is_some() {
+        let top = file.skybox.map_or(Color::from_hex(SKY_COLOR), color);
+        scene.sky = Box::new(FlatSky { top, bottom: file.skybox_bottom.map(color) });
+    }
+
+    let mut textures = HashMap::new();
+    for (name, texture_path) in &file.textures {
+        textures.insert(name.as_str(), Texture::load(texture_path)?);
+    }
+
+    for desc in &file.materials {
+        let mut texture = match (&desc.texture, desc.checker, &desc.procedural) {
+            (Some(name), _, _) => Some(textures.get(name.as_str()).cloned().ok_or_else(|| format!("Textura desconocida: {}", name))?),
+            (None, Some([first, second]), _) => Some(Texture::checkerboard(color(first), color(second))),
+            (None, None, Some(procedural)) => {
+                let pattern = Pattern::from_name(&procedural.pattern)
+                    .ok_or_else(|| format!("Patrón procedural desconocido: {}", procedural.pattern))?;
+                let [first, second] = procedural.colors;
+                let mut generated = Procedural::new(pattern, color(first), color(second));
+                generated.scale = procedural.scale.unwrap_or(generated.scale);
+                generated.seed = procedural.seed;
+                Some(Texture::procedural(generated))
+            }
+            (None, None, None) => None,
+        };
+        if let (Some(texture), Some(name)) = (texture.as_mut(), &desc.filter) {
+            texture.filter = Some(TextureFilter::from_name(name).ok_or_else(|| format!("Filtro de textura desconocido: {}", name))?);
+        }
+        let normal_map = match (&desc.normal_map, desc.bump, &texture) {
+            (Some(name), _, _) => Some(textures.get(name.as_str()).cloned().ok_or_else(|| format!("Textura desconocida: {}", name))?),
+            (None, Some(strength), Some(texture)) => Some(texture.to_normal_map(strength)),
+            (None, Some(_), None) => return Err(format!("El material {} usa bump sin textura", desc.name)),
+            (None, None, _) => None,
+        };
+        let find_texture = |name: &str| textures.get(name).cloned().ok_or_else(|| format!("Textura desconocida: {}", name));
+        let mut face_textures = match &desc.atlas {
+            Some(atlas) => Some(FaceTextures::from_atlas(&find_texture(&atlas.texture)?, atlas.columns, atlas.rows, atlas.cells)),
+            None => None,
+        };
+        for (face_name, texture_name) in &desc.faces {
+            let faces = Face::from_name(face_name).ok_or_else(|| format!("Cara desconocida: {}", face_name))?;
+            let texture = find_texture(texture_name)?;
+            for face in faces {
+                face_textures.get_or_insert_with(FaceTextures::new).set(face, texture.clone());
+            }
+        }
+
+        let mut material = Material::new(color(desc.diffuse), desc.specular, desc.albedo, desc.refractive_index, texture);
+        material.normal_map = normal_map;
+        material.face_textures = face_textures;
+        material.transmission_roughness = desc.transmission_roughness.clamp(0.0, 1.0);
+        material.two_sided = desc.two_sided;
+        for (face_name, orientation) in &desc.orientation {
+            let faces = Face::from_name(face_name).ok_or_else(|| format!("Cara desconocida: {}", face_name))?;
+            let orientation = UvOrientation::new(orientation.rotate, orientation.flip)
+                .ok_or_else(|| format!("Giro de textura inválido (múltiplos de 90): {}", orientation.rotate))?;
+            for face in faces {
+                material.face_orientation[face.index()] = orientation;
+            }
+        }
+        material.emission = color(desc.emission);
+        material.emission_intensity = desc.emission_intensity;
+        material.ripples = desc.ripples.as_ref().map(|ripples| {
+            let defaults = Ripples::default();
+            Ripples {
+                amplitude: ripples.amplitude.unwrap_or(defaults.amplitude),
+                wavelength: ripples.wavelength.unwrap_or(defaults.wavelength),
+                speed: ripples.speed.unwrap_or(defaults.speed),
+                ..defaults
+            }
+        });
+        material.alpha = match &desc.alpha {
+            Some(path) => Some(Arc::new(AlphaMask::load(path)?)),
+            None => None,
+        };
+        let shape = match &desc.shape {
+            Some(name) => BlockShape::from_name(name).ok_or_else(|| format!("Forma desconocida: {}", name))?,
+            None => BlockShape::Full,
+        };
+        scene.world.palette.add_shaped(&desc.name, material, shape);
+    }
+
+    for desc in &file.blocks {
+        scene.world.active_group = group(&mut scene, &desc.group);
+        let world = &mut scene.world;
+        let block = world.palette.id(&desc.block).ok_or_else(|| format!("Bloque desconocido: {}", desc.block))?;
+        let from = ivec3(desc.from);
+        let to = desc.to.map_or(from, ivec3);
+        match desc.op.as_str() {
+            "set" => world.set_block(from, block),
+            "fill" => world.fill(from, to, block),
+            "hollow" => world.hollow_box(from, to, block),
+            "line" => world.line(from, to, block),
+            op => return Err(format!("Operación de bloques desconocida: {}", op)),
+        }
+    }
+
+    for desc in &file.prefabs {
+        scene.world.active_group = group(&mut scene, &desc.group);
+        let structure = load_prefab(&desc.path, &scene.world.palette)?.rotated_y(desc.rotate);
+        scene.world.stamp(&structure, ivec3(desc.at));
+    }
+
+    for desc in &file.spheres {
+        let material_id = scene.world.palette.id(&desc.material).ok_or_else(|| format!("Material desconocido: {}", desc.material))?;
+        let mut sphere = Sphere::new(vec3(desc.center), desc.radius, scene.world.palette.material(material_id).unwrap());
+        sphere.group = group(&mut scene, &desc.group);
+        scene.add_object(sphere);
+    }
+
+    for desc in &file.meshes {
+        let material_id = scene.world.palette.id(&desc.material).ok_or_else(|| format!("Material desconocido: {}", desc.material))?;
+        let mut mesh = Mesh::load_obj(&desc.path, scene.world.palette.material(material_id).unwrap())?;
+        mesh.transform(vec3(desc.at), desc.scale);
+        mesh.group = group(&mut scene, &desc.group);
+        scene.add_object(mesh);
+    }
+
+    for desc in &file.planes {
+        let material_id = scene.world.palette.id(&desc.material).ok_or_else(|| format!("Material desconocido: {}", desc.material))?;
+        let mut plane = Plane::new(vec3(desc.point), vec3(desc.normal), desc.half_size, scene.world.palette.material(material_id).unwrap());
+        plane.tile_size = desc.tile_size;
+        plane.group = group(&mut scene, &desc.group);
+        scene.add_object(plane);
+    }
+
+    for desc in &file.billboards {
+        let material_id = scene.world.palette.id(&desc.material).ok_or_else(|| format!("Material desconocido: {}", desc.material))?;
+        let [width, height] = desc.size;
+        let mut billboard = Billboard::new(vec3(desc.position), (width, height), scene.world.palette.material(material_id).unwrap());
+        billboard.alpha = match &desc.alpha {
+            Some(path) => Some(Arc::new(AlphaMask::load(path)?)),
+            None => None,
+        };
+        billboard.upright = desc.upright;
+        billboard.casts_shadows = desc.casts_shadows;
+        billboard.group = group(&mut scene, &desc.group);
+        scene.billboards.push(billboard);
+    }
+
+    for desc in &file.decals {
+        let material_id = scene.world.palette.id(&desc.material).ok_or_else(|| format!("Material desconocido: {}", desc.material))?;
+        let face = match Face::from_name(&desc.face).as_deref() {
+            Some([face]) => *face,
+            _ => return Err(format!("Cara desconocida: {}", desc.face)),
+        };
+        let mut decal = Decal::new(ivec3(desc.block), face, scene.world.palette.material(material_id).unwrap());
+        if let Some([width, height]) = desc.size {
+            decal.size = (width, height);
+        }
+        decal.alpha = match &desc.alpha {
+            Some(path) => Some(Arc::new(AlphaMask::load(path)?)),
+            None => None,
+        };
+        decal.casts_shadows = desc.casts_shadows;
+        decal.group = group(&mut scene, &desc.group);
+        scene.add_decal(decal);
+    }
+
+    for (index, desc) in file.torches.iter().enumerate() {
+        let material_id = scene.world.palette.id(&desc.material).ok_or_else(|| format!("Material desconocido: {}", desc.material))?;
+        let mut torch = Torch::new(vec3(desc.position), scene.world.palette.material(material_id).unwrap());
+        if let Some(height) = desc.height {
+            torch.height = height;
+        }
+        if let Some(flame) = desc.color {
+            torch.flame_color = color(flame);
+        }
+        if let Some(intensity) = desc.intensity {
+            torch.intensity = intensity;
+        }
+        if let Some(flicker) = desc.flicker {
+            torch.flicker = flicker;
+        }
+        // Cada antorcha parpadea a su ritmo
+        torch.seed = index as u64;
+        torch.group = group(&mut scene, &desc.group);
+        scene.add_torch(torch);
+    }
+
+    for desc in &file.lights {
+        let mut light = Light::new(vec3(desc.position), color(desc.color), desc.intensity);
+        if let Some(name) = &desc.shadow {
+            light.shadow = ShadowModel::from_name(name).ok_or_else(|| format!("Modelo de sombras desconocido: {}", name))?;
+        }
+        light.shadow_bias = desc.shadow_bias;
+        light.casts_shadows = desc.casts_shadows;
+        light.group = group(&mut scene, &desc.group);
+        light.excluded_groups = desc.excluded_groups.iter().map(|name| scene.add_group(name)).collect();
+        if let Some(path) = &desc.profile {
+            let mut profile = LightProfile::load(path)?;
+            if let Some(direction) = desc.direction {
+                profile.direction = vec3(direction);
+            }
+            light.profile = Some(Arc::new(profile));
+        }
+        light.kind = match (desc.directional, &desc.spot, &desc.area) {
+            (None, None, None) => LightKind::Point,
+            (Some(direction), None, None) => LightKind::Directional { direction: vec3(direction) },
+            (None, Some(spot), None) => LightKind::Spot(SpotLight {
+                direction: vec3(spot.direction),
+                inner_angle: spot.inner_angle,
+                outer_angle: spot.outer_angle,
+                falloff: spot.falloff,
+            }),
+            (None, None, Some(area)) => LightKind::Area(AreaLight::new(vec3(area.edge_u), vec3(area.edge_v), area.samples)),
+            _ => return Err("Una luz solo puede ser direccional, foco o de área".to_string()),
+        };
+        if let Some([constant, linear, quadratic]) = desc.attenuation {
+            light.attenuation = Attenuation::new(constant, linear, quadratic);
+        }
+        scene.add_light(light);
+    }
+
+    scene.portals = file.portals.iter()
+        .map(|desc| Portal::new(vec3(desc.corner), vec3(desc.edge_u), vec3(desc.edge_v)))
+        .collect();
+
+    scene.rebuild_blocks();
+
+    if let Some(ceiling) = &file.sky_ceiling {
+        scene.add_sky_ceiling(color(ceiling.color), ceiling.intensity, ceiling.samples);
+    }
+    if let Some(desc) = &file.night {
+        let mut night_sky = NightSky::new(0);
+        night_sky.night = desc.amount.clamp(0.0, 1.0);
+        if let Some(direction) = desc.moon_direction {
+            night_sky.moon_direction = vec3(direction).normalize();
+        }
+        if let Some(size) = desc.moon_size {
+            night_sky.moon_radius = size.to_radians();
+        }
+        if let Some(name) = &desc.moon_texture {
+            night_sky.moon_texture = Some(textures.get(name.as_str()).cloned().ok_or_else(|| format!("Textura desconocida: {}", name))?);
+        }
+        night_sky.star_density = desc.star_density.unwrap_or(night_sky.star_density);
+        night_sky.moon_light = desc.moon_light.unwrap_or(night_sky.moon_light);
+        scene.add_night_sky(night_sky);
+    }
+    if let Some(desc) = &file.snow {
+        let mut snow = Snow::new(desc.coverage.clamp(0.0, 1.0));
+        if let Some(name) = &desc.texture {
+            snow.texture = Some(textures.get(name.as_str()).cloned().ok_or_else(|| format!("Textura desconocida: {}", name))?);
+        }
+        scene.snow = Some(snow);
+    }
+
+    let mut camera = Camera::new(vec3(file.camera.eye), vec3(file.camera.center), vec3(file.camera.up));
+    if let Some(fov) = file.camera.fov {
+        camera.fov = fov.clamp(1.0, 179.0).to_radians();
+    }
+    scene.face_billboards(&camera.eye);
+    Ok((scene, camera))
+}
@@ -0,0 +1,107 @@
+use nalgebra_glm::IVec3;
+
+use crate::prefab::save_prefab;
+use crate::world::{Axis, BlockEdit, Structure, World, AIR};
+
+// Selección de una caja del mundo a partir de dos esquinas, con portapapeles
+// para copiar, cortar, pegar y mover regiones (un WorldEdit mínimo)
+pub struct Selection {
+    pub first: Option<IVec3>,
+    pub second: Option<IVec3>,
+    pub clipboard: Option<Structure>,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Selection {
+            first: None,
+            second: None,
+            clipboard: None,
+        }
+    }
+
+    // Esquinas mínima y máxima de la caja, si ya se eligieron las dos
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        match (self.first, self.second) {
+            (Some(a), Some(b)) => Some((a.inf(&b), a.sup(&b))),
+            _ => None,
+        }
+    }
+
+    pub fn copy(&mut self, world: &World) -> bool {
+        match self.bounds() {
+            Some((min, max)) => {
+                self.clipboard = Some(world.extract(min, max));
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Copia la selección y la deja vacía
+    pub fn cut(&mut self, world: &mut World) -> bool {
+        if !self.copy(world) {
+            return false;
+        }
+        let (min, max) = self.bounds().unwrap();
+        world.fill(min, max, AIR);
+        true
+    }
+
+    // Coloca el portapapeles con su esquina mínima en `at`
+    pub fn paste(&self, world: &mut World, at: IVec3) -> bool {
+        match &self.clipboard {
+            Some(structure) => {
+                world.stamp(structure, at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Corta la selección, la pega en `at` y mueve la caja a la nueva posición
+    pub fn move_to(&mut self, world: &mut World, at: IVec3) -> bool {
+        let Some((min, max)) = self.bounds() else {
+            return false;
+        };
+        self.cut(world);
+        self.paste(world, at);
+        self.first = Some(at);
+        self.second = Some(at + (max - min));
+        true
+    }
+
+    pub fn rotate(&mut self, world: &mut World) -> bool {
+        let Some((min, max)) = self.bounds() else {
+            return false;
+        };
+        world.rotate_region_y(min, max, 1);
+        // Al girar, el ancho y el fondo se intercambian
+        let size = max - min;
+        self.first = Some(min);
+        self.second = Some(min + IVec3::new(size.z, size.y, size.x));
+        true
+    }
+
+    pub fn mirror(&mut self, world: &mut World, axis: Axis) -> bool {
+        match self.bounds() {
+            Some((min, max)) => {
+                world.mirror_region(min, max, axis);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Guarda la región seleccionada como prefab, con coordenadas relativas a su esquina mínima
+    pub fn export(&self, world: &World, path: &str) -> Result<(), String> {
+        let (min, max) = self.bounds().ok_or("No hay una región seleccionada")?;
+        save_prefab(path, &world.extract(min, max), &world.palette)
+    }
+}
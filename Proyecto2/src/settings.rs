@@ -0,0 +1,104 @@
+use crate::fog::Fog;
+use crate::texture::TextureFilter;
+use crate::tonemap::Tonemap;
+
+// Tope de la tangente en el bias de sombra, para que los rayos casi paralelos a la cara
+// no salgan despegados de ella
+const MAX_SHADOW_SLOPE: f32 = 10.0;
+
+// Parámetros del render que antes eran constantes en el código
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    // Número máximo de rebotes recursivos (refracción, caminos indirectos)
+    pub max_depth: u32,
+    // Desplazamiento del origen de los rayos secundarios para evitar el acné de sombras
+    pub origin_bias: f32,
+    pub samples_per_pixel: u32,
+    // Muestreo adaptativo: se traza una muestra por píxel y solo los píxeles cuyo color
+    // difiere de un vecino más que este umbral (0 a 1 por canal) reciben `samples_per_pixel`
+    pub adaptive_threshold: Option<f32>,
+    // Filtro de las texturas que no eligen uno propio
+    pub texture_filter: TextureFilter,
+    // Oclusión ambiental en Whitted: rayos por impacto (0 la desactiva), distancia a la
+    // que un objeto cuenta como oclusor y cuánto oscurece el color base (0 a 1)
+    pub ao_samples: u32,
+    pub ao_radius: f32,
+    pub ao_strength: f32,
+    // Intensidad del destello de lente de las luces visibles; 0 lo desactiva
+    pub lens_flare: f32,
+    // Bloom: resplandor de lo que pasa de `bloom_threshold` en luminancia lineal,
+    // sumado con `bloom_intensity` (0 lo desactiva) y difuminado con una desviación de
+    // `bloom_radius` veces el alto de la imagen
+    pub bloom_threshold: f32,
+    pub bloom_intensity: f32,
+    pub bloom_radius: f32,
+    // Compresión del color lineal al escribir en el framebuffer, tras multiplicarlo por `exposure`
+    pub tonemap: Tonemap,
+    pub exposure: f32,
+    // Unidades del mundo que mide un bloque del diorama (1 por defecto; 0.1 para un .vox
+    // importado a 0.1 unidades por vóxel). Las distancias pensadas para bloques de 1
+    // se multiplican por ella: el bias de los rayos, el radio de la oclusión ambiental,
+    // la caída de las luces, la niebla y la velocidad de la cámara
+    pub world_scale: f32,
+    // Rayos de sombra por punto de las luces de área y las sombras suaves; None deja
+    // los de cada luz
+    pub shadow_samples: Option<u32>,
+    // Endurecimiento por contacto: con un oclusor a menos de esta distancia en bloques
+    // la luz se muestrea más pequeña, así que la penumbra se estrecha cerca del
+    // contacto y se abre lejos de él. 0 lo desactiva
+    pub contact_hardening: f32,
+    // Separación del origen de los rayos de sombra, en bloques: `shadow_normal_bias` a lo
+    // largo de la normal más `shadow_slope_bias` por la tangente del ángulo con la luz.
+    // Las caras rasantes, donde aparece el acné, se separan más, y las de frente tan poco
+    // que la sombra llega hasta el pie de los cubos
+    pub shadow_normal_bias: f32,
+    pub shadow_slope_bias: f32,
+    // Niebla por distancia que se suma a la del tiempo; None la desactiva
+    pub fog: Option<Fog>,
+    // Dibuja encima de la imagen las cajas envolventes de los objetos y las normales
+    // de una muestra de impactos, para revisar colocaciones y caras
+    pub debug_overlay: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            max_depth: 3,
+            origin_bias: 1e-4,
+            samples_per_pixel: 1,
+            adaptive_threshold: None,
+            texture_filter: TextureFilter::Nearest,
+            ao_samples: 0,
+            ao_radius: 1.0,
+            ao_strength: 0.8,
+            lens_flare: 0.0,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.0,
+            bloom_radius: 0.02,
+            tonemap: Tonemap::Aces,
+            exposure: 1.0,
+            world_scale: 1.0,
+            shadow_samples: None,
+            contact_hardening: 0.0,
+            shadow_normal_bias: 5e-5,
+            shadow_slope_bias: 2e-4,
+            fog: None,
+            debug_overlay: false,
+        }
+    }
+}
+
+impl RenderSettings {
+    // Separación del origen de los rayos secundarios a la escala del mundo
+    pub fn bias(&self) -> f32 {
+        self.origin_bias * self.world_scale
+    }
+
+    // Separación de un rayo de sombra a la escala del mundo, con `normal_bias` en bloques
+    // y `cos_theta` el coseno entre la normal y la dirección a la luz
+    pub fn shadow_bias(&self, normal_bias: f32, cos_theta: f32) -> f32 {
+        let cos_theta = cos_theta.abs().clamp(1e-3, 1.0);
+        let slope = ((1.0 - cos_theta * cos_theta).sqrt() / cos_theta).min(MAX_SHADOW_SLOPE);
+        (normal_bias + self.shadow_slope_bias * slope) * self.world_scale
+    }
+}
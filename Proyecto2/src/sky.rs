@@ -0,0 +1,116 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+
+// Color del cielo cuando la escena no indica otro: el cenit del cielo por defecto y el
+// color liso de `FlatSky`
+pub const SKY_COLOR: u32 = 0x448EE4;
+const HORIZON_COLOR: u32 = 0xA9CBEF;
+const GROUND_COLOR: u32 = 0x4A4E57;
+const HAZE_COLOR: u32 = 0xDCE4EC;
+// Altura (componente y de la dirección) a la que la bruma baja a un tercio
+const HAZE_HEIGHT: f32 = 0.12;
+// Ancho relativo del borde difuminado del disco del sol, y exponente y fuerza del halo
+const SUN_EDGE: f32 = 0.25;
+const SUN_HALO_EXPONENT: i32 = 64;
+const SUN_HALO: f32 = 0.4;
+
+// Cielo que ven los rayos que no golpean nada cuando la escena no tiene cubemap.
+// `direction` llega normalizada y `sun` es la dirección hacia el sol y el color de su
+// luz, si la escena tiene uno; un cielo con imagen puede ignorarlo
+pub trait SkyModel: Send + Sync {
+    fn color(&self, direction: &Vec3, sun: Option<(Vec3, Color)>) -> Color;
+}
+
+// Color liso, o degradado vertical de `bottom` (y = -1) a `top` (y = 1)
+pub struct FlatSky {
+    pub top: Color,
+    pub bottom: Option<Color>,
+}
+
+impl FlatSky {
+    pub fn new(color: Color) -> Self {
+        FlatSky { top: color, bottom: None }
+    }
+}
+
+impl Default for FlatSky {
+    fn default() -> Self {
+        FlatSky::new(Color::from_hex(SKY_COLOR))
+    }
+}
+
+impl SkyModel for FlatSky {
+    fn color(&self, direction: &Vec3, _sun: Option<(Vec3, Color)>) -> Color {
+        match self.bottom {
+            Some(bottom) => {
+                let t = (direction.y * 0.5 + 0.5).clamp(0.0, 1.0);
+                bottom * (1.0 - t) + self.top * t
+            }
+            None => self.top,
+        }
+    }
+}
+
+// Cielo calculado por dirección: degradado del horizonte al cenit, suelo bajo el
+// horizonte, una franja de bruma que los une y el disco del sol donde está la luz
+pub struct ProceduralSky {
+    pub zenith: Color,
+    pub horizon: Color,
+    pub ground: Color,
+    // Cuánto tapa la bruma en el horizonte (0 a 1); se desvanece al subir y al bajar
+    pub haze: f32,
+    pub haze_color: Color,
+    // Radio angular del disco del sol en radianes, y su brillo como múltiplo del color
+    // de la luz; con 0 no se dibuja
+    pub sun_radius: f32,
+    pub sun_intensity: f32,
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        ProceduralSky {
+            zenith: Color::from_hex(SKY_COLOR),
+            horizon: Color::from_hex(HORIZON_COLOR),
+            ground: Color::from_hex(GROUND_COLOR),
+            haze: 0.6,
+            haze_color: Color::from_hex(HAZE_COLOR),
+            sun_radius: 0.03,
+            sun_intensity: 8.0,
+        }
+    }
+}
+
+impl SkyModel for ProceduralSky {
+    fn color(&self, direction: &Vec3, sun: Option<(Vec3, Color)>) -> Color {
+        let y = direction.y;
+        let sky = if y >= 0.0 {
+            // La raíz alarga el color del horizonte y deja el cenit arriba del todo
+            let t = y.sqrt();
+            self.horizon * (1.0 - t) + self.zenith * t
+        } else {
+            let t = smoothstep(0.0, 0.2, -y);
+            self.horizon * (1.0 - t) + self.ground * t
+        };
+
+        let haze = self.haze.clamp(0.0, 1.0) * (-y.abs() / HAZE_HEIGHT).exp();
+        let sky = sky * (1.0 - haze) + self.haze_color * haze;
+
+        let Some((sun_direction, sun_color)) = sun else { return sky };
+        if self.sun_intensity <= 0.0 || self.sun_radius <= 0.0 {
+            return sky;
+        }
+        let cos = direction.dot(&sun_direction.normalize());
+        let (outer, inner) = ((self.sun_radius * (1.0 + SUN_EDGE)).cos(), self.sun_radius.cos());
+        let disk = smoothstep(outer, inner, cos);
+        let halo = cos.max(0.0).powi(SUN_HALO_EXPONENT) * SUN_HALO;
+        // El suelo tapa el sol cuando se hunde bajo el horizonte
+        let above = smoothstep(-0.01, 0.01, y);
+        sky + sun_color * ((self.sun_intensity * disk + halo) * above)
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
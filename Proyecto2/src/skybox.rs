@@ -1,20 +1,229 @@
 use nalgebra_glm::Vec3;
-use crate::cube::Cube;
-use crate::material::Material;
+use crate::color::Color;
+use crate::noise::fbm_3d;
+use crate::texture::Texture;
 
+// Lado de cada cara del cielo generado
+pub const PROCEDURAL_RESOLUTION: usize = 128;
+
+// Cielo como cubemap: seis texturas en el orden +X, -X, +Y, -Y, +Z, -Z, orientadas
+// como en OpenGL. Los rayos que no golpean nada toman el color de la cara que atraviesan.
 pub struct Skybox {
-    pub cube: Cube,
+    faces: Vec<Texture>,
+    distribution: SkyDistribution,
 }
 
 impl Skybox {
-    pub fn new(size: f32, material: Material) -> Self {
-        let half_size = size / 2.0;
-        let cube = Cube::new(
-            Vec3::new(-half_size, -half_size, -half_size),
-            Vec3::new(half_size, half_size, half_size),
-            material.into(),
-        );
-        
-        Skybox { cube }
+    pub fn new(faces: [Texture; 6]) -> Self {
+        Skybox::from_faces(faces.into())
+    }
+
+    fn from_faces(faces: Vec<Texture>) -> Self {
+        let distribution = SkyDistribution::new(&faces);
+        Skybox { faces, distribution }
+    }
+
+    // Cielo elegido por nombre: `flat` (sin cubemap, color liso), `procedural`, o la
+    // ruta de una imagen en cruz
+    pub fn from_name(name: &str, seed: u64) -> Result<Option<Self>, String> {
+        match name {
+            "flat" => Ok(None),
+            "procedural" => Ok(Some(Skybox::procedural(seed, PROCEDURAL_RESOLUTION))),
+            path => Skybox::load_cross(path).map(Some),
+        }
+    }
+
+    // Carga las seis caras desde archivos separados, en el orden de `new`
+    pub fn load_faces(paths: [&str; 6]) -> Result<Self, String> {
+        let mut faces = Vec::with_capacity(6);
+        for path in paths {
+            faces.push(Texture::load(path)?);
+        }
+        Ok(Skybox::from_faces(faces))
+    }
+
+    // Carga una imagen en cruz horizontal de 4x3 caras:
+    //        +Y
+    //    -X  +Z  +X  -Z
+    //        -Y
+    pub fn load_cross(path: &str) -> Result<Self, String> {
+        let image = Texture::load(path)?;
+        let (width, height) = image.size();
+        let face = width / 4;
+        if face == 0 || width != face * 4 || height != face * 3 {
+            return Err(format!("{}: la cruz debe medir 4x3 caras cuadradas ({}x{})", path, width, height));
+        }
+
+        let cell = |column: usize, row: usize| image.crop(column * face, row * face, face, face);
+        Ok(Skybox::new([cell(2, 1), cell(0, 1), cell(1, 0), cell(1, 2), cell(1, 1), cell(3, 1)]))
+    }
+
+    // Cielo generado: degradado del horizonte al cenit con nubes de ruido fbm,
+    // y una bruma más oscura bajo el horizonte. `resolution` es el lado de cada cara.
+    pub fn procedural(seed: u64, resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        let faces = (0..6).map(|face| {
+            let mut data = Vec::with_capacity(resolution * resolution);
+            for y in 0..resolution {
+                for x in 0..resolution {
+                    let sc = (x as f32 + 0.5) / resolution as f32 * 2.0 - 1.0;
+                    let tc = (y as f32 + 0.5) / resolution as f32 * 2.0 - 1.0;
+                    data.push(procedural_sky(seed, &face_direction(face, sc, tc).normalize()));
+                }
+            }
+            Texture::new(data, resolution, resolution)
+        }).collect();
+        Skybox::from_faces(faces)
+    }
+
+    pub fn sample(&self, direction: &Vec3) -> Color {
+        let (face, sc, tc, major) = face_coordinates(direction);
+        if major <= 0.0 {
+            return Color::black();
+        }
+        self.faces[face].get_color_bilinear((sc / major + 1.0) * 0.5, (tc / major + 1.0) * 0.5)
+    }
+
+    // Dirección hacia el cielo elegida según su brillo para (u1, u2, u3) en [0, 1)³, y
+    // su densidad de probabilidad por ángulo sólido
+    pub fn sample_direction(&self, u1: f32, u2: f32, u3: f32) -> (Vec3, f32) {
+        self.distribution.sample(u1, u2, u3)
+    }
+}
+
+// Fracción del brillo medio del cielo que se suma a todos los texeles al repartir las
+// muestras: las zonas oscuras siguen pudiendo salir y la estimación no tiene sesgo
+// aunque el sol, la niebla o la noche cambien el cielo después
+const UNIFORM_FLOOR: f32 = 0.05;
+
+// Función de distribución acumulada sobre los texeles de las seis caras, con el peso
+// de cada uno proporcional a su luminancia por el ángulo sólido que cubre: las
+// muestras de la luz del cielo se concentran en el sol y las zonas brillantes
+struct SkyDistribution {
+    // Lado en texeles de cada cara y posición de su primer texel en `cdf`
+    sizes: Vec<(usize, usize)>,
+    offsets: Vec<usize>,
+    cdf: Vec<f32>,
+    total: f32,
+}
+
+impl SkyDistribution {
+    fn new(faces: &[Texture]) -> Self {
+        let sizes: Vec<(usize, usize)> = faces.iter().map(|face| face.size()).collect();
+        let mut offsets = Vec::with_capacity(faces.len());
+        let mut luminances = Vec::new();
+        let mut solid_angles = Vec::new();
+        for (face, &(width, height)) in faces.iter().zip(&sizes) {
+            offsets.push(luminances.len());
+            for y in 0..height {
+                for x in 0..width {
+                    let (u, v) = ((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32);
+                    luminances.push(face.get_color_at(u, v).luminance());
+                    solid_angles.push(texel_solid_angle(u * 2.0 - 1.0, v * 2.0 - 1.0, width, height));
+                }
+            }
+        }
+
+        let mean = luminances.iter().sum::<f32>() / luminances.len().max(1) as f32;
+        let floor = (mean * UNIFORM_FLOOR).max(1e-6);
+        let mut cdf = Vec::with_capacity(luminances.len());
+        let mut total = 0.0;
+        for (luminance, solid_angle) in luminances.into_iter().zip(solid_angles) {
+            total += (luminance + floor) * solid_angle;
+            cdf.push(total);
+        }
+        SkyDistribution { sizes, offsets, cdf, total }
+    }
+
+    // Cara y texel del índice `index` de `cdf`
+    fn texel(&self, index: usize) -> (usize, usize, usize) {
+        let face = self.offsets.partition_point(|&offset| offset <= index) - 1;
+        let local = index - self.offsets[face];
+        let width = self.sizes[face].0;
+        (face, local % width, local / width)
+    }
+
+    fn weight(&self, index: usize) -> f32 {
+        self.cdf[index] - if index > 0 { self.cdf[index - 1] } else { 0.0 }
+    }
+
+    fn sample(&self, u1: f32, u2: f32, u3: f32) -> (Vec3, f32) {
+        if self.cdf.is_empty() || self.total <= 0.0 {
+            return (Vec3::new(0.0, 1.0, 0.0), 0.0);
+        }
+        let index = self.cdf.partition_point(|&value| value < u1 * self.total).min(self.cdf.len() - 1);
+        let (face, x, y) = self.texel(index);
+        let (width, height) = self.sizes[face];
+        // Punto uniforme dentro del texel
+        let sc = (x as f32 + u2) / width as f32 * 2.0 - 1.0;
+        let tc = (y as f32 + u3) / height as f32 * 2.0 - 1.0;
+        let direction = face_direction(face, sc, tc).normalize();
+        (direction, self.texel_pdf(index, sc, tc, width, height))
+    }
+
+    // Probabilidad del texel repartida sobre el ángulo sólido en (sc, tc)
+    fn texel_pdf(&self, index: usize, sc: f32, tc: f32, width: usize, height: usize) -> f32 {
+        self.weight(index) / self.total / texel_solid_angle(sc, tc, width, height)
+    }
+}
+
+// Ángulo sólido de un texel de una cara de `width` x `height` en (s, t) de [-1, 1]²: el
+// área del texel sobre el plano a distancia 1 proyectada sobre la esfera
+fn texel_solid_angle(sc: f32, tc: f32, width: usize, height: usize) -> f32 {
+    let area = (2.0 / width as f32) * (2.0 / height as f32);
+    area / (1.0 + sc * sc + tc * tc).powf(1.5)
+}
+
+// Cara que atraviesa la dirección, coordenadas (s, t) sin normalizar y el valor del eje mayor
+fn face_coordinates(d: &Vec3) -> (usize, f32, f32, f32) {
+    let abs = d.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        if d.x > 0.0 { (0, -d.z, -d.y, abs.x) } else { (1, d.z, -d.y, abs.x) }
+    } else if abs.y >= abs.z {
+        if d.y > 0.0 { (2, d.x, d.z, abs.y) } else { (3, d.x, -d.z, abs.y) }
+    } else if d.z > 0.0 {
+        (4, d.x, -d.y, abs.z)
+    } else {
+        (5, -d.x, -d.y, abs.z)
     }
 }
+
+// Inversa de `face_coordinates` para (s, t) en [-1, 1]
+fn face_direction(face: usize, sc: f32, tc: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -tc, -sc),
+        1 => Vec3::new(-1.0, -tc, sc),
+        2 => Vec3::new(sc, 1.0, tc),
+        3 => Vec3::new(sc, -1.0, -tc),
+        4 => Vec3::new(sc, -tc, 1.0),
+        _ => Vec3::new(-sc, -tc, -1.0),
+    }
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn procedural_sky(seed: u64, direction: &Vec3) -> Color {
+    let horizon = Color::new(190, 215, 240);
+    let zenith = Color::new(68, 142, 228);
+    let ground = Color::new(105, 115, 125);
+    let cloud = Color::new(245, 245, 250);
+
+    if direction.y < 0.0 {
+        return mix(horizon, ground, (-direction.y).sqrt());
+    }
+
+    let sky = mix(horizon, zenith, direction.y.sqrt());
+    // Nubes sobre un plano a cierta altura: cerca del horizonte se ven más juntas y se desvanecen
+    let height = direction.y + 0.15;
+    let (x, z) = (direction.x / height * 1.5, direction.z / height * 1.5);
+    let density = fbm_3d(seed, x, z, 0.0, 5);
+    let coverage = smoothstep(0.5, 0.75, density) * smoothstep(0.0, 0.2, direction.y);
+    mix(sky, cloud, coverage * 0.9)
+}
@@ -0,0 +1,55 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::noise::fbm_3d;
+use crate::texture::{Texture, TextureFilter};
+
+// Caras con la normal por debajo de esta componente vertical no retienen nieve
+const MIN_SLOPE: f32 = 0.5;
+// Ancho de la transición entre nieve y superficie, en unidades de ruido
+const EDGE: f32 = 0.08;
+// Frecuencia de las manchas de nieve cuando la cobertura es parcial
+const PATCH_SCALE: f32 = 0.6;
+
+// Nieve acumulada sobre las caras que miran hacia arriba de cualquier material, para
+// pasar el diorama a invierno sin tocar la paleta. `coverage` va de 0 (nada) a 1 (todo
+// cubierto); con valores intermedios queda en manchas que crecen con la cobertura.
+pub struct Snow {
+    pub coverage: f32,
+    // Sin textura la nieve es de un blanco liso
+    pub texture: Option<Texture>,
+    pub seed: u64,
+}
+
+impl Snow {
+    pub fn new(coverage: f32) -> Self {
+        Snow { coverage, texture: None, seed: 0 }
+    }
+
+    // Cuánta nieve hay (0 a 1) en `point`, sobre una cara con normal `normal`
+    pub fn amount(&self, point: &Vec3, normal: &Vec3) -> f32 {
+        if self.coverage <= 0.0 || normal.y <= MIN_SLOPE {
+            return 0.0;
+        }
+        let slope = ((normal.y - MIN_SLOPE) / (1.0 - MIN_SLOPE)).min(1.0);
+        let noise = fbm_3d(self.seed, point.x * PATCH_SCALE, point.y * PATCH_SCALE, point.z * PATCH_SCALE, 3);
+        // Con cobertura 1 el umbral supera cualquier valor del ruido y todo queda blanco
+        let patch = ((self.coverage * (1.0 + EDGE) - noise) / EDGE).clamp(0.0, 1.0);
+        patch * slope
+    }
+
+    // Color de la nieve en `point`, con la textura proyectada desde arriba en coordenadas
+    // del mundo para que continúe de un bloque al siguiente
+    pub fn color(&self, point: &Vec3, default_filter: TextureFilter) -> Color {
+        match &self.texture {
+            Some(texture) => texture.sample(point.x.rem_euclid(1.0), point.z.rem_euclid(1.0), default_filter),
+            None => Color::new(240, 244, 250),
+        }
+    }
+}
+
+impl Default for Snow {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
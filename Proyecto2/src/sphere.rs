@@ -0,0 +1,84 @@
+use nalgebra_glm::Vec3;
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::scene::DEFAULT_GROUP;
+use std::sync::Arc;
+
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+    pub material: Arc<Material>,
+    pub casts_shadows: bool, // Si es falso, los rayos de sombra lo atraviesan
+    pub visible_to_camera: bool, // Si es falso, los rayos primarios lo ignoran
+    pub group: usize, // Grupo de la escena al que pertenece
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32, material: Arc<Material>) -> Self {
+        Sphere {
+            center,
+            radius,
+            material,
+            casts_shadows: true,
+            visible_to_camera: true,
+            group: DEFAULT_GROUP,
+        }
+    }
+}
+
+impl RayIntersect for Sphere {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let oc = origin - self.center;
+        let a = direction.dot(direction);
+        let b = 2.0 * oc.dot(direction);
+        let c = oc.dot(&oc) - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+
+        if discriminant < 0.0 {
+            return Intersect::empty();
+        }
+
+        // Si el origen está dentro de la esfera la primera raíz es negativa y se usa la salida
+        let sqrt_discriminant = discriminant.sqrt();
+        let t_near = (-b - sqrt_discriminant) / (2.0 * a);
+        let t_far = (-b + sqrt_discriminant) / (2.0 * a);
+        let distance = if t_near > 0.0 { t_near } else { t_far };
+        if distance <= 0.0 {
+            return Intersect::empty();
+        }
+
+        // La normal siempre apunta hacia fuera de la esfera
+        let point = origin + direction * distance;
+        let normal = (point - self.center).normalize();
+
+        let mut intersect = Intersect::new(point, normal, distance, self.material.clone());
+        intersect.uv = Some(intersect.calculate_uv());
+        intersect.front_face = t_near > 0.0;
+        intersect
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let extent = Vec3::repeat(self.radius);
+        (self.center - extent, self.center + extent)
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+
+    fn group(&self) -> usize {
+        self.group
+    }
+
+    fn material(&self) -> &Arc<Material> {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Arc<Material> {
+        &mut self.material
+    }
+}
@@ -0,0 +1,108 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+
+// Distancia a la que se coloca la luz del sol; como con la luna, sus rayos llegan casi paralelos
+pub const SUN_DISTANCE: f32 = 1000.0;
+// Hora de salida y de puesta del sol
+const SUNRISE: f32 = 6.0;
+const SUNSET: f32 = 18.0;
+// Inclinación del recorrido hacia +Z (el sur), para que a mediodía no caiga a plomo
+const TILT: f32 = 0.35;
+// Colores de la luz con el sol en el horizonte y alto
+const HORIZON_COLOR: u32 = 0xFF8C46;
+const NOON_COLOR: u32 = 0xFFF8EC;
+// Resplandor del cielo hacia el sol al amanecer y al atardecer
+const GLOW_COLOR: u32 = 0xFF7A3C;
+// Luz ambiente de Whitted en noche cerrada, como fracción de la de día
+const NIGHT_AMBIENT: f32 = 0.12;
+
+// Ciclo de día y noche: la hora del día mueve el sol de este (+X) a oeste (-X) y de
+// ella salen su dirección, el color y la intensidad de su luz, el tono del cielo y
+// cuánto es de noche.
+#[derive(Debug, Clone)]
+pub struct Sun {
+    // Hora del día, de 0 a 24
+    pub time_of_day: f32,
+    // Segundos de escena que dura un día completo; 0 deja la hora quieta
+    pub day_length: f32,
+    // Intensidad de la luz a mediodía
+    pub intensity: f32,
+}
+
+impl Sun {
+    pub fn new(time_of_day: f32) -> Self {
+        Sun { time_of_day: time_of_day.rem_euclid(24.0), day_length: 0.0, intensity: 1.0 }
+    }
+
+    // Avanza la hora según los segundos de escena transcurridos
+    pub fn advance(&mut self, dt: f32) {
+        if self.day_length > 0.0 {
+            self.scrub(dt / self.day_length * 24.0);
+        }
+    }
+
+    // Mueve la hora `hours` hacia delante o hacia atrás
+    pub fn scrub(&mut self, hours: f32) {
+        self.time_of_day = (self.time_of_day + hours).rem_euclid(24.0);
+    }
+
+    // Dirección hacia el sol; por debajo del horizonte de noche
+    pub fn direction(&self) -> Vec3 {
+        let angle = (self.time_of_day - SUNRISE) / (SUNSET - SUNRISE) * PI;
+        Vec3::new(angle.cos(), angle.sin(), TILT).normalize()
+    }
+
+    // Altura del sol sobre el horizonte (la componente y de su dirección)
+    pub fn elevation(&self) -> f32 {
+        self.direction().y
+    }
+
+    // Naranja al salir y al ponerse, casi blanca a mediodía
+    pub fn light_color(&self) -> Color {
+        let t = smoothstep(0.0, 0.4, self.elevation());
+        Color::from_hex(HORIZON_COLOR) * (1.0 - t) + Color::from_hex(NOON_COLOR) * t
+    }
+
+    // Se apaga poco después de hundirse bajo el horizonte
+    pub fn light_intensity(&self) -> f32 {
+        self.intensity * smoothstep(-0.05, 0.15, self.elevation())
+    }
+
+    // Cuánto es de noche, de 0 a 1, para el cielo nocturno
+    pub fn night(&self) -> f32 {
+        1.0 - smoothstep(-0.2, 0.05, self.elevation())
+    }
+
+    // Luz ambiente (fracción del color base visible sin luz directa)
+    pub fn ambient(&self) -> f32 {
+        NIGHT_AMBIENT + (1.0 - NIGHT_AMBIENT) * smoothstep(-0.15, 0.25, self.elevation())
+    }
+
+    // Cielo de día `day` visto en `direction` a esta hora: se oscurece con el sol bajo y,
+    // cerca del horizonte, se tiñe de naranja en la dirección por la que sale o se pone
+    pub fn sky(&self, direction: &Vec3, day: Color) -> Color {
+        let elevation = self.elevation();
+        let daylight = smoothstep(-0.2, 0.3, elevation);
+        let toward = self.direction();
+        let facing = {
+            let (a, b) = (Vec3::new(direction.x, 0.0, direction.z), Vec3::new(toward.x, 0.0, toward.z));
+            if a.magnitude() < 1e-6 || b.magnitude() < 1e-6 { 0.0 } else { a.normalize().dot(&b.normalize()).max(0.0) }
+        };
+        // El resplandor es máximo con el sol en el horizonte y se concentra en las direcciones bajas
+        let glow = (1.0 - (elevation.abs() / 0.3)).max(0.0) * (1.0 - direction.y.abs()).powi(3) * (0.3 + 0.7 * facing);
+        day * daylight + Color::from_hex(GLOW_COLOR) * (glow * 0.8)
+    }
+}
+
+impl Default for Sun {
+    fn default() -> Self {
+        Self::new(12.0)
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
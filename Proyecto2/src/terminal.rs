@@ -0,0 +1,208 @@
+use std::io::{self, Read, Write};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use minifb::Key;
+
+use crate::framebuffer::Framebuffer;
+
+// Lo que se espera el resto de una secuencia de escape; si no llega, fue un ESC suelto
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+// Backend de salida que dibuja el framebuffer en la terminal usando caracteres
+// de medio bloque ('▀') con color ANSI truecolor: cada celda representa dos
+// píxeles, el superior como color de texto y el inferior como color de fondo.
+pub struct Terminal {
+    keys: Receiver<Key>,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Terminal {
+    pub fn new() -> Self {
+        // Modo raw para leer teclas sin esperar Enter y sin eco
+        let _ = Command::new("stty").args(["raw", "-echo"]).stdin(std::process::Stdio::inherit()).status();
+
+        // Un hilo lee la entrada byte a byte y otro la traduce a teclas, para poder
+        // esperar el resto de una secuencia de escape con un tiempo límite
+        let (byte_sender, bytes) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut byte = [0u8; 1];
+            while stdin.read_exact(&mut byte).is_ok() && byte_sender.send(byte[0]).is_ok() {}
+        });
+
+        let (sender, keys) = mpsc::channel();
+        thread::spawn(move || decode_keys(&bytes, &sender));
+
+        // Ocultar el cursor y limpiar la pantalla
+        print!("\x1b[?25l\x1b[2J");
+        let _ = io::stdout().flush();
+
+        Terminal { keys }
+    }
+
+    // Teclas recibidas desde el último frame
+    pub fn pressed_keys(&self) -> Vec<Key> {
+        self.keys.try_iter().collect()
+    }
+
+    pub fn present(&self, framebuffer: &Framebuffer) {
+        let mut output = String::with_capacity(framebuffer.width * framebuffer.height * 20);
+        output.push_str("\x1b[H");
+
+        for y in (0..framebuffer.height).step_by(2) {
+            for x in 0..framebuffer.width {
+                let top = framebuffer.buffer[y * framebuffer.width + x];
+                // Si la altura es impar, la última fila se completa con negro
+                let bottom = if y + 1 < framebuffer.height {
+                    framebuffer.buffer[(y + 1) * framebuffer.width + x]
+                } else {
+                    0x000000
+                };
+
+                output.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    (top >> 16) & 0xFF, (top >> 8) & 0xFF, top & 0xFF,
+                    (bottom >> 16) & 0xFF, (bottom >> 8) & 0xFF, bottom & 0xFF,
+                ));
+            }
+            // En modo raw hace falta el retorno de carro explícito
+            output.push_str("\x1b[0m\r\n");
+        }
+
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(output.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Restaurar el cursor y el modo normal de la terminal
+        print!("\x1b[0m\x1b[?25h\r\n");
+        let _ = io::stdout().flush();
+        let _ = Command::new("stty").arg("sane").stdin(std::process::Stdio::inherit()).status();
+    }
+}
+
+// Traduce los bytes de la terminal a teclas hasta que se cierre alguno de los canales
+fn decode_keys(bytes: &Receiver<u8>, keys: &Sender<Key>) {
+    let mut pending = None;
+    while let Some(byte) = pending.take().or_else(|| bytes.recv().ok()) {
+        let key = match byte {
+            // Secuencias de escape de las flechas: ESC [ A/B/C/D. Un ESC sin nada detrás
+            // en `ESCAPE_TIMEOUT` es la tecla Escape, que sale
+            0x1b => match bytes.recv_timeout(ESCAPE_TIMEOUT) {
+                Ok(b'[') => match bytes.recv_timeout(ESCAPE_TIMEOUT) {
+                    Ok(b'A') => Some(Key::Up),
+                    Ok(b'B') => Some(Key::Down),
+                    Ok(b'C') => Some(Key::Right),
+                    Ok(b'D') => Some(Key::Left),
+                    _ => None,
+                },
+                // Otra tecla justo después del ESC: se traduce en la siguiente vuelta
+                Ok(next) => {
+                    pending = Some(next);
+                    Some(Key::Escape)
+                }
+                Err(_) => Some(Key::Escape),
+            },
+            // En modo raw Ctrl-C no genera señal, lo tratamos como salir
+            0x03 | b'q' => Some(Key::Escape),
+            c => char_to_key(c as char),
+        };
+
+        if let Some(key) = key {
+            if keys.send(key).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+fn char_to_key(c: char) -> Option<Key> {
+    match c.to_ascii_lowercase() {
+        'a' => Some(Key::A),
+        'b' => Some(Key::B),
+        'c' => Some(Key::C),
+        'd' => Some(Key::D),
+        'e' => Some(Key::E),
+        'f' => Some(Key::F),
+        'g' => Some(Key::G),
+        'h' => Some(Key::H),
+        'i' => Some(Key::I),
+        'j' => Some(Key::J),
+        'k' => Some(Key::K),
+        'l' => Some(Key::L),
+        'm' => Some(Key::M),
+        'n' => Some(Key::N),
+        'o' => Some(Key::O),
+        'p' => Some(Key::P),
+        'q' => Some(Key::Q),
+        'r' => Some(Key::R),
+        's' => Some(Key::S),
+        't' => Some(Key::T),
+        'u' => Some(Key::U),
+        'v' => Some(Key::V),
+        'w' => Some(Key::W),
+        'x' => Some(Key::X),
+        'y' => Some(Key::Y),
+        'z' => Some(Key::Z),
+        '0' => Some(Key::Key0),
+        '1' => Some(Key::Key1),
+        '2' => Some(Key::Key2),
+        '3' => Some(Key::Key3),
+        '4' => Some(Key::Key4),
+        '5' => Some(Key::Key5),
+        '6' => Some(Key::Key6),
+        '7' => Some(Key::Key7),
+        '8' => Some(Key::Key8),
+        '9' => Some(Key::Key9),
+        ' ' => Some(Key::Space),
+        '\r' => Some(Key::Enter),
+        '\t' => Some(Key::Tab),
+        '+' => Some(Key::Equal),
+        '-' => Some(Key::Minus),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(input: &[u8]) -> Vec<Key> {
+        let (byte_sender, bytes) = mpsc::channel();
+        for byte in input {
+            byte_sender.send(*byte).unwrap();
+        }
+        drop(byte_sender);
+        let (sender, keys) = mpsc::channel();
+        decode_keys(&bytes, &sender);
+        drop(sender);
+        keys.iter().collect()
+    }
+
+    #[test]
+    fn decodes_arrows_and_a_lone_escape() {
+        assert_eq!(decode(b"\x1b[Aw\x1b[D"), vec![Key::Up, Key::W, Key::Left]);
+        assert_eq!(decode(b"\x1b"), vec![Key::Escape]);
+        assert_eq!(decode(b"\x1bw"), vec![Key::Escape, Key::W]);
+    }
+
+    #[test]
+    fn lone_escape_does_not_wait_for_another_key() {
+        let (byte_sender, bytes) = mpsc::channel();
+        let (sender, keys) = mpsc::channel();
+        thread::spawn(move || decode_keys(&bytes, &sender));
+        byte_sender.send(0x1b).unwrap();
+        assert_eq!(keys.recv_timeout(ESCAPE_TIMEOUT * 20), Ok(Key::Escape));
+    }
+}
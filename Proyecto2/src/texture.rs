@@ -1,32 +1,322 @@
 use crate::color::Color;
+use crate::procedural::{Procedural, BAKE_RESOLUTION};
+use image::{DynamicImage, GenericImageView};
+use nalgebra_glm::Vec3;
+
+// Cómo se obtiene el color entre texeles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFilter {
+    // El texel más cercano, aspecto pixel-art
+    Nearest,
+    // Interpolación entre los cuatro texeles vecinos
+    Bilinear,
+}
+
+impl TextureFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(TextureFilter::Nearest),
+            "bilinear" => Some(TextureFilter::Bilinear),
+            _ => None,
+        }
+    }
+}
+
+// Archivo del que sale una textura, para volver a generarla si cambia en disco
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureSource {
+    File(String),
+    // Mapa de normales calculado del brillo de la imagen con esa intensidad
+    NormalMap(String, f32),
+}
+
+impl TextureSource {
+    pub fn path(&self) -> &str {
+        match self {
+            TextureSource::File(path) | TextureSource::NormalMap(path, _) => path,
+        }
+    }
+}
+
+// Copia de la textura a menor resolución; cada nivel tiene la mitad de ancho y de
+// alto que el anterior, con cada texel como promedio de cuatro del nivel de arriba
+#[derive(Debug, Clone)]
+struct MipLevel {
+    data: Vec<Color>,
+    width: usize,
+    height: usize,
+}
 
 #[derive(Debug, Clone)] // Añadido Clone aquí
 pub struct Texture {
     data: Vec<Color>, // Los colores de la textura
     width: usize,
     height: usize,
+    mips: Vec<MipLevel>, // Niveles de mipmap a partir de la mitad de resolución
+    pub filter: Option<TextureFilter>, // Si es None se usa el filtro global de `RenderSettings`
+    pub source: Option<TextureSource>, // None si se creó en memoria
+    procedural: Option<Procedural>, // Si está, el color se calcula en cada punto y no hay texeles
 }
 
 impl Texture {
     pub fn new(data: Vec<Color>, width: usize, height: usize) -> Self {
         assert!(data.len() == width * height, "El tamaño de los datos no coincide con las dimensiones de la textura.");
-        Texture { data, width, height }
+        let mips = mip_chain(&data, width, height);
+        Texture { data, width, height, mips, filter: None, source: None, procedural: None }
+    }
+
+    // Tablero de ajedrez de 2x2 casillas; con el filtro al vecino más cercano para que
+    // los bordes queden nítidos. Cada repetición de la textura son cuatro casillas.
+    pub fn checkerboard(first: Color, second: Color) -> Self {
+        let mut texture = Texture::new(vec![first, second, second, first], 2, 2);
+        texture.filter = Some(TextureFilter::Nearest);
+        texture
+    }
+
+    // Textura sin imagen que calcula su color en cada (u, v)
+    pub fn procedural(procedural: Procedural) -> Self {
+        Texture { data: Vec::new(), width: 0, height: 0, mips: Vec::new(), filter: None, source: None, procedural: Some(procedural) }
+    }
+
+    // Carga la imagen usando la crate `image`
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let img = image::open(file_path).map_err(|e| format!("No se pudo abrir {}: {}", file_path, e))?;
+        let (width, height) = img.dimensions();
+
+        // Convertir la imagen a un Vec<Color>; otros formatos (RGBA, gris) se pasan a RGB.
+        // Las imágenes en flotante (.hdr, .exr) ya son lineales y pueden pasar de 1
+        let pixel_data: Vec<Color> = match img {
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => img.to_rgb32f().pixels()
+                .map(|pixel| Color::from_linear(pixel[0].max(0.0), pixel[1].max(0.0), pixel[2].max(0.0)))
+                .collect(),
+            _ => img.to_rgb8().pixels().map(|pixel| Color::new(pixel[0], pixel[1], pixel[2])).collect(),
+        };
+
+        let mut texture = Texture::new(pixel_data, width as usize, height as usize);
+        texture.source = Some(TextureSource::File(file_path.to_string()));
+        Ok(texture)
+    }
+
+    // Versión de esta textura generada a partir de `image`, la imagen recién cargada de
+    // `path`, o None si la textura no sale de ese archivo. Conserva el filtro propio.
+    pub fn reloaded(&self, path: &str, image: &Texture) -> Option<Texture> {
+        let source = self.source.as_ref().filter(|source| source.path() == path)?;
+        let mut texture = match source {
+            TextureSource::File(_) => image.clone(),
+            TextureSource::NormalMap(_, strength) => image.to_normal_map(*strength),
+        };
+        texture.filter = self.filter;
+        Some(texture)
+    }
+
+    // Ancho y alto en texeles; las procedurales, los de la imagen en que se hornean
+    pub fn size(&self) -> (usize, usize) {
+        if self.procedural.is_some() {
+            return (BAKE_RESOLUTION, BAKE_RESOLUTION);
+        }
+        (self.width, self.height)
+    }
+
+    // Copia del rectángulo de `width` x `height` texeles con esquina superior izquierda en (x, y)
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Texture {
+        if let Some(procedural) = &self.procedural {
+            return procedural.bake(BAKE_RESOLUTION).crop(x, y, width, height);
+        }
+        assert!(x + width <= self.width && y + height <= self.height, "El recorte se sale de la textura.");
+        let data = (y..y + height)
+            .flat_map(|row| self.data[row * self.width + x..row * self.width + x + width].iter().copied())
+            .collect();
+        let mut texture = Texture::new(data, width, height);
+        texture.filter = self.filter;
+        texture
     }
 
     pub fn get_color_at(&self, u: f32, v: f32) -> Color {
-        if self.data.is_empty() {
-            return Color::black();
+        if let Some(procedural) = &self.procedural {
+            return procedural.color_at(u, v);
+        }
+        nearest(&self.data, self.width, self.height, u, v)
+    }
+
+    // Color con el filtro de la textura, o `default_filter` si no tiene uno propio
+    pub fn sample(&self, u: f32, v: f32, default_filter: TextureFilter) -> Color {
+        match self.filter.unwrap_or(default_filter) {
+            TextureFilter::Nearest => self.get_color_at(u, v),
+            TextureFilter::Bilinear => self.get_color_bilinear(u, v),
+        }
+    }
+
+    // Como `sample`, pero en el nivel de mipmap que corresponde a un píxel que cubre
+    // `footprint` veces el ancho de la textura: de lejos, un texel ya promediado en
+    // lugar de uno cualquiera de los muchos que caen en el píxel, que es lo que hace
+    // parpadear las texturas de los bloques del fondo
+    pub fn sample_footprint(&self, u: f32, v: f32, default_filter: TextureFilter, footprint: f32) -> Color {
+        let texels = footprint * self.width.max(self.height) as f32;
+        if self.procedural.is_some() || texels <= 1.0 {
+            return self.sample(u, v, default_filter);
         }
+        let level = (texels.log2().round() as usize).min(self.mips.len());
+        let Some(mip) = level.checked_sub(1).and_then(|index| self.mips.get(index)) else {
+            return self.sample(u, v, default_filter);
+        };
+        match self.filter.unwrap_or(default_filter) {
+            TextureFilter::Nearest => nearest(&mip.data, mip.width, mip.height, u, v),
+            TextureFilter::Bilinear => bilinear(&mip.data, mip.width, mip.height, u, v),
+        }
+    }
+
+    pub fn get_color_bilinear(&self, u: f32, v: f32) -> Color {
+        if let Some(procedural) = &self.procedural {
+            return procedural.color_at(u, v);
+        }
+        bilinear(&self.data, self.width, self.height, u, v)
+    }
+
+    // Normal en espacio tangente guardada en la textura: x hacia +u, y hacia
+    // arriba en la imagen y z saliendo de la cara, codificadas de [-1, 1] a [0, 255]
+    pub fn sample_normal(&self, u: f32, v: f32, default_filter: TextureFilter) -> Vec3 {
+        let [x, y, z] = channels(self.sample(u, v, default_filter));
+        Vec3::new(x * 2.0 - 1.0, y * 2.0 - 1.0, z * 2.0 - 1.0).normalize()
+    }
+
+    // Mapa de normales que trata el brillo de la textura como altura; `strength`
+    // exagera el relieve. Las diferencias se toman a unos 1/32 del ancho para que
+    // el relieve siga las formas (piedras, tablas) y no el ruido de cada píxel.
+    pub fn to_normal_map(&self, strength: f32) -> Texture {
+        if let Some(procedural) = &self.procedural {
+            return procedural.bake(BAKE_RESOLUTION).to_normal_map(strength);
+        }
+        let step = (self.width / 32).max(1);
+        let height = |x: usize, y: usize| {
+            let [r, g, b] = channels(self.data[(y % self.height) * self.width + x % self.width]);
+            0.299 * r + 0.587 * g + 0.114 * b
+        };
+
+        let mut data = Vec::with_capacity(self.data.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let left = height(x + self.width - step % self.width, y);
+                let right = height(x + step, y);
+                let up = height(x, y + self.height - step % self.height);
+                let down = height(x, y + step);
+                // La y de la imagen crece hacia abajo y la del espacio tangente hacia arriba
+                let normal = Vec3::new((left - right) * strength, (down - up) * strength, 1.0).normalize();
+                let encode = |value: f32| ((value * 0.5 + 0.5) * 255.0).round() as u8;
+                data.push(Color::new(encode(normal.x), encode(normal.y), encode(normal.z)));
+            }
+        }
+
+        let mut normal_map = Texture::new(data, self.width, self.height);
+        normal_map.filter = self.filter;
+        normal_map.source = match &self.source {
+            Some(TextureSource::File(path)) => Some(TextureSource::NormalMap(path.clone(), strength)),
+            _ => None,
+        };
+        normal_map
+    }
+}
+
+// Texel de `data` más cercano a (u, v)
+fn nearest(data: &[Color], width: usize, height: usize, u: f32, v: f32) -> Color {
+    if data.is_empty() {
+        return Color::black();
+    }
+    let x = ((u.clamp(0.0, 1.0) * width as f32) as usize).min(width - 1);
+    let y = ((v.clamp(0.0, 1.0) * height as f32) as usize).min(height - 1);
+    data[y * width + x]
+}
 
-        let u = u.clamp(0.0, 1.0);
-        let v = v.clamp(0.0, 1.0);
+// Interpolación entre los cuatro texeles de `data` que rodean (u, v)
+fn bilinear(data: &[Color], width: usize, height: usize, u: f32, v: f32) -> Color {
+    if data.is_empty() {
+        return Color::black();
+    }
 
-        let x = (u * self.width as f32) as usize;
-        let y = (v * self.height as f32) as usize;
+    // Los centros de los texeles están en (i + 0.5) / ancho
+    let fx = (u.clamp(0.0, 1.0) * width as f32 - 0.5).max(0.0);
+    let fy = (v.clamp(0.0, 1.0) * height as f32 - 0.5).max(0.0);
+    let x0 = (fx as usize).min(width - 1);
+    let y0 = (fy as usize).min(height - 1);
+    let x1 = (x0 + 1).min(width - 1);
+    let y1 = (y0 + 1).min(height - 1);
+    let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
 
-        let x = x.min(self.width - 1);
-        let y = y.min(self.height - 1);
+    let texel = |x: usize, y: usize| data[y * width + x];
+    let top = texel(x0, y0) * (1.0 - tx) + texel(x1, y0) * tx;
+    let bottom = texel(x0, y1) * (1.0 - tx) + texel(x1, y1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
 
+// Niveles de mipmap de la imagen hasta llegar a un solo texel. En los lados impares
+// el último texel se repite al promediar
+fn mip_chain(data: &[Color], width: usize, height: usize) -> Vec<MipLevel> {
+    let mut mips: Vec<MipLevel> = Vec::new();
+    loop {
+        let (previous, previous_width, previous_height) = match mips.last() {
+            Some(last) => (last.data.as_slice(), last.width, last.height),
+            None => (data, width, height),
+        };
+        if previous.is_empty() || (previous_width <= 1 && previous_height <= 1) {
+            return mips;
+        }
+        let (mip_width, mip_height) = ((previous_width / 2).max(1), (previous_height / 2).max(1));
+        let texel = |x: usize, y: usize| previous[y.min(previous_height - 1) * previous_width + x.min(previous_width - 1)];
+        let mut level = Vec::with_capacity(mip_width * mip_height);
+        for y in 0..mip_height {
+            for x in 0..mip_width {
+                let sum = texel(2 * x, 2 * y) + texel(2 * x + 1, 2 * y) + texel(2 * x, 2 * y + 1) + texel(2 * x + 1, 2 * y + 1);
+                level.push(sum * 0.25);
+            }
+        }
+        mips.push(MipLevel { data: level, width: mip_width, height: mip_height });
+    }
+}
+
+// Canales del color en [0, 1] tal como estaban en la imagen, con gamma: los mapas de
+// normales y las alturas se guardan así y no como luz
+fn channels(color: Color) -> [f32; 3] {
+    color.to_srgb()
+}
+
+// Texeles con alfa por debajo de la mitad se consideran transparentes
+const ALPHA_THRESHOLD: u8 = 128;
+
+// Canal alfa de una imagen, para recortar la silueta de un sprite sobre su quad o los
+// huecos de un bloque. Las texturas guardan solo RGB, así que el alfa se lee aparte.
+// Una imagen sin canal alfa da una máscara opaca.
+#[derive(Debug, Clone)]
+pub struct AlphaMask {
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl AlphaMask {
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let img = image::open(file_path).map_err(|e| format!("No se pudo abrir {}: {}", file_path, e))?;
+        let (width, height) = img.dimensions();
+        let data = img.to_rgba8().pixels().map(|pixel| pixel[3]).collect();
+        Ok(AlphaMask { data, width: width as usize, height: height as usize })
+    }
+
+    // Alfa de 0 a 255 del texel más cercano a (u, v)
+    fn texel(&self, u: f32, v: f32) -> u8 {
+        if self.data.is_empty() {
+            return u8::MAX;
+        }
+        let x = ((u.clamp(0.0, 1.0) * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v.clamp(0.0, 1.0) * self.height as f32) as usize).min(self.height - 1);
         self.data[y * self.width + x]
     }
+
+    // Si el texel más cercano a (u, v) es opaco
+    pub fn covers(&self, u: f32, v: f32) -> bool {
+        self.texel(u, v) >= ALPHA_THRESHOLD
+    }
+
+    // Opacidad en (u, v) de 0 a 1; lo que no cubre cuenta como 0
+    pub fn opacity(&self, u: f32, v: f32) -> f32 {
+        let alpha = self.texel(u, v);
+        if alpha >= ALPHA_THRESHOLD { alpha as f32 / 255.0 } else { 0.0 }
+    }
 }
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::cube::Face;
+use crate::material::FaceTextures;
+use crate::scene::Scene;
+use crate::texture::{Texture, TextureSource};
+use crate::world::Palette;
+
+// Extensiones de imagen que se buscan en un paquete
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+// Sufijo de los mapas de normales dentro de un paquete: `cobblestone_n.png`
+const NORMAL_MAP_SUFFIX: &str = "_n";
+
+// Texturas de un tipo de bloque dentro de un paquete
+#[derive(Clone)]
+#[derive(Default)]
+struct PackEntry {
+    texture: Option<Texture>,
+    normal_map: Option<Texture>,
+    faces: Option<FaceTextures>,
+}
+
+// Paquete de texturas: un directorio con una imagen por tipo de bloque, nombrada como
+// el bloque en la paleta (`dirt.png`, `grass.png`, `log.png`...), y opcionalmente
+// imágenes por cara con el sufijo de la cara (`grass_top.png`, `grass_side.png`,
+// `grass_bottom.png`, `log_north.png`...). Los bloques que no aparecen en el paquete
+// conservan su textura original.
+pub struct TexturePack {
+    pub name: String,
+    entries: HashMap<String, PackEntry>,
+}
+
+impl TexturePack {
+    pub fn load(dir: &str) -> Result<Self, String> {
+        let files = fs::read_dir(dir).map_err(|e| format!("No se pudo abrir el paquete {}: {}", dir, e))?;
+        let mut images: Vec<(String, String)> = Vec::new();
+        for file in files {
+            let path = file.map_err(|e| format!("No se pudo leer el paquete {}: {}", dir, e))?.path();
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            let (Some(stem), Some(path)) = (path.file_stem().and_then(|s| s.to_str()), path.to_str()) else { continue };
+            if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+                images.push((stem.to_lowercase(), path.to_string()));
+            }
+        }
+
+        let mut entries: HashMap<String, PackEntry> = HashMap::new();
+        for (name, path) in images.iter().filter(|(name, _)| !name.ends_with(NORMAL_MAP_SUFFIX)) {
+            // `grass_top` es la tapa del bloque `grass`
+            let face = name.rsplit_once('_').and_then(|(block, face)| Some((block, Face::from_name(face)?)));
+            if let Some((block, faces)) = face {
+                let texture = Texture::load(path)?;
+                let entry = entries.entry(block.to_string()).or_default();
+                for face in faces {
+                    entry.faces.get_or_insert_with(FaceTextures::new).set(face, texture.clone());
+                }
+                continue;
+            }
+
+            let normal_name = format!("{}{}", name, NORMAL_MAP_SUFFIX);
+            let entry = entries.entry(name.clone()).or_default();
+            entry.texture = Some(Texture::load(path)?);
+            if let Some((_, normal_path)) = images.iter().find(|(other, _)| *other == normal_name) {
+                entry.normal_map = Some(Texture::load(normal_path)?);
+            }
+        }
+        if entries.is_empty() {
+            return Err(format!("El paquete {} no tiene imágenes", dir));
+        }
+
+        let name = Path::new(dir).file_name().and_then(|n| n.to_str()).unwrap_or(dir).to_string();
+        Ok(TexturePack { name, entries })
+    }
+
+    // Las texturas que tiene ahora la paleta (también la falta de textura), para poder volver a ellas
+    pub fn from_palette(name: &str, palette: &Palette) -> Self {
+        let entries = palette.named_materials()
+            .map(|(block, material)| {
+                let entry = PackEntry {
+                    texture: material.texture.clone(),
+                    normal_map: material.normal_map.clone(),
+                    faces: material.face_textures.clone(),
+                };
+                (block.to_string(), entry)
+            })
+            .collect();
+        TexturePack { name: name.to_string(), entries }
+    }
+}
+
+// Paquetes entre los que se puede cambiar en tiempo de ejecución. El primero es
+// siempre el de la escena tal como se cargó.
+pub struct TexturePacks {
+    packs: Vec<TexturePack>,
+    current: usize,
+}
+
+impl TexturePacks {
+    pub fn new(scene: &Scene, packs: Vec<TexturePack>) -> Self {
+        let mut all = vec![TexturePack::from_palette("original", &scene.world.palette)];
+        all.extend(packs);
+        TexturePacks { packs: all, current: 0 }
+    }
+
+    pub fn current(&self) -> &TexturePack {
+        &self.packs[self.current]
+    }
+
+    // Pasa al siguiente paquete (volviendo al original tras el último) y lo aplica
+    pub fn next(&mut self, scene: &mut Scene) {
+        self.select(scene, (self.current + 1) % self.packs.len());
+    }
+
+    pub fn select(&mut self, scene: &mut Scene, index: usize) {
+        let Some(pack) = self.packs.get(index) else { return };
+        let original = &self.packs[0];
+        let palette = &scene.world.palette;
+
+        let mut materials = Vec::new();
+        for (block, material) in palette.named_materials() {
+            // Lo que el paquete no trae se toma del original
+            let Some(base) = original.entries.get(block) else { continue };
+            let entry = pack.entries.get(block).unwrap_or(base);
+            let mut material = (**material).clone();
+            // Las imágenes del paquete no traen filtro: heredan el de la textura original
+            let filter = base.texture.as_ref().and_then(|texture| texture.filter);
+            material.texture = entry.texture.clone().or_else(|| base.texture.clone()).map(|mut texture| {
+                texture.filter = texture.filter.or(filter);
+                texture
+            });
+            material.face_textures = entry.faces.clone().map(|mut faces| {
+                for texture in faces.textures_mut() {
+                    texture.filter = texture.filter.or(filter);
+                }
+                faces
+            });
+            // Sin mapa de normales propio, se recalcula del color si el original salía de él
+            material.normal_map = entry.normal_map.clone().or_else(|| {
+                let normal_map = base.normal_map.as_ref()?;
+                match (&normal_map.source, &material.texture) {
+                    (Some(TextureSource::NormalMap(_, strength)), Some(texture)) => Some(texture.to_normal_map(*strength)),
+                    _ => Some(normal_map.clone()),
+                }
+            });
+            materials.push((palette.id(block).unwrap(), material));
+        }
+
+        scene.replace_block_materials(materials);
+        self.current = index;
+    }
+}
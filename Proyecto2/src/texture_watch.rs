@@ -0,0 +1,55 @@
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::scene::Scene;
+use crate::texture::Texture;
+
+// Cada cuánto se consulta la fecha de modificación de los archivos
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Vigila los archivos de textura de la escena y los recarga en cuanto cambian, para
+// ver en la ventana abierta lo que se edita en un programa de dibujo. Compara la fecha
+// de modificación de cada archivo, sin depender de los avisos del sistema operativo.
+pub struct TextureWatcher {
+    files: Vec<(String, Option<SystemTime>)>,
+    last_poll: Instant,
+}
+
+impl TextureWatcher {
+    // Empieza a vigilar las texturas que usa ahora la escena
+    pub fn new(scene: &Scene) -> Self {
+        let files = scene.texture_paths().into_iter().map(|path| {
+            let modified = modified(&path);
+            (path, modified)
+        });
+        TextureWatcher { files: files.collect(), last_poll: Instant::now() }
+    }
+
+    // Recarga en la escena los archivos modificados desde la última consulta
+    pub fn poll(&mut self, scene: &mut Scene) {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+
+        for (path, last_modified) in self.files.iter_mut() {
+            let current = modified(path);
+            if current.is_none() || current == *last_modified {
+                continue;
+            }
+            // Si el editor aún no termina de guardar, su siguiente escritura vuelve a cambiar la fecha
+            *last_modified = current;
+            match Texture::load(path) {
+                Ok(image) => {
+                    scene.reload_texture(path, &image);
+                    eprintln!("Textura recargada: {}", path);
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,33 @@
+use crate::color::Color;
+
+// Cómo se comprime el color lineal, que no tiene techo, al rango [0, 1] de la pantalla
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tonemap {
+    // Recorta cada canal en 1, como antes de trabajar en flotante
+    Clamp,
+    // x / (1 + x): nunca llega al blanco y las luces fuertes se aplanan suavemente
+    Reinhard,
+    // Aproximación de Narkowicz a la curva filmica de ACES: más contraste en los medios
+    Aces,
+}
+
+impl Tonemap {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clamp" | "none" => Some(Tonemap::Clamp),
+            "reinhard" => Some(Tonemap::Reinhard),
+            "aces" => Some(Tonemap::Aces),
+            _ => None,
+        }
+    }
+
+    // Color lineal listo para la gamma, multiplicado antes por `exposure`
+    pub fn apply(self, color: Color, exposure: f32) -> Color {
+        let [r, g, b] = (color * exposure).linear().map(|x| match self {
+            Tonemap::Clamp => x.min(1.0),
+            Tonemap::Reinhard => x / (1.0 + x),
+            Tonemap::Aces => ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0),
+        });
+        Color::from_linear(r, g, b)
+    }
+}
@@ -0,0 +1,85 @@
+use nalgebra_glm::Vec3;
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::light::{Light, ShadowModel};
+use crate::material::Material;
+use crate::noise::fbm_2d;
+use crate::scene::DEFAULT_GROUP;
+
+// Lado del palo y de la punta, en fracción de bloque (2 y 3 de 16 píxeles)
+const STICK_WIDTH: f32 = 2.0 / 16.0;
+const TIP_SIZE: f32 = 3.0 / 16.0;
+// Cambios de brillo por segundo del parpadeo
+const FLICKER_SPEED: f32 = 6.0;
+
+// Antorcha: un palo fino, una punta que brilla y una luz puntual en la punta que
+// parpadea. Se coloca de una vez con `Scene::add_torch`, que crea los dos cubos y la
+// luz y se encarga de animar el parpadeo con el reloj de la escena.
+#[derive(Debug, Clone)]
+pub struct Torch {
+    // Base del palo
+    pub position: Vec3,
+    pub height: f32,
+    pub stick: Arc<Material>,
+    pub flame_color: Color,
+    pub intensity: f32,
+    // Distancia a la que la luz se apaga del todo
+    pub range: f32,
+    // Cuánto baja el brillo en el parpadeo (0 lo deja fijo, 1 puede apagarla)
+    pub flicker: f32,
+    pub seed: u64,
+    pub group: usize,
+}
+
+impl Torch {
+    pub fn new(position: Vec3, stick: Arc<Material>) -> Self {
+        Torch {
+            position,
+            height: 0.6,
+            stick,
+            flame_color: Color::new(255, 170, 60),
+            intensity: 0.6,
+            range: 6.0,
+            flicker: 0.3,
+            seed: 0,
+            group: DEFAULT_GROUP,
+        }
+    }
+
+    // Centro de la punta, donde está la luz
+    pub fn tip(&self) -> Vec3 {
+        self.position + Vec3::new(0.0, self.height + TIP_SIZE * 0.5, 0.0)
+    }
+
+    // Palo y punta luminosa. La punta no hace sombra: la luz está dentro de ella.
+    pub fn cubes(&self) -> [Cube; 2] {
+        let half_stick = Vec3::new(STICK_WIDTH * 0.5, 0.0, STICK_WIDTH * 0.5);
+        let mut stick = Cube::new(self.position - half_stick, self.position + half_stick + Vec3::new(0.0, self.height, 0.0), self.stick.clone());
+        stick.group = self.group;
+
+        let mut flame = Material::new(self.flame_color, 0.0, [0.0, 0.0, 0.0, 0.0], 0.0, None);
+        flame.emission = self.flame_color;
+        flame.emission_intensity = 1.0;
+        let half_tip = Vec3::repeat(TIP_SIZE * 0.5);
+        let mut tip = Cube::new(self.tip() - half_tip, self.tip() + half_tip, Arc::new(flame));
+        tip.casts_shadows = false;
+        tip.group = self.group;
+        [stick, tip]
+    }
+
+    pub fn light(&self) -> Light {
+        let mut light = Light::new(self.tip(), self.flame_color, self.intensity);
+        light.shadow = ShadowModel::Hard;
+        light.range = Some(self.range);
+        light.group = self.group;
+        light
+    }
+
+    // Intensidad de la luz en el instante `time`, en segundos de escena
+    pub fn intensity_at(&self, time: f32) -> f32 {
+        let noise = fbm_2d(self.seed, time * FLICKER_SPEED, 0.0, 2);
+        self.intensity * (1.0 - self.flicker.clamp(0.0, 1.0) * noise)
+    }
+}
@@ -0,0 +1,214 @@
+use nalgebra_glm::{IVec3, Vec3};
+use std::sync::Arc;
+
+use crate::block_shape::BlockShape;
+use crate::cube::{Cube, Face};
+use crate::material::Material;
+use crate::ray_intersect::Intersect;
+use crate::world::{BlockId, PlacedBlock, World, AIR};
+
+// Celda de la rejilla sin bloque
+const EMPTY: PlacedBlock = PlacedBlock { id: AIR, group: 0 };
+
+// Bloques del mundo en un arreglo 3D denso que cubre su caja envolvente, con los
+// materiales de la paleta indexados por identificador. Los rayos la recorren celda
+// a celda con un DDA 3D (Amanatides y Woo), así que el coste depende de las celdas
+// que atraviesan y no del número de bloques.
+pub struct VoxelGrid {
+    min: IVec3,
+    size: IVec3,
+    cells: Vec<PlacedBlock>,
+    materials: Vec<Option<Arc<Material>>>,
+    shapes: Vec<BlockShape>,
+}
+
+// Bloque golpeado por un rayo
+pub struct VoxelHit {
+    pub intersect: Intersect,
+    pub position: IVec3,
+    pub block: PlacedBlock,
+}
+
+impl Default for VoxelGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VoxelGrid {
+    pub fn new() -> Self {
+        VoxelGrid { min: IVec3::zeros(), size: IVec3::zeros(), cells: Vec::new(), materials: Vec::new(), shapes: Vec::new() }
+    }
+
+    pub fn from_world(world: &World) -> Self {
+        let Some((min, max)) = world.bounds() else {
+            return VoxelGrid::new();
+        };
+        let size = max - min + IVec3::repeat(1);
+        let mut grid = VoxelGrid {
+            min,
+            size,
+            cells: vec![EMPTY; (size.x * size.y * size.z) as usize],
+            materials: Vec::new(),
+            shapes: Vec::new(),
+        };
+        let mut highest = AIR;
+        for (position, placed) in world.placed_blocks() {
+            let index = grid.index(position).unwrap();
+            grid.cells[index] = placed;
+            highest = highest.max(placed.id);
+        }
+        grid.materials = (0..=highest).map(|id: BlockId| world.palette.material(id)).collect();
+        grid.shapes = (0..=highest).map(|id: BlockId| world.palette.shape(id)).collect();
+        grid
+    }
+
+    // Esquinas mínima y máxima (exclusiva) de la rejilla en coordenadas del mundo
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let min = Vec3::new(self.min.x as f32, self.min.y as f32, self.min.z as f32);
+        (min, min + Vec3::new(self.size.x as f32, self.size.y as f32, self.size.z as f32))
+    }
+
+    fn index(&self, position: IVec3) -> Option<usize> {
+        let local = position - self.min;
+        let inside = (0..3).all(|axis| local[axis] >= 0 && local[axis] < self.size[axis]);
+        inside.then(|| ((local.z * self.size.y + local.y) * self.size.x + local.x) as usize)
+    }
+
+    // Bloque con material en esa posición, si lo hay
+    pub fn get(&self, position: IVec3) -> Option<(PlacedBlock, &Arc<Material>)> {
+        let placed = self.cells[self.index(position)?];
+        let material = self.materials.get(placed.id as usize)?.as_ref()?;
+        Some((placed, material))
+    }
+
+    // Primer bloque aceptado por `accept` que atraviesa el rayo antes de `max_distance`.
+    // Si el rayo nace dentro de un bloque se devuelve su cara de salida con la normal
+    // hacia dentro, igual que al intersecar un cubo desde su interior. En las celdas de
+    // bloques finos se interseca su caja y, si el rayo no la toca, se sigue avanzando.
+    pub fn traverse(&self, origin: &Vec3, direction: &Vec3, max_distance: f32, accept: impl Fn(&PlacedBlock, &Material) -> bool) -> Option<VoxelHit> {
+        if self.cells.is_empty() {
+            return None;
+        }
+
+        // Tramo del rayo dentro de la caja de la rejilla y eje por el que entra
+        let (grid_min, grid_max) = self.bounds();
+        let mut t_enter = 0.0f32;
+        let mut t_exit = max_distance;
+        let mut entry_axis = None;
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < grid_min[axis] || origin[axis] > grid_max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (grid_min[axis] - origin[axis]) / direction[axis];
+            let t1 = (grid_max[axis] - origin[axis]) / direction[axis];
+            if t0.min(t1) > t_enter {
+                t_enter = t0.min(t1);
+                entry_axis = Some(axis);
+            }
+            t_exit = t_exit.min(t0.max(t1));
+        }
+        if t_enter > t_exit {
+            return None;
+        }
+
+        // Celda inicial; en la cara de entrada el redondeo puede caer fuera, así que se acota
+        let start = origin + direction * t_enter;
+        let mut voxel = IVec3::new(0, 0, 0);
+        let mut step = IVec3::new(0, 0, 0);
+        let mut t_next = Vec3::repeat(f32::INFINITY);
+        let mut t_delta = Vec3::repeat(f32::INFINITY);
+        for axis in 0..3 {
+            voxel[axis] = (start[axis].floor() as i32).clamp(self.min[axis], self.min[axis] + self.size[axis] - 1);
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                t_next[axis] = ((voxel[axis] + 1) as f32 - origin[axis]) / direction[axis];
+                t_delta[axis] = 1.0 / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                t_next[axis] = (voxel[axis] as f32 - origin[axis]) / direction[axis];
+                t_delta[axis] = -1.0 / direction[axis];
+            }
+        }
+
+        let mut t = t_enter;
+        loop {
+            if let Some((placed, material)) = self.get(voxel) {
+                let cell = Vec3::new(voxel.x as f32, voxel.y as f32, voxel.z as f32);
+                let shape = self.shapes.get(placed.id as usize).copied().unwrap_or_default();
+                let hit = if !accept(&placed, material) {
+                    None
+                } else if shape.is_full() {
+                    // Sin eje de entrada el origen está dentro de este bloque: se sale por la cara más cercana
+                    Some(match entry_axis {
+                        Some(axis) => (t, axis, false),
+                        None => {
+                            let axis = t_next.imin();
+                            (t_next[axis], axis, true)
+                        }
+                    })
+                } else {
+                    shape.intersect(&(origin - cell), direction)
+                };
+
+                if let Some((distance, axis, inside)) = hit {
+                    if distance > max_distance {
+                        return None;
+                    }
+                    let face_hit = |distance: f32, axis: usize, inside: bool| {
+                        let intersect = face_intersect(origin, direction, &cell, distance, axis, inside, material);
+                        Intersect { group: placed.group, ..intersect }
+                    };
+
+                    let intersect = face_hit(distance, axis, inside);
+                    if material.covers(intersect.uv) {
+                        return Some(VoxelHit { intersect, position: voxel, block: placed });
+                    }
+                    // Por un hueco del alfa se ve la cara de enfrente del mismo bloque, por dentro
+                    if shape.is_full() && !inside {
+                        let exit_axis = t_next.imin();
+                        let back = face_hit(t_next[exit_axis], exit_axis, true);
+                        if back.distance <= max_distance && material.covers(back.uv) {
+                            return Some(VoxelHit { intersect: back, position: voxel, block: placed });
+                        }
+                    }
+                }
+            }
+
+            let axis = t_next.imin();
+            t = t_next[axis];
+            if t > t_exit {
+                return None;
+            }
+            voxel[axis] += step[axis];
+            if voxel[axis] < self.min[axis] || voxel[axis] >= self.min[axis] + self.size[axis] {
+                return None;
+            }
+            t_next[axis] += t_delta[axis];
+            entry_axis = Some(axis);
+        }
+    }
+}
+
+// Impacto en la cara del eje `axis` de la celda con esquina `cell`, a `distance` del
+// origen. Con `inside` el rayo sale del bloque y la cara es la de salida.
+fn face_intersect(origin: &Vec3, direction: &Vec3, cell: &Vec3, distance: f32, axis: usize, inside: bool, material: &Arc<Material>) -> Intersect {
+    // La cara impactada siempre mira en contra de la dirección del rayo en ese eje
+    let mut normal = Vec3::new(0.0, 0.0, 0.0);
+    normal[axis] = -direction[axis].signum();
+    // Normal exterior de la cara, usada para orientar la textura
+    let face_normal = if inside { -normal } else { normal };
+
+    let point = origin + direction * distance;
+    let local = point - cell;
+    let mut intersect = Intersect::new(point, normal, distance, material.clone());
+    let (uv, basis) = Cube::oriented_face(&local, &face_normal, material);
+    intersect.uv = Some(uv);
+    intersect.tangent = Some(basis);
+    intersect.face = Some(Face::from_normal(&face_normal));
+    intersect.front_face = !inside;
+    intersect
+}
@@ -0,0 +1,59 @@
+use nalgebra_glm::Vec3;
+
+use crate::noise::value_noise_3d;
+
+// Caras con la normal por debajo de esta componente vertical no se ondulan: solo
+// la superficie del agua, no los lados de los bloques
+const MIN_SLOPE: f32 = 0.5;
+// Direcciones de las ondas en el plano XZ, sin alinear con la rejilla de bloques
+const WAVE_DIRECTIONS: [(f32, f32); 3] = [(0.8, 0.6), (-0.4, 0.92), (0.96, -0.28)];
+
+// Ondas en la superficie del agua: la altura es una suma de senos que avanzan con el
+// tiempo de la escena más un poco de ruido para que no se vea el patrón, y la normal
+// se inclina con su pendiente. La geometría no cambia, solo cómo refleja y refracta.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ripples {
+    // Altura de las ondas; controla cuánto se inclina la normal
+    pub amplitude: f32,
+    // Distancia entre crestas en bloques
+    pub wavelength: f32,
+    // Velocidad de avance en bloques por segundo
+    pub speed: f32,
+    pub seed: u64,
+}
+
+impl Default for Ripples {
+    fn default() -> Self {
+        Ripples { amplitude: 0.03, wavelength: 1.2, speed: 0.6, seed: 0 }
+    }
+}
+
+impl Ripples {
+    // Normal de `normal` inclinada por las ondas en `point` en el instante `time`
+    pub fn perturb(&self, point: &Vec3, normal: &Vec3, time: f32) -> Vec3 {
+        if normal.y < MIN_SLOPE || self.amplitude <= 0.0 {
+            return *normal;
+        }
+        let k = std::f32::consts::TAU / self.wavelength.max(1e-3);
+        let mut slope = (0.0, 0.0);
+        for (index, (dx, dz)) in WAVE_DIRECTIONS.into_iter().enumerate() {
+            // Cada onda más corta y baja que la anterior, con la misma pendiente máxima:
+            // la altura es amplitude / scale · sin(k · scale · x), así que su derivada
+            // no depende de `scale`
+            let scale = 1.0 + index as f32 * 0.6;
+            let phase = k * scale * (dx * point.x + dz * point.z) - k * self.speed * time;
+            let derivative = self.amplitude * k * phase.cos();
+            slope.0 += derivative * dx;
+            slope.1 += derivative * dz;
+        }
+
+        // Ruido que cambia con el tiempo, derivado por diferencias finitas
+        let noise = |x: f32, z: f32| value_noise_3d(self.seed, x * 2.0, z * 2.0, time * self.speed);
+        let h = 0.05;
+        let base = noise(point.x, point.z);
+        slope.0 += self.amplitude * (noise(point.x + h, point.z) - base) / h;
+        slope.1 += self.amplitude * (noise(point.x, point.z + h) - base) / h;
+
+        Vec3::new(normal.x - slope.0, normal.y, normal.z - slope.1).normalize()
+    }
+}
@@ -0,0 +1,216 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::particles::Particle;
+use crate::rng::Rng;
+
+// Segundos que tardan los efectos en acercarse al nuevo estado al cambiar el tiempo
+const TRANSITION_SECONDS: f32 = 3.0;
+// Las superficies se mojan rápido y se secan despacio
+const DRYING_SECONDS: f32 = 20.0;
+// Gotas con la lluvia al máximo
+const MAX_DROPS: usize = 800;
+// Margen horizontal alrededor del mundo y altura sobre él desde la que cae la lluvia
+const RAIN_MARGIN: f32 = 4.0;
+const RAIN_HEIGHT: f32 = 8.0;
+const RAIN_SPEED: f32 = 14.0;
+// Relámpagos por segundo en tormenta, y cuánto tarda en apagarse el destello
+const LIGHTNING_RATE: f32 = 0.15;
+const FLASH_DECAY: f32 = 6.0;
+
+// Estado del tiempo; sin ciclo automático se queda en el que se elija
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Storm,
+}
+
+impl WeatherKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clear" => Some(WeatherKind::Clear),
+            "rain" => Some(WeatherKind::Rain),
+            "storm" => Some(WeatherKind::Storm),
+            _ => None,
+        }
+    }
+
+    // Despejado, lluvia, tormenta y vuelta a empezar
+    pub fn next(self) -> Self {
+        match self {
+            WeatherKind::Clear => WeatherKind::Rain,
+            WeatherKind::Rain => WeatherKind::Storm,
+            WeatherKind::Storm => WeatherKind::Clear,
+        }
+    }
+
+    // Efectos hacia los que se mueve la atmósfera en este estado
+    fn target(self) -> Atmosphere {
+        match self {
+            WeatherKind::Clear => Atmosphere::default(),
+            WeatherKind::Rain => Atmosphere { sky_darkening: 0.5, fog_density: 0.02, wetness: 0.8, rain: 0.5, wind: 1.0, flash: 0.0 },
+            WeatherKind::Storm => Atmosphere { sky_darkening: 0.8, fog_density: 0.05, wetness: 1.0, rain: 1.0, wind: 4.0, flash: 0.0 },
+        }
+    }
+}
+
+// Efectos del tiempo que leen el cielo y los integradores; todo a 0 es un día despejado
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Atmosphere {
+    // Cuánto se cubre y oscurece el cielo (0 a 1)
+    pub sky_darkening: f32,
+    // Niebla exponencial por unidad de distancia
+    pub fog_density: f32,
+    // Superficies mojadas: más oscuras y con más brillo especular (0 a 1)
+    pub wetness: f32,
+    // Fracción de las gotas de lluvia que caen
+    pub rain: f32,
+    // Velocidad horizontal del viento en +X
+    pub wind: f32,
+    // Destello de un relámpago sobre el cielo
+    pub flash: f32,
+}
+
+impl Atmosphere {
+    // Cielo cubierto: se desatura hacia un gris algo más oscuro que el cielo original
+    pub fn overcast(&self, sky: Color) -> Color {
+        if self.sky_darkening <= 0.0 && self.flash <= 0.0 {
+            return sky;
+        }
+        let luminance = sky.luminance() * (1.0 - 0.6 * self.sky_darkening);
+        let grey = Color::from_linear(luminance, luminance, luminance);
+        sky * (1.0 - self.sky_darkening) + grey * self.sky_darkening + Color::from_linear(1.0, 1.0, 1.0) * self.flash
+    }
+
+    // Mezcla `color`, visto a `distance`, con el color de la niebla
+    pub fn fog(&self, color: Color, fog_color: Color, distance: f32) -> Color {
+        if self.fog_density <= 0.0 {
+            return color;
+        }
+        let transmittance = (-self.fog_density * distance).exp();
+        color * transmittance + fog_color * (1.0 - transmittance)
+    }
+}
+
+// Máquina de estados del tiempo. Al cambiar de estado los efectos de `atmosphere` se
+// mezclan poco a poco hacia los del nuevo, y la lluvia se genera sobre el mundo como
+// partículas que caen hasta el primer bloque que encuentran.
+pub struct Weather {
+    pub kind: WeatherKind,
+    // Segundos que dura cada estado antes de pasar al siguiente; None lo deja fijo
+    pub cycle: Option<f32>,
+    pub atmosphere: Atmosphere,
+    time_in_state: f32,
+    drops: Vec<Particle>,
+    // Altura a la que se detiene cada gota
+    floors: Vec<f32>,
+    rng: Rng,
+}
+
+impl Weather {
+    // Empieza ya con los efectos del estado, sin transición
+    pub fn new(kind: WeatherKind, seed: u64) -> Self {
+        Weather {
+            kind,
+            cycle: None,
+            atmosphere: kind.target(),
+            time_in_state: 0.0,
+            drops: Vec::new(),
+            floors: Vec::new(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn set(&mut self, kind: WeatherKind) {
+        self.kind = kind;
+        self.time_in_state = 0.0;
+    }
+
+    pub fn next(&mut self) {
+        self.set(self.kind.next());
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.drops
+    }
+
+    // Avanza `dt` segundos. La lluvia cae dentro de `area` (mínimo y máximo del mundo) y
+    // `floor` da la altura del primer obstáculo bajo un punto.
+    pub fn update(&mut self, dt: f32, area: Option<(Vec3, Vec3)>, floor: impl Fn(&Vec3) -> f32) {
+        self.time_in_state += dt;
+        if let Some(cycle) = self.cycle.filter(|cycle| self.time_in_state >= *cycle) {
+            self.set(self.kind.next());
+            self.time_in_state -= cycle.min(self.time_in_state);
+        }
+
+        let target = self.kind.target();
+        let blend = |current: &mut f32, target: f32, seconds: f32| *current += (target - *current) * (1.0 - (-dt / seconds).exp());
+        let current = &mut self.atmosphere;
+        blend(&mut current.sky_darkening, target.sky_darkening, TRANSITION_SECONDS);
+        blend(&mut current.fog_density, target.fog_density, TRANSITION_SECONDS);
+        blend(&mut current.rain, target.rain, TRANSITION_SECONDS);
+        blend(&mut current.wind, target.wind, TRANSITION_SECONDS);
+        let wetting = if target.wetness > current.wetness { TRANSITION_SECONDS } else { DRYING_SECONDS };
+        blend(&mut current.wetness, target.wetness, wetting);
+
+        current.flash *= (-dt * FLASH_DECAY).exp();
+        if self.kind == WeatherKind::Storm && self.rng.next_f32() < dt * LIGHTNING_RATE {
+            current.flash = 1.0;
+        }
+
+        match area {
+            Some(area) => self.update_rain(dt, area, floor),
+            None => {
+                self.drops.clear();
+                self.floors.clear();
+            }
+        }
+    }
+
+    fn update_rain(&mut self, dt: f32, (min, max): (Vec3, Vec3), floor: impl Fn(&Vec3) -> f32) {
+        let wanted = (self.atmosphere.rain * MAX_DROPS as f32) as usize;
+        let velocity = Vec3::new(self.atmosphere.wind, -RAIN_SPEED, 0.0);
+        let top = max.y + RAIN_HEIGHT;
+
+        let mut index = 0;
+        while index < self.drops.len() {
+            let drop = &mut self.drops[index];
+            drop.velocity = velocity;
+            drop.position += velocity * dt;
+            if drop.position.y > self.floors[index] {
+                index += 1;
+            } else if self.drops.len() > wanted {
+                // Sobran gotas: las que llegan al suelo no se reponen
+                self.drops.swap_remove(index);
+                self.floors.swap_remove(index);
+            } else {
+                let position = self.random_point(min, max, top);
+                self.floors[index] = floor(&position);
+                self.drops[index].position = position;
+                index += 1;
+            }
+        }
+
+        // Las nuevas aparecen a cualquier altura para que la lluvia no llegue como una cortina
+        while self.drops.len() < wanted {
+            let mut position = self.random_point(min, max, top);
+            let ground = floor(&position);
+            position.y = ground + (top - ground) * self.rng.next_f32();
+            self.floors.push(ground);
+            self.drops.push(Particle { position, velocity, color: Color::new(190, 200, 215), opacity: 0.35 });
+        }
+    }
+
+    fn random_point(&mut self, min: Vec3, max: Vec3, top: f32) -> Vec3 {
+        let x = min.x - RAIN_MARGIN + (max.x - min.x + 2.0 * RAIN_MARGIN) * self.rng.next_f32();
+        let z = min.z - RAIN_MARGIN + (max.z - min.z + 2.0 * RAIN_MARGIN) * self.rng.next_f32();
+        Vec3::new(x, top, z)
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new(WeatherKind::Clear, 0)
+    }
+}
@@ -0,0 +1,232 @@
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use softbuffer::{Context, Surface};
+use winit::application::ApplicationHandler;
+use winit::dpi::LogicalSize;
+use winit::event::{ElementState, MouseButton as WinitButton, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{Window, WindowId};
+
+use crate::app::App;
+use crate::camera::Camera;
+use crate::input::{Input, InputState, Key, MouseButton};
+use crate::pacing::FramePacer;
+use crate::scene::Scene;
+
+// Ventana con winit + softbuffer (feature `winit-backend`). A diferencia de minifb
+// conoce el factor de escala de la pantalla y entrega todos los eventos del ratón.
+pub fn run<F>(app: &mut App, title: &str, input: F)
+where
+    F: FnMut(&dyn Input, &mut Camera, &mut Scene),
+{
+    let event_loop = EventLoop::new().expect("No se pudo crear el bucle de eventos de winit");
+    let pacer = FramePacer::new(app.target_fps);
+    let mut handler = WinitHandler {
+        app,
+        title: title.to_string(),
+        input,
+        window: None,
+        state: InputState::new(),
+        pacer,
+    };
+    event_loop.run_app(&mut handler).expect("Error en el bucle de eventos de winit");
+}
+
+struct WinitWindow {
+    window: Rc<Window>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+}
+
+struct WinitHandler<'a, F> {
+    app: &'a mut App,
+    title: String,
+    input: F,
+    window: Option<WinitWindow>,
+    // Entrada acumulada desde el último fotograma
+    state: InputState,
+    pacer: FramePacer,
+}
+
+impl<F: FnMut(&dyn Input, &mut Camera, &mut Scene)> WinitHandler<'_, F> {
+    fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+        let dt = self.pacer.begin_frame();
+        self.state.frame_time = dt;
+        (self.input)(&self.state, &mut self.app.camera, &mut self.app.scene);
+        self.app.resolution_keys(&self.state);
+        self.state.keys_pressed.clear();
+        self.state.mouse_delta = (0.0, 0.0);
+        self.app.frame(dt);
+        self.app.inspect(&self.state);
+
+        let Some(target) = self.window.as_mut() else { return };
+        let size = target.window.inner_size();
+        let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) else {
+            return;
+        };
+        target.surface.resize(width, height).expect("No se pudo redimensionar la superficie");
+
+        let (width, height) = (size.width as usize, size.height as usize);
+        let mut buffer = target.surface.buffer_mut().expect("No se pudo obtener el buffer de la ventana");
+        self.app.framebuffer.blit_scaled(&mut buffer, width, height, self.app.display_rect((width, height)));
+        buffer.present().expect("No se pudo presentar el fotograma");
+
+        event_loop.set_control_flow(ControlFlow::WaitUntil(self.pacer.next_frame()));
+    }
+
+    // Posición del cursor en píxeles de la resolución completa, igual que con minifb
+    fn to_framebuffer(&self, x: f64, y: f64) -> Option<(f32, f32)> {
+        let size = self.window.as_ref()?.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return None;
+        }
+        Some(self.app.window_to_pixels((size.width as usize, size.height as usize), x as f32, y as f32))
+    }
+}
+
+impl<F: FnMut(&dyn Input, &mut Camera, &mut Scene)> ApplicationHandler for WinitHandler<'_, F> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+        let (width, height) = self.app.window_size;
+        let attributes = Window::default_attributes()
+            .with_title(self.title.clone())
+            .with_inner_size(LogicalSize::new(width as f64, height as f64));
+        let window = Rc::new(event_loop.create_window(attributes).expect("No se pudo crear la ventana"));
+        let context = Context::new(window.clone()).expect("No se pudo iniciar softbuffer");
+        let surface = Surface::new(&context, window.clone()).expect("No se pudo crear la superficie");
+        let scale = self.app.scale_factor.unwrap_or(window.scale_factor() as f32);
+        self.app.apply_scale_factor(scale);
+        self.window = Some(WinitWindow { window, surface });
+        self.pacer.begin_frame();
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::RedrawRequested => self.redraw(event_loop),
+            // Al mover la ventana a otra pantalla cambia la escala detectada
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } if self.app.scale_factor.is_none() => {
+                self.app.apply_scale_factor(scale_factor as f32);
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                let PhysicalKey::Code(code) = event.physical_key else { return };
+                let Some(key) = translate_key(code) else { return };
+                if key == Key::Escape {
+                    event_loop.exit();
+                    return;
+                }
+                match event.state {
+                    ElementState::Pressed => {
+                        if !event.repeat {
+                            self.state.keys_pressed.push(key);
+                        }
+                        if !self.state.keys_down.contains(&key) {
+                            self.state.keys_down.push(key);
+                        }
+                    }
+                    ElementState::Released => self.state.keys_down.retain(|down| *down != key),
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = self.to_framebuffer(position.x, position.y);
+                if let (Some((x, y)), Some((last_x, last_y))) = (position, self.state.mouse_position) {
+                    self.state.mouse_delta.0 += x - last_x;
+                    self.state.mouse_delta.1 += y - last_y;
+                }
+                self.state.mouse_position = position;
+            }
+            WindowEvent::CursorLeft { .. } => self.state.mouse_position = None,
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    WinitButton::Left => MouseButton::Left,
+                    WinitButton::Middle => MouseButton::Middle,
+                    WinitButton::Right => MouseButton::Right,
+                    _ => return,
+                };
+                self.state.mouse_buttons.retain(|down| *down != button);
+                if state == ElementState::Pressed {
+                    self.state.mouse_buttons.push(button);
+                }
+            }
+            // Las teclas soltadas fuera de la ventana no llegan; mejor olvidarlas todas
+            WindowEvent::Focused(false) => {
+                self.state.keys_down.clear();
+                self.state.mouse_buttons.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Cuando llega el momento que marca el `FramePacer` se pide el siguiente fotograma
+        let waiting = matches!(event_loop.control_flow(), ControlFlow::WaitUntil(until) if until > Instant::now());
+        if !waiting {
+            if let Some(target) = &self.window {
+                target.window.request_redraw();
+            }
+            event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + Duration::from_secs(1)));
+        }
+    }
+}
+
+// Traduce las teclas de winit a los identificadores de minifb que usan los controles
+fn translate_key(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::KeyA => Key::A,
+        KeyCode::KeyB => Key::B,
+        KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G,
+        KeyCode::KeyH => Key::H,
+        KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J,
+        KeyCode::KeyK => Key::K,
+        KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M,
+        KeyCode::KeyN => Key::N,
+        KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyT => Key::T,
+        KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V,
+        KeyCode::KeyW => Key::W,
+        KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y,
+        KeyCode::KeyZ => Key::Z,
+        KeyCode::Digit0 => Key::Key0,
+        KeyCode::Digit1 => Key::Key1,
+        KeyCode::Digit2 => Key::Key2,
+        KeyCode::Digit3 => Key::Key3,
+        KeyCode::Digit4 => Key::Key4,
+        KeyCode::Digit5 => Key::Key5,
+        KeyCode::Digit6 => Key::Key6,
+        KeyCode::Digit7 => Key::Key7,
+        KeyCode::Digit8 => Key::Key8,
+        KeyCode::Digit9 => Key::Key9,
+        KeyCode::ArrowUp => Key::Up,
+        KeyCode::ArrowDown => Key::Down,
+        KeyCode::ArrowLeft => Key::Left,
+        KeyCode::ArrowRight => Key::Right,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::Space => Key::Space,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Equal => Key::Equal,
+        KeyCode::Minus => Key::Minus,
+        KeyCode::ShiftLeft => Key::LeftShift,
+        KeyCode::ShiftRight => Key::RightShift,
+        KeyCode::ControlLeft => Key::LeftCtrl,
+        KeyCode::ControlRight => Key::RightCtrl,
+        _ => return None,
+    };
+    Some(key)
+}
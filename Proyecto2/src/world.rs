@@ -0,0 +1,381 @@
+use nalgebra_glm::IVec3;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::block_shape::BlockShape;
+use crate::material::Material;
+use crate::scene::DEFAULT_GROUP;
+
+// Identificador de tipo de bloque dentro de la paleta
+pub type BlockId = u16;
+
+// El bloque 0 siempre es aire: colocarlo equivale a borrar
+pub const AIR: BlockId = 0;
+
+// Relación entre identificadores de bloque, nombres, materiales y formas
+pub struct Palette {
+    names: Vec<String>,
+    materials: Vec<Option<Arc<Material>>>,
+    shapes: Vec<BlockShape>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Palette {
+            names: vec!["air".to_string()],
+            materials: vec![None],
+            shapes: vec![BlockShape::Full],
+        }
+    }
+
+    // Registra un tipo de bloque y devuelve su identificador
+    pub fn add(&mut self, name: &str, material: Material) -> BlockId {
+        if let Some(id) = self.id(name) {
+            self.materials[id as usize] = Some(Arc::new(material));
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.materials.push(Some(Arc::new(material)));
+        self.shapes.push(BlockShape::Full);
+        (self.names.len() - 1) as BlockId
+    }
+
+    // Registra un tipo de bloque que no ocupa la celda entera
+    pub fn add_shaped(&mut self, name: &str, material: Material, shape: BlockShape) -> BlockId {
+        let id = self.add(name, material);
+        self.shapes[id as usize] = shape;
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<BlockId> {
+        self.names.iter().position(|n| n == name).map(|i| i as BlockId)
+    }
+
+    pub fn name(&self, id: BlockId) -> Option<&str> {
+        self.names.get(id as usize).map(String::as_str)
+    }
+
+    pub fn material(&self, id: BlockId) -> Option<Arc<Material>> {
+        self.materials.get(id as usize).cloned().flatten()
+    }
+
+    pub fn shape(&self, id: BlockId) -> BlockShape {
+        self.shapes.get(id as usize).copied().unwrap_or_default()
+    }
+
+    // Nombre y material de cada tipo de bloque, sin el aire
+    pub fn named_materials(&self) -> impl Iterator<Item = (&str, &Arc<Material>)> {
+        self.names.iter().zip(&self.materials)
+            .filter_map(|(name, material)| Some((name.as_str(), material.as_ref()?)))
+    }
+
+    pub fn materials(&self) -> impl Iterator<Item = &Arc<Material>> {
+        self.materials.iter().flatten()
+    }
+
+    pub fn materials_mut(&mut self) -> impl Iterator<Item = &mut Arc<Material>> {
+        self.materials.iter_mut().flatten()
+    }
+}
+
+// Operaciones de edición comunes al mundo y a las estructuras: todas se
+// expresan a partir de `set_block`, con rangos inclusivos en ambos extremos
+pub trait BlockEdit {
+    fn set_block(&mut self, pos: IVec3, block: BlockId);
+
+    // Rellena la caja [min, max]
+    fn fill(&mut self, min: IVec3, max: IVec3, block: BlockId) {
+        let (min, max) = (min.inf(&max), min.sup(&max));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    self.set_block(IVec3::new(x, y, z), block);
+                }
+            }
+        }
+    }
+
+    // Solo las seis caras de la caja [min, max], dejando el interior intacto
+    fn hollow_box(&mut self, min: IVec3, max: IVec3, block: BlockId) {
+        let (min, max) = (min.inf(&max), min.sup(&max));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let on_border = x == min.x || x == max.x || y == min.y || y == max.y || z == min.z || z == max.z;
+                    if on_border {
+                        self.set_block(IVec3::new(x, y, z), block);
+                    }
+                }
+            }
+        }
+    }
+
+    // Línea de bloques entre dos puntos (incluidos), sin huecos entre pasos
+    fn line(&mut self, from: IVec3, to: IVec3, block: BlockId) {
+        let delta = to - from;
+        let steps = delta.x.abs().max(delta.y.abs()).max(delta.z.abs());
+        if steps == 0 {
+            self.set_block(from, block);
+            return;
+        }
+
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let pos = IVec3::new(
+                from.x + (delta.x as f32 * t).round() as i32,
+                from.y + (delta.y as f32 * t).round() as i32,
+                from.z + (delta.z as f32 * t).round() as i32,
+            );
+            self.set_block(pos, block);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "x" => Some(Axis::X),
+            "y" => Some(Axis::Y),
+            "z" => Some(Axis::Z),
+            _ => None,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+// Giro de `quarter_turns` cuartos de vuelta alrededor del eje Y (sentido antihorario visto desde arriba)
+fn rotate_y(pos: IVec3, quarter_turns: i32) -> IVec3 {
+    match quarter_turns.rem_euclid(4) {
+        1 => IVec3::new(pos.z, pos.y, -pos.x),
+        2 => IVec3::new(-pos.x, pos.y, -pos.z),
+        3 => IVec3::new(-pos.z, pos.y, pos.x),
+        _ => pos,
+    }
+}
+
+// Reflejo respecto al plano perpendicular a `axis` que pasa por el origen
+fn mirror(pos: IVec3, axis: Axis) -> IVec3 {
+    let mut mirrored = pos;
+    mirrored[axis.index()] = -pos[axis.index()];
+    mirrored
+}
+
+fn in_box(pos: &IVec3, min: &IVec3, max: &IVec3) -> bool {
+    pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y && pos.z >= min.z && pos.z <= max.z
+}
+
+// Conjunto de bloques con posiciones relativas, listo para colocarse en el mundo
+#[derive(Debug, Clone, Default)]
+pub struct Structure {
+    pub blocks: HashMap<IVec3, BlockId>,
+}
+
+impl Structure {
+    pub fn new() -> Self {
+        Structure { blocks: HashMap::new() }
+    }
+
+    // Copia girada `quarter_turns` cuartos de vuelta alrededor del eje Y (sentido antihorario visto desde arriba)
+    pub fn rotated_y(&self, quarter_turns: i32) -> Structure {
+        let blocks = self.blocks.iter()
+            .map(|(pos, block)| (rotate_y(*pos, quarter_turns), *block))
+            .collect();
+        Structure { blocks }
+    }
+
+    // Copia reflejada respecto al plano perpendicular a `axis` que pasa por el origen
+    pub fn mirrored(&self, axis: Axis) -> Structure {
+        let blocks = self.blocks.iter()
+            .map(|(pos, block)| (mirror(*pos, axis), *block))
+            .collect();
+        Structure { blocks }
+    }
+
+    // Añade a la estructura su reflejo: cada bloque con coordenada `c` en `axis`
+    // se copia a `sum - c`, es decir, el plano de simetría está en `sum / 2`
+    pub fn mirror_copy(&mut self, axis: Axis, sum: i32) {
+        let mut shift = IVec3::new(0, 0, 0);
+        shift[axis.index()] = sum;
+        for (pos, block) in self.mirrored(axis).blocks {
+            self.blocks.insert(pos + shift, block);
+        }
+    }
+}
+
+impl BlockEdit for Structure {
+    fn set_block(&mut self, pos: IVec3, block: BlockId) {
+        self.blocks.insert(pos, block);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedBlock {
+    pub id: BlockId,
+    pub group: usize,
+}
+
+// Mundo de bloques de tamaño 1 alineados a la cuadrícula entera
+pub struct World {
+    pub palette: Palette,
+    blocks: HashMap<IVec3, PlacedBlock>,
+    // Alturas ocupadas de cada columna (x, z), para encontrar la más alta sin recorrer todo
+    columns: HashMap<(i32, i32), BTreeSet<i32>>,
+    // Grupo asignado a los bloques colocados a partir de ahora
+    pub active_group: usize,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        World {
+            palette: Palette::new(),
+            blocks: HashMap::new(),
+            columns: HashMap::new(),
+            active_group: DEFAULT_GROUP,
+        }
+    }
+
+    // Esquinas mínima y máxima de los bloques colocados, si hay alguno
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        let mut positions = self.blocks.keys();
+        let first = *positions.next()?;
+        Some(positions.fold((first, first), |(min, max), pos| (min.inf(pos), max.sup(pos))))
+    }
+
+    pub fn get_block(&self, pos: IVec3) -> BlockId {
+        self.blocks.get(&pos).map_or(AIR, |placed| placed.id)
+    }
+
+    // Si hay un bloque que ocupa la celda entera; los finos dejan ver las caras vecinas
+    pub fn is_full_block(&self, pos: IVec3) -> bool {
+        let block = self.get_block(pos);
+        block != AIR && self.palette.shape(block).is_full()
+    }
+
+    // Altura y tipo del bloque más alto de la columna (x, z), si hay alguno
+    pub fn top_block(&self, x: i32, z: i32) -> Option<(i32, BlockId)> {
+        let y = *self.columns.get(&(x, z))?.last()?;
+        Some((y, self.get_block(IVec3::new(x, y, z))))
+    }
+
+    // Todas las escrituras pasan por aquí y por `remove_placed` para que `columns` siga
+    // al día con `blocks`
+    fn insert_placed(&mut self, pos: IVec3, placed: PlacedBlock) {
+        self.blocks.insert(pos, placed);
+        self.columns.entry((pos.x, pos.z)).or_default().insert(pos.y);
+    }
+
+    fn remove_placed(&mut self, pos: &IVec3) -> Option<PlacedBlock> {
+        let placed = self.blocks.remove(pos)?;
+        if let Some(column) = self.columns.get_mut(&(pos.x, pos.z)) {
+            column.remove(&pos.y);
+            if column.is_empty() {
+                self.columns.remove(&(pos.x, pos.z));
+            }
+        }
+        Some(placed)
+    }
+
+    // Coloca una estructura desplazada por `offset`; el aire de la estructura borra
+    pub fn stamp(&mut self, structure: &Structure, offset: IVec3) {
+        for (pos, block) in &structure.blocks {
+            self.set_block(pos + offset, *block);
+        }
+    }
+
+    // Copia los bloques de la caja [min, max] con posiciones relativas a su esquina mínima
+    pub fn extract(&self, min: IVec3, max: IVec3) -> Structure {
+        let (min, max) = (min.inf(&max), min.sup(&max));
+        let blocks = self.blocks.iter()
+            .filter(|(pos, _)| in_box(pos, &min, &max))
+            .map(|(pos, placed)| (pos - min, placed.id))
+            .collect();
+        Structure { blocks }
+    }
+
+    // Gira los bloques de la caja [min, max] alrededor del eje Y, manteniendo la
+    // esquina `min` fija; si la caja no es cuadrada cambia de ancho a fondo
+    pub fn rotate_region_y(&mut self, min: IVec3, max: IVec3, quarter_turns: i32) {
+        let (min, max) = (min.inf(&max), min.sup(&max));
+        let size = max - min;
+        // Tras girar respecto a `min`, este desplazamiento devuelve la caja a empezar en `min`
+        let correction = match quarter_turns.rem_euclid(4) {
+            1 => IVec3::new(0, 0, size.x),
+            2 => IVec3::new(size.x, 0, size.z),
+            3 => IVec3::new(size.z, 0, 0),
+            _ => IVec3::new(0, 0, 0),
+        };
+        self.transform_region(min, max, |pos| min + rotate_y(pos - min, quarter_turns) + correction);
+    }
+
+    // Refleja en su sitio los bloques de la caja [min, max] a lo largo de `axis`
+    pub fn mirror_region(&mut self, min: IVec3, max: IVec3, axis: Axis) {
+        let (min, max) = (min.inf(&max), min.sup(&max));
+        self.transform_region(min, max, |pos| {
+            let mut mirrored = pos;
+            mirrored[axis.index()] = min[axis.index()] + max[axis.index()] - pos[axis.index()];
+            mirrored
+        });
+    }
+
+    // Mueve cada bloque de la caja a `map(pos)` conservando su tipo y su grupo
+    fn transform_region(&mut self, min: IVec3, max: IVec3, map: impl Fn(IVec3) -> IVec3) {
+        let inside: Vec<IVec3> = self.blocks.keys()
+            .filter(|pos| in_box(pos, &min, &max))
+            .cloned()
+            .collect();
+        let moved: Vec<(IVec3, PlacedBlock)> = inside.iter()
+            .map(|pos| (map(*pos), self.remove_placed(pos).unwrap()))
+            .collect();
+        for (pos, placed) in moved {
+            self.insert_placed(pos, placed);
+        }
+    }
+
+    // Posiciones de los bloques en un orden fijo, para que la escena sea la misma en cada ejecución
+    pub fn positions(&self) -> Vec<IVec3> {
+        let mut positions: Vec<IVec3> = self.blocks.keys().copied().collect();
+        positions.sort_by_key(|pos| (pos.x, pos.y, pos.z));
+        positions
+    }
+
+    // Cada bloque colocado con su posición, sin orden fijo
+    pub fn placed_blocks(&self) -> impl Iterator<Item = (IVec3, PlacedBlock)> + '_ {
+        self.blocks.iter().map(|(pos, placed)| (*pos, *placed))
+    }
+}
+
+impl BlockEdit for World {
+    fn set_block(&mut self, pos: IVec3, block: BlockId) {
+        if block == AIR {
+            self.remove_placed(&pos);
+        } else {
+            self.insert_placed(pos, PlacedBlock { id: block, group: self.active_group });
+        }
+    }
+}
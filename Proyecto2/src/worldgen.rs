@@ -0,0 +1,256 @@
+use nalgebra_glm::{IVec3, Vec2};
+use std::collections::{HashSet, VecDeque};
+
+use crate::noise::{fbm_2d, fbm_3d};
+use crate::rng::Rng;
+use crate::world::{BlockEdit, BlockId, Structure, World, AIR};
+
+// Parámetros del generador de árboles
+#[derive(Debug, Clone)]
+pub struct TreeParams {
+    // Bloques de tronco antes de la copa
+    pub trunk_height: i32,
+    pub canopy_radius: i32,
+    // Probabilidad de que exista cada hoja del borde de la copa (el interior siempre se llena)
+    pub leaf_density: f32,
+}
+
+impl Default for TreeParams {
+    fn default() -> Self {
+        TreeParams {
+            trunk_height: 4,
+            canopy_radius: 2,
+            leaf_density: 0.7,
+        }
+    }
+}
+
+// Genera un árbol con la base del tronco en el origen de la estructura
+pub fn generate_tree(params: &TreeParams, log: BlockId, leaves: BlockId, rng: &mut Rng) -> Structure {
+    let mut tree = Structure::new();
+    let radius = params.canopy_radius.max(0);
+    let center = IVec3::new(0, params.trunk_height, 0);
+
+    // Copa aproximadamente esférica alrededor de la punta del tronco
+    for dx in -radius..=radius {
+        for dy in -radius..=radius {
+            for dz in -radius..=radius {
+                let distance_sq = dx * dx + dy * dy + dz * dz;
+                if distance_sq > radius * radius + 1 {
+                    continue;
+                }
+                let on_edge = distance_sq > (radius - 1) * (radius - 1);
+                if on_edge && rng.next_f32() >= params.leaf_density {
+                    continue;
+                }
+                tree.set_block(center + IVec3::new(dx, dy, dz), leaves);
+            }
+        }
+    }
+
+    // El tronco se coloca después para atravesar las hojas hasta el centro de la copa
+    tree.line(IVec3::new(0, 0, 0), center, log);
+
+    tree
+}
+
+// Configuración del generador de terreno
+#[derive(Debug, Clone)]
+pub struct WorldGenConfig {
+    pub seed: u64,
+    // Extensión horizontal del terreno (inclusiva) en X y Z; la Y de `min` es el fondo
+    pub min: IVec3,
+    pub max: IVec3,
+    // Altura media de la superficie y cuánto puede subir o bajar
+    pub surface_height: i32,
+    pub height_amplitude: f32,
+    // Tamaño de las colinas en bloques
+    pub height_scale: f32,
+    // Capas de tierra bajo la superficie antes de la piedra
+    pub soil_depth: i32,
+    // Paso de excavación de cuevas y salientes con ruido 3D
+    pub caves: bool,
+    pub cave_scale: f32,
+    // Cuanto más bajo, más huecos (el ruido está en [0, 1))
+    pub cave_threshold: f32,
+    // Nivel del mar: el aire conectado por debajo de esta altura se llena de agua
+    pub sea_level: Option<i32>,
+}
+
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        WorldGenConfig {
+            seed: 0,
+            min: IVec3::new(-5, -4, -5),
+            max: IVec3::new(4, 0, 4),
+            surface_height: 0,
+            height_amplitude: 1.5,
+            height_scale: 6.0,
+            soil_depth: 1,
+            caves: false,
+            cave_scale: 4.0,
+            cave_threshold: 0.55,
+            sea_level: None,
+        }
+    }
+}
+
+// Bloques usados por el generador de terreno
+pub struct TerrainBlocks {
+    pub surface: BlockId,
+    pub soil: BlockId,
+    pub stone: BlockId,
+    pub water: BlockId,
+}
+
+// Columnas de terreno con altura dada por ruido, seguidas del paso de cuevas si está activo
+pub fn generate_terrain(world: &mut World, config: &WorldGenConfig, blocks: &TerrainBlocks) {
+    for x in config.min.x..=config.max.x {
+        for z in config.min.z..=config.max.z {
+            let height = surface_height(config, x, z);
+            for y in config.min.y..=height {
+                let block = if y == height {
+                    blocks.surface
+                } else if y >= height - config.soil_depth {
+                    blocks.soil
+                } else {
+                    blocks.stone
+                };
+                world.set_block(IVec3::new(x, y, z), block);
+            }
+        }
+    }
+
+    if config.caves {
+        carve_caves(world, config);
+    }
+
+    if let Some(sea_level) = config.sea_level {
+        flood_water(world, config, sea_level, blocks.water);
+    }
+}
+
+pub fn surface_height(config: &WorldGenConfig, x: i32, z: i32) -> i32 {
+    let scale = config.height_scale.max(0.001);
+    let noise = fbm_2d(config.seed, x as f32 / scale, z as f32 / scale, 3);
+    config.surface_height + ((noise * 2.0 - 1.0) * config.height_amplitude).round() as i32
+}
+
+// Elimina los bloques donde el ruido 3D supera el umbral, formando cuevas y salientes.
+// La capa del fondo nunca se excava para que el terreno no quede agujereado por debajo.
+pub fn carve_caves(world: &mut World, config: &WorldGenConfig) {
+    let scale = config.cave_scale.max(0.001);
+    let cave_seed = config.seed ^ 0xC0FF_EE00_DEAD_BEEF;
+    let top = config.surface_height + config.height_amplitude.ceil() as i32;
+
+    for x in config.min.x..=config.max.x {
+        for y in (config.min.y + 1)..=top {
+            for z in config.min.z..=config.max.z {
+                let density = fbm_3d(cave_seed, x as f32 / scale, y as f32 / scale, z as f32 / scale, 2);
+                if density > config.cave_threshold {
+                    world.set_block(IVec3::new(x, y, z), AIR);
+                }
+            }
+        }
+    }
+}
+
+// Llena de agua el aire hasta `sea_level` partiendo de la superficie del mar y
+// avanzando por vecinos, así las cuevas cerradas quedan secas
+pub fn flood_water(world: &mut World, config: &WorldGenConfig, sea_level: i32, water: BlockId) {
+    let inside = |pos: &IVec3| {
+        pos.x >= config.min.x && pos.x <= config.max.x
+            && pos.z >= config.min.z && pos.z <= config.max.z
+            && pos.y >= config.min.y && pos.y <= sea_level
+    };
+
+    let mut visited = HashSet::new();
+    let mut pending = VecDeque::new();
+    for x in config.min.x..=config.max.x {
+        for z in config.min.z..=config.max.z {
+            let pos = IVec3::new(x, sea_level, z);
+            if world.get_block(pos) == AIR && visited.insert(pos) {
+                pending.push_back(pos);
+            }
+        }
+    }
+
+    let neighbours = [
+        IVec3::new(1, 0, 0), IVec3::new(-1, 0, 0),
+        IVec3::new(0, -1, 0),
+        IVec3::new(0, 0, 1), IVec3::new(0, 0, -1),
+    ];
+
+    while let Some(pos) = pending.pop_front() {
+        world.set_block(pos, water);
+        for offset in &neighbours {
+            let next = pos + offset;
+            if inside(&next) && world.get_block(next) == AIR && visited.insert(next) {
+                pending.push_back(next);
+            }
+        }
+    }
+}
+
+// Parámetros del generador de caminos
+#[derive(Debug, Clone)]
+pub struct PathParams {
+    // Ancho en bloques; si es par, la columna de más queda hacia +X y +Z
+    pub width: i32,
+    // Desviación del punto medio de la curva respecto a la línea recta, en bloques
+    // (positiva hacia la izquierda yendo de `from` a `to`)
+    pub bend: f32,
+    // Bloques de superficie sobre los que no se pone camino, por ejemplo el agua
+    pub skip: Vec<BlockId>,
+}
+
+impl Default for PathParams {
+    fn default() -> Self {
+        PathParams {
+            width: 1,
+            bend: 0.0,
+            skip: Vec::new(),
+        }
+    }
+}
+
+// Camino curvo entre dos puntos que sustituye el bloque más alto de cada columna,
+// así sigue la altura del terreno. De `from` y `to` solo se usan X y Z.
+pub fn generate_path(world: &mut World, from: IVec3, to: IVec3, params: &PathParams, block: BlockId) {
+    let start = Vec2::new(from.x as f32, from.z as f32);
+    let end = Vec2::new(to.x as f32, to.z as f32);
+    let direction = end - start;
+    let length = direction.norm();
+
+    // Curva de Bézier cuadrática con el punto de control desplazado en perpendicular
+    let perpendicular = if length > 0.0 {
+        Vec2::new(-direction.y, direction.x) / length
+    } else {
+        Vec2::new(0.0, 0.0)
+    };
+    let control = (start + end) * 0.5 + perpendicular * params.bend;
+
+    let steps = ((length + params.bend.abs()) * 2.0).ceil().max(1.0) as i32;
+    let width = params.width.max(1);
+    let (low, high) = ((width - 1) / 2, width / 2);
+    let mut columns = HashSet::new();
+
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let point = start * (1.0 - t) * (1.0 - t) + control * 2.0 * (1.0 - t) * t + end * t * t;
+        let (cx, cz) = (point.x.round() as i32, point.y.round() as i32);
+        for dx in -low..=high {
+            for dz in -low..=high {
+                columns.insert((cx + dx, cz + dz));
+            }
+        }
+    }
+
+    for (x, z) in columns {
+        if let Some((y, top)) = world.top_block(x, z) {
+            if !params.skip.contains(&top) {
+                world.set_block(IVec3::new(x, y, z), block);
+            }
+        }
+    }
+}